@@ -0,0 +1,716 @@
+// On-disk format of timelines, json with a version number, so files from older versions can still be read later
+//
+// {
+//     "version": 1,
+//     "assets": [ { "name": "hero", "kind": "atlas", "path": "hero.json" } ], // kind is texture or atlas, path relative to this file
+//     "scenes":
+//     [
+//         {
+//             "name": "intro",
+//             "duration": 2.0, // Optional, default is the last key
+//             "loop_region": [0.5, 2.0], // Optional
+//             "layers":
+//             [
+//                 {
+//                     "name": "hero",
+//                     "asset": "hero", // Optional, has to be one of the assets
+//                     "z_index": 1,
+//                     "tracks":
+//                     [
+//                         {
+//                             "property": "position", // position, rotation, scale, color, opacity or frame
+//                             "keys":
+//                             [
+//                                 { "time": 0.0, "value": [100.0, 200.0], "interpolation": "step" },
+//                                 { "time": 1.0, "value": [300.0, 200.0], "interpolation": { "bezier": [[0.42, 0.0], [0.58, 1.0]] } },
+//                                 { "time": 2.0, "value": [300.0, 400.0] } // interpolation defaults to linear
+//                             ]
+//                         }
+//                     ]
+//                 }
+//             ]
+//         }
+//     ]
+// }
+//
+// Values: position and scale [x, y], rotation (radians) and opacity a number, color [r, g, b, a], frame a whole number
+// In the Timeline of a scene every track is called "layer.property", for example "hero.position"
+
+use std::{collections::HashMap, fmt, path::{Path, PathBuf}, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{sprite::SpriteSheet, timeline::{Interpolation, Property, Timeline, Track, TrackValue}};
+
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnimationFile
+{
+    pub version: u32,
+    #[serde(default)]
+    pub assets: Vec<AssetData>,
+    #[serde(default)]
+    pub scenes: Vec<SceneData>
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind
+{
+    Texture,
+    Atlas // Aseprite or TexturePacker json, see Renderer::load_atlas
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AssetData
+{
+    pub name: String,
+    pub kind: AssetKind,
+    pub path: String
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneData
+{
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loop_region: Option<(f32, f32)>,
+    #[serde(default)]
+    pub layers: Vec<LayerData>
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayerData
+{
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+    #[serde(default)]
+    pub z_index: u32,
+    #[serde(default)]
+    pub tracks: Vec<TrackData>
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrackData
+{
+    pub property: Property,
+    pub keys: Vec<KeyData>
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyData
+{
+    pub time: f32,
+    pub value: KeyValue,
+    #[serde(default)]
+    pub interpolation: Interpolation
+}
+
+// Raw value of a key, what it means depends on the property of the track
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyValue
+{
+    Number(f32),
+    List(Vec<f32>)
+}
+
+#[derive(Debug)]
+pub enum AnimationFileError
+{
+    Io { path: PathBuf, error: std::io::Error },
+    Parse { path: PathBuf, line: usize, column: usize, message: String },
+    Version { path: PathBuf, found: u32 },
+    // location is where in the file, like scenes[0].layers[2].asset, line is the line of it if the json was there to look it up
+    MissingAsset { path: PathBuf, location: String, line: Option<usize>, asset: String },
+    Invalid { path: PathBuf, location: String, line: Option<usize>, message: String },
+    // The asset file exists, but is not an image or an atlas
+    Asset { path: PathBuf, location: String, line: Option<usize>, message: String }
+}
+
+impl fmt::Display for AnimationFileError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            AnimationFileError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            AnimationFileError::Parse { path, line, column, message } => write!(f, "{}:{}:{}: {}", path.display(), line, column, message),
+            AnimationFileError::Version { path, found } => write!(f, "{}: version {} is newer than the supported version {}", path.display(), found, FORMAT_VERSION),
+            AnimationFileError::MissingAsset { path, location, line, asset } => write!(f, "{}{}: {} uses the missing asset {}", path.display(), Line(*line), location, asset),
+            AnimationFileError::Invalid { path, location, line, message } | AnimationFileError::Asset { path, location, line, message } => write!(f, "{}{}: {}: {}", path.display(), Line(*line), location, message)
+        }
+    }
+}
+
+// ":line" after the path, nothing if it is not known
+struct Line(Option<usize>);
+
+impl fmt::Display for Line
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self.0
+        {
+            Some(line) => write!(f, ":{}", line),
+            None => Ok(())
+        }
+    }
+}
+
+impl AnimationFileError
+{
+    // Looks up the line of the location in the json of the file
+    pub fn with_line(mut self, json: &str) -> Self
+    {
+        if let AnimationFileError::MissingAsset { location, line, .. } | AnimationFileError::Invalid { location, line, .. } | AnimationFileError::Asset { location, line, .. } = &mut self
+        {
+            *line = line.or_else(|| line_of(json, location));
+        }
+        self
+    }
+}
+
+impl std::error::Error for AnimationFileError {}
+
+impl Default for AnimationFile
+{
+    fn default() -> Self
+    {
+        Self { version: FORMAT_VERSION, assets: Vec::new(), scenes: Vec::new() }
+    }
+}
+
+impl AnimationFile
+{
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AnimationFileError>
+    {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|error| AnimationFileError::Io { path: path.to_path_buf(), error })?;
+        Self::parse(&json, path)
+    }
+
+    // path is only used for the errors
+    pub fn parse(json: &str, path: impl AsRef<Path>) -> Result<Self, AnimationFileError>
+    {
+        let path = path.as_ref();
+        let file: AnimationFile = serde_json::from_str(json).map_err(|error| AnimationFileError::Parse
+        {
+            path: path.to_path_buf(),
+            line: error.line(),
+            column: error.column(),
+            message: error.to_string()
+        })?;
+
+        file.validate(path).map_err(|error| error.with_line(json))?;
+        Ok(file)
+    }
+
+    pub fn to_json(&self) -> String
+    {
+        serde_json::to_string_pretty(self).expect("Animation files always serialize")
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AnimationFileError>
+    {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_json()).map_err(|error| AnimationFileError::Io { path: path.to_path_buf(), error })
+    }
+
+    // Checks everything the types can not, the first problem gets returned
+    pub fn validate(&self, path: &Path) -> Result<(), AnimationFileError>
+    {
+        let invalid = |location: String, message: String| AnimationFileError::Invalid { path: path.to_path_buf(), location, line: None, message };
+
+        if self.version > FORMAT_VERSION
+        {
+            return Err(AnimationFileError::Version { path: path.to_path_buf(), found: self.version });
+        }
+
+        for (a, asset) in self.assets.iter().enumerate()
+        {
+            if self.assets[..a].iter().any(|other| other.name == asset.name)
+            {
+                return Err(invalid(format!("assets[{}].name", a), format!("there already is an asset called {}", asset.name)));
+            }
+        }
+
+        for (s, scene) in self.scenes.iter().enumerate()
+        {
+            if self.scenes[..s].iter().any(|other| other.name == scene.name)
+            {
+                return Err(invalid(format!("scenes[{}].name", s), format!("there already is a scene called {}", scene.name)));
+            }
+            if let Some(duration) = scene.duration && !(duration.is_finite() && duration >= 0.0)
+            {
+                return Err(invalid(format!("scenes[{}].duration", s), "has to be 0 or more".to_string()));
+            }
+            if let Some((start, end)) = scene.loop_region && !(start.is_finite() && end.is_finite())
+            {
+                return Err(invalid(format!("scenes[{}].loop_region", s), "the start and the end have to be numbers".to_string()));
+            }
+            if let Some((start, end)) = scene.loop_region && start >= end
+            {
+                return Err(invalid(format!("scenes[{}].loop_region", s), "the start has to be before the end".to_string()));
+            }
+
+            for (l, layer) in scene.layers.iter().enumerate()
+            {
+                let location = format!("scenes[{}].layers[{}]", s, l);
+
+                if scene.layers[..l].iter().any(|other| other.name == layer.name)
+                {
+                    return Err(invalid(format!("{}.name", location), format!("there already is a layer called {}", layer.name)));
+                }
+                if let Some(asset) = &layer.asset && !self.assets.iter().any(|other| &other.name == asset)
+                {
+                    return Err(AnimationFileError::MissingAsset { path: path.to_path_buf(), location: format!("{}.asset", location), line: None, asset: asset.clone() });
+                }
+
+                for (t, track) in layer.tracks.iter().enumerate()
+                {
+                    let location = format!("{}.tracks[{}]", location, t);
+
+                    if layer.tracks[..t].iter().any(|other| other.property == track.property)
+                    {
+                        return Err(invalid(format!("{}.property", location), format!("the layer already has a {} track", track.property.name())));
+                    }
+
+                    for (k, key) in track.keys.iter().enumerate()
+                    {
+                        let location = format!("{}.keys[{}]", location, k);
+
+                        if !key.time.is_finite() || key.time < 0.0
+                        {
+                            return Err(invalid(format!("{}.time", location), "has to be 0 or more".to_string()));
+                        }
+                        if track.keys[..k].iter().any(|other| other.time == key.time)
+                        {
+                            return Err(invalid(format!("{}.time", location), format!("there already is a key at {}", key.time)));
+                        }
+                        key.value.to_track_value(track.property).map_err(|message| invalid(format!("{}.value", location), message))?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Every asset file has to exist, paths are relative to the animation file at path
+    pub fn validate_asset_files(&self, path: &Path) -> Result<(), AnimationFileError>
+    {
+        for (a, asset) in self.assets.iter().enumerate()
+        {
+            let asset_path = Self::asset_path(path, asset);
+            if !asset_path.exists()
+            {
+                return Err(AnimationFileError::Invalid { path: path.to_path_buf(), location: format!("assets[{}].path", a), line: None, message: format!("{} does not exist", asset_path.display()) });
+            }
+        }
+        Ok(())
+    }
+
+    // Every frame key of a layer with an atlas has to be one of its frames, frame_count is the number of frames of the asset with that name, None if it is no atlas
+    pub fn validate_frames(&self, path: &Path, frame_count: impl Fn(&str) -> Option<usize>) -> Result<(), AnimationFileError>
+    {
+        for (s, scene) in self.scenes.iter().enumerate()
+        {
+            for (l, layer) in scene.layers.iter().enumerate()
+            {
+                let Some(count) = layer.asset.as_deref().and_then(&frame_count) else { continue };
+
+                for (t, track) in layer.tracks.iter().enumerate().filter(|(_, track)| track.property == Property::Frame)
+                {
+                    for (k, key) in track.keys.iter().enumerate()
+                    {
+                        if let Ok(TrackValue::Frame(frame)) = key.value.to_track_value(Property::Frame) && frame >= count
+                        {
+                            return Err(AnimationFileError::Invalid
+                            {
+                                path: path.to_path_buf(),
+                                location: format!("scenes[{}].layers[{}].tracks[{}].keys[{}].value", s, l, t, k),
+                                line: None,
+                                message: format!("frame {} does not exist, {} has {} frames", frame, layer.asset.as_deref().unwrap_or_default(), count)
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn asset_path(path: &Path, asset: &AssetData) -> PathBuf
+    {
+        path.parent().unwrap_or(Path::new("")).join(&asset.path)
+    }
+
+    pub fn scene(&self, name: &str) -> Option<&SceneData>
+    {
+        self.scenes.iter().find(|scene| scene.name == name)
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&AssetData>
+    {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+
+    // Timeline of the scene, paused at 0, None if there is no scene with that name
+    // Only for validated files, invalid values get skipped
+    pub fn timeline(&self, scene: &str) -> Option<Timeline>
+    {
+        let scene = self.scene(scene)?;
+
+        let mut timeline = Timeline::new();
+        for layer in &scene.layers
+        {
+            for track_data in &layer.tracks
+            {
+                let mut track = Track::new(&format!("{}.{}", layer.name, track_data.property.name()), track_data.property);
+                for key in &track_data.keys
+                {
+                    if let Ok(value) = key.value.to_track_value(track_data.property)
+                    {
+                        track.add_key(key.time, value, key.interpolation);
+                    }
+                }
+                timeline.add_track(track);
+            }
+        }
+        timeline.set_duration(scene.duration);
+        timeline.loop_region = scene.loop_region;
+        Some(timeline)
+    }
+}
+
+// 1 based line of a location like scenes[0].layers[2].asset in the json, None if it is not there
+fn line_of(json: &str, location: &str) -> Option<usize>
+{
+    let bytes = json.as_bytes();
+    let mut position = skip_whitespace(bytes, 0);
+
+    let parts: Vec<&str> = location.split('.').collect();
+    for (p, part) in parts.iter().enumerate()
+    {
+        let (key, indices) = part.split_once('[').map_or((*part, ""), |(key, rest)| (key, rest));
+
+        // The value of the key in the object at position
+        if bytes.get(position) != Some(&b'{')
+        {
+            return None;
+        }
+        position = skip_whitespace(bytes, position + 1);
+        loop
+        {
+            let name_end = skip_string(bytes, position)?;
+            let name = json.get(position + 1..name_end - 1)?;
+            let value = skip_whitespace(bytes, skip_whitespace(bytes, name_end) + 1);
+            if name == key
+            {
+                // Points at the key, that is where people look
+                if indices.is_empty() && p + 1 == parts.len()
+                {
+                    return Some(json[..position].matches('\n').count() + 1);
+                }
+                position = value;
+                break;
+            }
+            position = skip_whitespace(bytes, skip_value(bytes, value)?);
+            if bytes.get(position) != Some(&b',')
+            {
+                return None;
+            }
+            position = skip_whitespace(bytes, position + 1);
+        }
+
+        // Element of the array at position for every [index]
+        for index in indices.split('[').filter(|index| !index.is_empty())
+        {
+            let index: usize = index.trim_end_matches(']').parse().ok()?;
+            if bytes.get(position) != Some(&b'[')
+            {
+                return None;
+            }
+            position = skip_whitespace(bytes, position + 1);
+            for _ in 0..index
+            {
+                position = skip_whitespace(bytes, skip_value(bytes, position)?);
+                if bytes.get(position) != Some(&b',')
+                {
+                    return None;
+                }
+                position = skip_whitespace(bytes, position + 1);
+            }
+        }
+    }
+
+    Some(json[..position].matches('\n').count() + 1)
+}
+
+fn skip_whitespace(bytes: &[u8], mut position: usize) -> usize
+{
+    while bytes.get(position).is_some_and(u8::is_ascii_whitespace)
+    {
+        position += 1;
+    }
+    position
+}
+
+// End of the string starting at position, after the closing quote
+fn skip_string(bytes: &[u8], position: usize) -> Option<usize>
+{
+    if bytes.get(position) != Some(&b'"')
+    {
+        return None;
+    }
+    let mut position = position + 1;
+    loop
+    {
+        match bytes.get(position)?
+        {
+            b'\\' => position += 2,
+            b'"' => return Some(position + 1),
+            _ => position += 1
+        }
+    }
+}
+
+// End of the value starting at position
+fn skip_value(bytes: &[u8], position: usize) -> Option<usize>
+{
+    match bytes.get(position)?
+    {
+        b'"' => skip_string(bytes, position),
+        b'{' | b'[' =>
+        {
+            let mut depth = 0;
+            let mut position = position;
+            loop
+            {
+                match bytes.get(position)?
+                {
+                    b'"' => { position = skip_string(bytes, position)?; continue; }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' =>
+                    {
+                        depth -= 1;
+                        if depth == 0
+                        {
+                            return Some(position + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                position += 1;
+            }
+        }
+        _ =>
+        {
+            let mut position = position;
+            while bytes.get(position).is_some_and(|byte| !matches!(byte, b',' | b'}' | b']') && !byte.is_ascii_whitespace())
+            {
+                position += 1;
+            }
+            Some(position)
+        }
+    }
+}
+
+impl SceneData
+{
+    // For tools that edit a Timeline, tracks have to be called "layer.property" like the ones from AnimationFile::timeline
+    // Layers get created in the order of their first track, without an asset
+    pub fn from_timeline(name: &str, timeline: &Timeline) -> Self
+    {
+        let mut layers: Vec<LayerData> = Vec::new();
+        for track in &timeline.tracks
+        {
+            let layer_name = track.name.rsplit_once('.').map_or(track.name.as_str(), |(layer, _)| layer);
+            let keys = track.keys().iter().map(|key| KeyData { time: key.time, value: KeyValue::from(key.value), interpolation: key.interpolation }).collect();
+            let track = TrackData { property: track.property, keys };
+
+            match layers.iter_mut().find(|layer| layer.name == layer_name)
+            {
+                Some(layer) => layer.tracks.push(track),
+                None => layers.push(LayerData { name: layer_name.to_string(), asset: None, z_index: 0, tracks: vec![track] })
+            }
+        }
+
+        Self { name: name.to_string(), duration: timeline.explicit_duration(), loop_region: timeline.loop_region, layers }
+    }
+}
+
+impl KeyValue
+{
+    pub fn to_track_value(&self, property: Property) -> Result<TrackValue, String>
+    {
+        match (property, self)
+        {
+            (Property::Position | Property::Scale, KeyValue::List(list)) if list.len() == 2 => Ok(TrackValue::Vec2(list[0], list[1])),
+            (Property::Position | Property::Scale, _) => Err(format!("{} needs [x, y]", property.name())),
+            (Property::Rotation | Property::Opacity, KeyValue::Number(value)) => Ok(TrackValue::Float(*value)),
+            (Property::Rotation | Property::Opacity, _) => Err(format!("{} needs a number", property.name())),
+            (Property::Color, KeyValue::List(list)) if list.len() == 4 => Ok(TrackValue::Color([list[0], list[1], list[2], list[3]])),
+            (Property::Color, _) => Err("color needs [r, g, b, a]".to_string()),
+            (Property::Frame, KeyValue::Number(frame)) if *frame >= 0.0 && frame.fract() == 0.0 => Ok(TrackValue::Frame(*frame as usize)),
+            (Property::Frame, _) => Err("frame needs a whole number of 0 or more".to_string())
+        }
+    }
+}
+
+impl From<TrackValue> for KeyValue
+{
+    fn from(value: TrackValue) -> Self
+    {
+        match value
+        {
+            TrackValue::Float(value) => KeyValue::Number(value),
+            TrackValue::Vec2(x, y) => KeyValue::List(vec![x, y]),
+            TrackValue::Color(color) => KeyValue::List(color.to_vec()),
+            TrackValue::Frame(frame) => KeyValue::Number(frame as f32)
+        }
+    }
+}
+
+// Animation file with its assets loaded, see Renderer::load_animation
+pub struct Animation
+{
+    pub file: AnimationFile,
+    pub textures: HashMap<String, usize>, // Texture assets by name
+    pub sheets: HashMap<String, Arc<SpriteSheet>> // Atlas assets by name
+}
+
+impl Animation
+{
+    pub fn timeline(&self, scene: &str) -> Option<Timeline>
+    {
+        self.file.timeline(scene)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const FILE: &str = r#"{
+        "version": 1,
+        "assets": [{ "name": "hero", "kind": "atlas", "path": "hero.json" }],
+        "scenes": [{
+            "name": "intro",
+            "duration": 3.0,
+            "loop_region": [0.5, 2.0],
+            "layers": [{
+                "name": "hero",
+                "asset": "hero",
+                "tracks": [
+                    { "property": "position", "keys": [
+                        { "time": 0.0, "value": [100.0, 200.0], "interpolation": "step" },
+                        { "time": 1.0, "value": [300.0, 200.0], "interpolation": { "bezier": [[0.42, 0.0], [0.58, 1.0]] } },
+                        { "time": 2.0, "value": [300.0, 400.0] }
+                    ] },
+                    { "property": "frame", "keys": [{ "time": 0.0, "value": 3 }] }
+                ]
+            }]
+        }]
+    }"#;
+
+    fn location(json: &str) -> String
+    {
+        match AnimationFile::parse(json, "test.json")
+        {
+            Err(AnimationFileError::Invalid { location, .. }) | Err(AnimationFileError::MissingAsset { location, .. }) => location,
+            other => panic!("Expected a validation error, got {:?}", other.map(|_| ()))
+        }
+    }
+
+    #[test]
+    fn scenes_become_timelines()
+    {
+        let file = AnimationFile::parse(FILE, "test.json").unwrap();
+        let timeline = file.timeline("intro").unwrap();
+        assert_eq!(timeline.duration(), 3.0);
+        assert_eq!(timeline.loop_region, Some((0.5, 2.0)));
+        assert_eq!(timeline.track("hero.frame").unwrap().sample(0.0), Some(TrackValue::Frame(3)));
+        assert_eq!(timeline.track("hero.position").unwrap().sample(0.5), Some(TrackValue::Vec2(100.0, 200.0)));
+        assert!(file.timeline("outro").is_none());
+    }
+
+    #[test]
+    fn timelines_round_trip_with_their_duration()
+    {
+        let file = AnimationFile::parse(FILE, "test.json").unwrap();
+        let timeline = file.timeline("intro").unwrap();
+
+        let mut scene = SceneData::from_timeline("intro", &timeline);
+        assert_eq!(scene.duration, Some(3.0));
+        scene.layers[0].asset = Some("hero".to_string());
+
+        let saved = AnimationFile { scenes: vec![scene], ..file.clone() };
+        assert_eq!(AnimationFile::parse(&saved.to_json(), "test.json").unwrap(), file);
+    }
+
+    #[test]
+    fn timelines_without_a_duration_stay_without_one()
+    {
+        let file = AnimationFile::parse(&FILE.replace("\"duration\": 3.0,", ""), "test.json").unwrap();
+        let scene = SceneData::from_timeline("intro", &file.timeline("intro").unwrap());
+        assert_eq!(scene.duration, None);
+        assert!(!AnimationFile { scenes: vec![scene], ..file }.to_json().contains("duration"));
+    }
+
+    #[test]
+    fn invalid_files_report_where_the_problem_is()
+    {
+        assert_eq!(location(&FILE.replace("\"duration\": 3.0", "\"duration\": -1.0")), "scenes[0].duration");
+        assert_eq!(location(&FILE.replace("[0.5, 2.0]", "[2.0, 0.5]")), "scenes[0].loop_region");
+        assert_eq!(location(&FILE.replace("\"asset\": \"hero\"", "\"asset\": \"villain\"")), "scenes[0].layers[0].asset");
+        assert_eq!(location(&FILE.replace("\"time\": 2.0", "\"time\": 1.0")), "scenes[0].layers[0].tracks[0].keys[2].time");
+        assert_eq!(location(&FILE.replace("\"value\": 3", "\"value\": 1.5")), "scenes[0].layers[0].tracks[1].keys[0].value");
+        assert!(matches!(AnimationFile::parse(&FILE.replace("\"version\": 1", "\"version\": 2"), "test.json"), Err(AnimationFileError::Version { found: 2, .. })));
+        assert!(matches!(AnimationFile::parse("{ \"version\": 1, ", "test.json"), Err(AnimationFileError::Parse { line: 1, .. })));
+    }
+
+    #[test]
+    fn validation_errors_have_the_line()
+    {
+        let error = AnimationFile::parse(&FILE.replace("\"duration\": 3.0", "\"duration\": -1.0"), "test.json").unwrap_err();
+        assert_eq!(error.to_string(), "test.json:6: scenes[0].duration: has to be 0 or more");
+
+        let error = AnimationFile::parse(&FILE.replace("\"value\": 3", "\"value\": 1.5"), "test.json").unwrap_err();
+        assert!(matches!(error, AnimationFileError::Invalid { line: Some(17), .. }));
+
+        assert_eq!(line_of(FILE, "scenes[0].layers[0].tracks[0].keys[2].time"), Some(15));
+        assert_eq!(line_of(FILE, "assets[0].path"), Some(3));
+        assert_eq!(line_of(FILE, "scenes[1].name"), None);
+    }
+
+    #[test]
+    fn frames_have_to_be_in_the_atlas()
+    {
+        let file = AnimationFile::parse(FILE, "test.json").unwrap();
+        assert!(file.validate_frames(Path::new("test.json"), |_| Some(4)).is_ok());
+        // Textures have no frames to check
+        assert!(file.validate_frames(Path::new("test.json"), |_| None).is_ok());
+
+        let error = file.validate_frames(Path::new("test.json"), |_| Some(3)).unwrap_err().with_line(FILE);
+        assert_eq!(error.to_string(), "test.json:17: scenes[0].layers[0].tracks[1].keys[0].value: frame 3 does not exist, hero has 3 frames");
+    }
+
+    #[test]
+    fn values_json_can_not_hold_are_rejected()
+    {
+        let mut file = AnimationFile::parse(FILE, "test.json").unwrap();
+        file.scenes[0].duration = Some(f32::NAN);
+        assert!(file.validate(Path::new("test.json")).is_err());
+
+        file.scenes[0].duration = None;
+        file.scenes[0].loop_region = Some((f32::NAN, 1.0));
+        assert!(file.validate(Path::new("test.json")).is_err());
+    }
+}
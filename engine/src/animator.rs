@@ -0,0 +1,563 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{skeleton::{Skeleton, SkeletonClip}, sprite::{AnimatedSprite, AnimationClip, PlayMode}, timeline::{Timeline, TrackValue}, tween::Lerp};
+
+// What a state plays, clips are only names with a length, so the same controller can drive sprites, skeletons and timelines
+#[derive(Clone, Debug, PartialEq)]
+pub enum Motion
+{
+    Clip { name: String, duration: f32, looping: bool },
+    // 1D blend tree, children sorted by threshold, the parameter blends between the two closest ones
+    // Children play in sync, so a walk and a run with different lengths keep their steps together
+    Blend { parameter: String, children: Vec<(f32, Motion)> }
+}
+
+impl Motion
+{
+    pub fn clip(name: &str, duration: f32, looping: bool) -> Self
+    {
+        Motion::Clip { name: name.to_string(), duration, looping }
+    }
+
+    // Clip of the sprite sheet of an AnimatedSprite
+    pub fn sprite(name: &str, clip: &AnimationClip) -> Self
+    {
+        Self::clip(name, clip.duration(), clip.mode != PlayMode::Once)
+    }
+
+    pub fn skeleton(name: &str, clip: &SkeletonClip) -> Self
+    {
+        Self::clip(name, clip.duration, clip.looping)
+    }
+
+    // Loops if the timeline has a loop region
+    pub fn timeline(name: &str, timeline: &Timeline) -> Self
+    {
+        Self::clip(name, timeline.duration(), timeline.loop_region.is_some())
+    }
+
+    pub fn blend(parameter: &str, mut children: Vec<(f32, Motion)>) -> Self
+    {
+        children.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Motion::Blend { parameter: parameter.to_string(), children }
+    }
+
+    // Length of one pass, blend trees mix the lengths of their children
+    pub fn duration(&self, floats: &HashMap<String, f32>) -> f32
+    {
+        match self
+        {
+            Motion::Clip { duration, .. } => *duration,
+            Motion::Blend { parameter, children } => blend_weights(children, floats.get(parameter).copied().unwrap_or(0.0)).iter().map(|(child, weight)| children[*child].1.duration(floats) * weight).sum()
+        }
+    }
+
+    // phase is the number of passes played, 1.5 = halfway through the second pass
+    fn collect(&self, phase: f32, weight: f32, floats: &HashMap<String, f32>, samples: &mut Vec<MotionSample>)
+    {
+        match self
+        {
+            Motion::Clip { name, duration, looping } =>
+            {
+                let progress = if *looping { phase.rem_euclid(1.0) } else { phase.clamp(0.0, 1.0) };
+                samples.push(MotionSample { clip: name.clone(), time: progress * duration, weight });
+            }
+            Motion::Blend { parameter, children } =>
+            {
+                for (child, child_weight) in blend_weights(children, floats.get(parameter).copied().unwrap_or(0.0))
+                {
+                    children[child].1.collect(phase, weight * child_weight, floats, samples);
+                }
+            }
+        }
+    }
+}
+
+// Children with a weight above 0, outside of the thresholds the closest child plays alone
+fn blend_weights(children: &[(f32, Motion)], value: f32) -> Vec<(usize, f32)>
+{
+    let next = children.partition_point(|(threshold, _)| *threshold <= value);
+    if next == 0
+    {
+        return if children.is_empty() { Vec::new() } else { vec![(0, 1.0)] };
+    }
+    if next == children.len()
+    {
+        return vec![(next - 1, 1.0)];
+    }
+
+    let (low, high) = (children[next - 1].0, children[next].0);
+    let t = (value - low) / (high - low);
+    vec![(next - 1, 1.0 - t), (next, t)]
+}
+
+// Clip to play at time with weight, the weights of all samples add up to 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct MotionSample
+{
+    pub clip: String,
+    pub time: f32, // Seconds into the clip
+    pub weight: f32
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition
+{
+    Greater(String, f32), // Float parameter
+    Less(String, f32),
+    Bool(String, bool),
+    Trigger(String) // Reset when the transition is taken
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition
+{
+    pub from: Option<usize>, // None = from any other state
+    pub to: usize,
+    pub conditions: Vec<Condition>, // All of them have to be true
+    pub duration: f32, // Crossfade in seconds, 0 = switches right away
+    pub exit_time: Option<f32> // Only taken after this many passes through the from state, 1.0 = once it played through
+}
+
+impl Transition
+{
+    pub fn new(from: usize, to: usize) -> Self
+    {
+        Self { from: Some(from), to, conditions: Vec::new(), duration: 0.0, exit_time: None }
+    }
+
+    // Jump, hit and death states are usually reachable from everywhere
+    pub fn from_any(to: usize) -> Self
+    {
+        Self { from: None, ..Self::new(0, to) }
+    }
+
+    pub fn with_condition(mut self, condition: Condition) -> Self
+    {
+        self.conditions.push(condition);
+        self
+    }
+
+    pub fn with_duration(mut self, duration: f32) -> Self
+    {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_exit_time(mut self, exit_time: f32) -> Self
+    {
+        self.exit_time = Some(exit_time);
+        self
+    }
+}
+
+struct State
+{
+    name: String,
+    motion: Motion,
+    speed: f32
+}
+
+#[derive(Copy, Clone)]
+struct Playing
+{
+    state: usize,
+    phase: f32 // Passes through the motion, see Motion::collect
+}
+
+// Animation state machine, owned by the game and updated with the dt of EngineEvent::update
+// The game sets parameters from its logic, the controller picks the states and crossfades between them
+// The first state added is where it starts
+pub struct AnimationController
+{
+    states: Vec<State>,
+    transitions: Vec<Transition>, // Checked in the order they were added
+    floats: HashMap<String, f32>,
+    bools: HashMap<String, bool>,
+    triggers: HashSet<String>,
+    current: Playing,
+    previous: Option<Playing>, // Fading out
+    fade: (f32, f32) // Elapsed and duration of the crossfade
+}
+
+impl Default for AnimationController
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl AnimationController
+{
+    pub fn new() -> Self
+    {
+        Self
+        {
+            states: Vec::new(),
+            transitions: Vec::new(),
+            floats: HashMap::new(),
+            bools: HashMap::new(),
+            triggers: HashSet::new(),
+            current: Playing { state: 0, phase: 0.0 },
+            previous: None,
+            fade: (0.0, 0.0)
+        }
+    }
+
+    pub fn add_state(&mut self, name: &str, motion: Motion) -> usize
+    {
+        self.states.push(State { name: name.to_string(), motion, speed: 1.0 });
+        self.states.len() - 1
+    }
+
+    // 1.0 = normal speed
+    pub fn set_state_speed(&mut self, state: usize, speed: f32)
+    {
+        self.states[state].speed = speed;
+    }
+
+    pub fn add_transition(&mut self, transition: Transition)
+    {
+        self.transitions.push(transition);
+    }
+
+    pub fn with_transition(mut self, transition: Transition) -> Self
+    {
+        self.add_transition(transition);
+        self
+    }
+
+    pub fn state_id(&self, name: &str) -> Option<usize>
+    {
+        self.states.iter().position(|state| state.name == name)
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f32)
+    {
+        self.floats.insert(name.to_string(), value);
+    }
+
+    pub fn float(&self, name: &str) -> f32
+    {
+        self.floats.get(name).copied().unwrap_or(0.0)
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool)
+    {
+        self.bools.insert(name.to_string(), value);
+    }
+
+    pub fn bool(&self, name: &str) -> bool
+    {
+        self.bools.get(name).copied().unwrap_or(false)
+    }
+
+    // Stays set until a transition uses it or it gets reset
+    pub fn set_trigger(&mut self, name: &str)
+    {
+        self.triggers.insert(name.to_string());
+    }
+
+    pub fn reset_trigger(&mut self, name: &str)
+    {
+        self.triggers.remove(name);
+    }
+
+    // Switches right away, without a transition
+    pub fn play(&mut self, state: usize)
+    {
+        self.crossfade(state, 0.0);
+    }
+
+    pub fn crossfade(&mut self, state: usize, duration: f32)
+    {
+        self.previous = if duration > 0.0 { Some(self.current) } else { None };
+        self.current = Playing { state, phase: 0.0 };
+        self.fade = (0.0, duration);
+    }
+
+    pub fn current_state(&self) -> &str
+    {
+        &self.states[self.current.state].name
+    }
+
+    pub fn current_state_id(&self) -> usize
+    {
+        self.current.state
+    }
+
+    // The state that is fading out
+    pub fn previous_state(&self) -> Option<&str>
+    {
+        self.previous.map(|previous| self.states[previous.state].name.as_str())
+    }
+
+    // Passes through the current state, 0.5 = halfway through the first one
+    pub fn state_time(&self) -> f32
+    {
+        self.current.phase
+    }
+
+    pub fn is_transitioning(&self) -> bool
+    {
+        self.previous.is_some()
+    }
+
+    // 0..1, 1 when there is no transition
+    pub fn transition_progress(&self) -> f32
+    {
+        if self.previous.is_some() { self.fade.0 / self.fade.1 } else { 1.0 }
+    }
+
+    pub fn update(&mut self, dt: f64)
+    {
+        let dt = dt as f32;
+        self.current.phase += self.advance(self.current.state, dt);
+        if let Some(previous) = self.previous
+        {
+            let phase = previous.phase + self.advance(previous.state, dt);
+            self.fade.0 += dt;
+            self.previous = if self.fade.0 >= self.fade.1 { None } else { Some(Playing { phase, ..previous }) };
+        }
+
+        // At most one transition per update, a chain plays one step each frame
+        let transition = self.transitions.iter().find(|transition| self.can_take(transition)).cloned();
+        if let Some(transition) = transition
+        {
+            for condition in &transition.conditions
+            {
+                if let Condition::Trigger(name) = condition
+                {
+                    self.triggers.remove(name);
+                }
+            }
+            self.crossfade(transition.to, transition.duration);
+        }
+    }
+
+    // Clips that play right now with their times and weights, the fading out state comes first
+    pub fn samples(&self) -> Vec<MotionSample>
+    {
+        let mut samples = Vec::new();
+        let t = self.transition_progress();
+        if let Some(previous) = self.previous
+        {
+            self.states[previous.state].motion.collect(previous.phase, 1.0 - t, &self.floats, &mut samples);
+        }
+        self.states[self.current.state].motion.collect(self.current.phase, t, &self.floats, &mut samples);
+        samples.retain(|sample| sample.weight > 0.0);
+        samples
+    }
+
+    // Poses the skeleton with the blended clips and updates its world matrices, clips by the names of the motions
+    pub fn apply_skeleton(&self, skeleton: &mut Skeleton, clips: &HashMap<String, SkeletonClip>)
+    {
+        skeleton.reset_pose();
+
+        // Every clip gets its share of what was applied so far, which ends up as the weighted average
+        let mut total = 0.0;
+        for sample in self.samples()
+        {
+            let clip = clips.get(&sample.clip).unwrap_or_else(|| panic!("No skeleton clip named {}", sample.clip));
+            total += sample.weight;
+            skeleton.apply(clip, sample.time, sample.weight / total);
+        }
+        skeleton.update_world();
+    }
+
+    // Frames can not be blended, so the sprite plays the clip with the most weight
+    // The sprite keeps its own time, it still needs its update for frames and events
+    pub fn apply_sprite(&self, sprite: &mut AnimatedSprite)
+    {
+        if let Some(sample) = self.samples().into_iter().max_by(|a, b| a.weight.total_cmp(&b.weight))
+        {
+            sprite.play(&sample.clip);
+        }
+    }
+
+    // Blended values of every track of the timelines, timelines by the names of the motions, tracks by their names
+    pub fn sample_timelines(&self, timelines: &HashMap<String, Timeline>) -> Vec<(String, TrackValue)>
+    {
+        let mut values: Vec<(String, TrackValue, f32)> = Vec::new();
+        for sample in self.samples()
+        {
+            let timeline = timelines.get(&sample.clip).unwrap_or_else(|| panic!("No timeline named {}", sample.clip));
+            for track in &timeline.tracks
+            {
+                let Some(value) = track.sample(sample.time) else { continue };
+                match values.iter_mut().find(|(name, _, _)| *name == track.name)
+                {
+                    Some((_, blended, total)) =>
+                    {
+                        *total += sample.weight;
+                        *blended = blended.lerp(&value, sample.weight / *total);
+                    }
+                    None => values.push((track.name.clone(), value, sample.weight))
+                }
+            }
+        }
+        values.into_iter().map(|(name, value, _)| (name, value)).collect()
+    }
+
+    fn advance(&self, state: usize, dt: f32) -> f32
+    {
+        let state = &self.states[state];
+        let duration = state.motion.duration(&self.floats);
+        if duration > 0.0 { dt * state.speed / duration } else { 0.0 }
+    }
+
+    fn can_take(&self, transition: &Transition) -> bool
+    {
+        let from = match transition.from
+        {
+            Some(from) => from == self.current.state,
+            None => transition.to != self.current.state
+        };
+
+        from && transition.exit_time.is_none_or(|exit_time| self.current.phase >= exit_time) && transition.conditions.iter().all(|condition| match condition
+        {
+            Condition::Greater(name, value) => self.float(name) > *value,
+            Condition::Less(name, value) => self.float(name) < *value,
+            Condition::Bool(name, value) => self.bool(name) == *value,
+            Condition::Trigger(name) => self.triggers.contains(name)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // idle <-> move by speed, jump from anywhere with a trigger, back to idle once it played through
+    fn controller() -> AnimationController
+    {
+        let mut controller = AnimationController::new();
+        let idle = controller.add_state("idle", Motion::clip("idle", 1.0, true));
+        let walk = controller.add_state("move", Motion::blend("speed", vec![(3.0, Motion::clip("run", 0.5, true)), (1.0, Motion::clip("walk", 1.0, true))]));
+        let jump = controller.add_state("jump", Motion::clip("jump", 0.5, false));
+
+        controller
+            .with_transition(Transition::new(idle, walk).with_condition(Condition::Greater("speed".to_string(), 0.5)).with_duration(0.25))
+            .with_transition(Transition::new(walk, idle).with_condition(Condition::Less("speed".to_string(), 0.5)))
+            .with_transition(Transition::from_any(jump).with_condition(Condition::Trigger("jump".to_string())))
+            .with_transition(Transition::new(jump, idle).with_exit_time(1.0))
+    }
+
+    fn weights(controller: &AnimationController) -> Vec<(String, f32)>
+    {
+        controller.samples().into_iter().map(|sample| (sample.clip, sample.weight)).collect()
+    }
+
+    #[test]
+    fn transitions_need_all_their_conditions()
+    {
+        let mut controller = controller();
+        controller.update(0.125);
+        assert_eq!(controller.current_state(), "idle");
+
+        controller.set_float("speed", 1.0);
+        controller.update(0.125);
+        assert_eq!(controller.current_state(), "move");
+        assert_eq!(controller.previous_state(), Some("idle"));
+
+        // No duration, switches right away
+        controller.set_float("speed", 0.0);
+        controller.update(0.125);
+        assert_eq!(controller.current_state(), "idle");
+        assert!(!controller.is_transitioning());
+    }
+
+    #[test]
+    fn triggers_are_used_up_by_the_transition()
+    {
+        let mut controller = controller();
+        controller.set_trigger("jump");
+        controller.reset_trigger("jump");
+        controller.update(0.125);
+        assert_eq!(controller.current_state(), "idle");
+
+        controller.set_trigger("jump");
+        controller.update(0.125);
+        assert_eq!(controller.current_state(), "jump");
+
+        controller.play(0);
+        controller.update(0.125);
+        assert_eq!(controller.current_state(), "idle");
+    }
+
+    #[test]
+    fn exit_time_waits_for_the_state_to_play_through()
+    {
+        let mut controller = controller();
+        controller.set_trigger("jump");
+        controller.update(0.0);
+
+        controller.update(0.25);
+        assert_eq!(controller.current_state(), "jump");
+        assert_eq!(controller.state_time(), 0.5);
+
+        controller.update(0.25);
+        assert_eq!(controller.current_state(), "idle");
+    }
+
+    #[test]
+    fn from_any_does_not_restart_the_current_state()
+    {
+        let mut controller = controller();
+        controller.set_trigger("jump");
+        controller.update(0.0);
+
+        // Stays set, there is no transition that uses it
+        controller.set_trigger("jump");
+        controller.update(0.125);
+        assert_eq!(controller.current_state(), "jump");
+        assert_eq!(controller.state_time(), 0.25);
+        assert!(controller.triggers.contains("jump"));
+    }
+
+    #[test]
+    fn crossfades_split_the_weight_between_the_states()
+    {
+        let mut controller = controller();
+        controller.set_float("speed", 1.0);
+        controller.update(0.0);
+        assert_eq!(controller.transition_progress(), 0.0);
+        assert_eq!(weights(&controller), vec![("idle".to_string(), 1.0)]);
+
+        controller.update(0.125);
+        assert_eq!(controller.transition_progress(), 0.5);
+        assert_eq!(weights(&controller), vec![("idle".to_string(), 0.5), ("walk".to_string(), 0.5)]);
+
+        controller.update(0.125);
+        assert!(!controller.is_transitioning());
+        assert_eq!(controller.transition_progress(), 1.0);
+        assert_eq!(weights(&controller), vec![("walk".to_string(), 1.0)]);
+    }
+
+    #[test]
+    fn blend_trees_mix_the_two_closest_children()
+    {
+        let mut controller = controller();
+        controller.play(1);
+
+        // Speed, weights of walk and run
+        let cases = [(0.0, 1.0, 0.0), (1.0, 1.0, 0.0), (1.5, 0.75, 0.25), (2.0, 0.5, 0.5), (3.0, 0.0, 1.0), (4.0, 0.0, 1.0)];
+        for (speed, walk, run) in cases
+        {
+            controller.set_float("speed", speed);
+            let samples = weights(&controller);
+            let weight = |clip: &str| samples.iter().find(|(name, _)| name == clip).map_or(0.0, |(_, weight)| *weight);
+            assert_eq!((weight("walk"), weight("run")), (walk, run), "speed {speed}");
+            assert_eq!(samples.iter().map(|(_, weight)| weight).sum::<f32>(), 1.0, "speed {speed}");
+        }
+
+        // Both children are at the same point of their own length
+        controller.set_float("speed", 2.0);
+        controller.update(0.375);
+        let samples = controller.samples();
+        assert_eq!(controller.states[1].motion.duration(&controller.floats), 0.75);
+        assert_eq!((samples[0].time, samples[1].time), (0.5, 0.25));
+    }
+}
@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use crate::sprite::{AnimationClip, Frame, PlayMode, SpriteSheet};
+
+// Used for frames without a duration and the clips of TexturePacker animations
+const DEFAULT_FRAME_DURATION: f32 = 0.1;
+
+// Content of an Aseprite, TexturePacker (hash or array export), DragonBones or Spine atlas file, without the texture
+pub struct Atlas
+{
+    pub image: Option<String>, // Image file of the atlas, relative to the json file
+    pub frames: Vec<Frame>,
+    pub names: Vec<String>, // Same order as frames
+    pub clips: Vec<(String, AnimationClip)>
+}
+
+impl Atlas
+{
+    pub fn into_sprite_sheet(self, texture: usize) -> SpriteSheet
+    {
+        let mut sheet = SpriteSheet::from_frames(texture, self.frames);
+        for (frame, name) in self.names.iter().enumerate()
+        {
+            sheet.set_name(name, frame);
+        }
+        for (name, clip) in self.clips
+        {
+            sheet.add_clip(&name, clip);
+        }
+        sheet
+    }
+}
+
+#[derive(Deserialize)]
+struct Rect
+{
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32
+}
+
+#[derive(Deserialize)]
+struct Size
+{
+    w: f32,
+    h: f32
+}
+
+#[derive(Deserialize)]
+struct Point
+{
+    x: f32,
+    y: f32
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawFrame
+{
+    filename: Option<String>, // Only in array exports, hash exports use the key
+    frame: Rect,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: Option<Rect>,
+    source_size: Option<Size>,
+    pivot: Option<Point>, // TexturePacker, normalized
+    duration: Option<f32> // Aseprite, milliseconds
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFrames
+{
+    Array(Vec<RawFrame>),
+    Hash(serde_json::Map<String, serde_json::Value>) // Needs the preserve_order feature of serde_json, the order are the frame numbers
+}
+
+#[derive(Deserialize)]
+struct FrameTag
+{
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+    repeat: Option<String>
+}
+
+#[derive(Deserialize)]
+struct SliceKey
+{
+    frame: usize,
+    bounds: Rect,
+    pivot: Option<Point> // Aseprite, pixels relative to bounds
+}
+
+#[derive(Deserialize)]
+struct Slice
+{
+    keys: Vec<SliceKey>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Meta
+{
+    image: Option<String>,
+    #[serde(default)]
+    frame_tags: Vec<FrameTag>, // Aseprite
+    #[serde(default)]
+    slices: Vec<Slice> // Aseprite
+}
+
+#[derive(Deserialize)]
+struct AtlasFile
+{
+    frames: RawFrames,
+    #[serde(default)]
+    meta: Meta,
+    #[serde(default)]
+    animations: HashMap<String, Vec<String>> // TexturePacker (pixi format), lists of frame names
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubTexture
+{
+    name: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    #[serde(default)]
+    frame_x: f32, // Minus the position inside of the untrimmed image
+    #[serde(default)]
+    frame_y: f32,
+    frame_width: Option<f32>,
+    frame_height: Option<f32>,
+    #[serde(default)]
+    rotated: bool
+}
+
+// DragonBones texture atlas (*_tex.json)
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesAtlas
+{
+    image_path: Option<String>,
+    #[serde(rename = "SubTexture")]
+    sub_textures: Vec<SubTexture>
+}
+
+// Aseprite tags become clips with the durations of their frames, TexturePacker animations clips with DEFAULT_FRAME_DURATION
+// An Aseprite file without tags gets one clip called "default" with every frame, DragonBones atlases have no clips
+pub fn parse_atlas(json: &str) -> Result<Atlas>
+{
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    if value.get("SubTexture").is_some()
+    {
+        return parse_dragonbones_atlas(serde_json::from_value(value)?);
+    }
+
+    let file: AtlasFile = serde_json::from_value(value)?;
+
+    let raw_frames = match file.frames
+    {
+        RawFrames::Array(frames) => frames,
+        RawFrames::Hash(map) =>
+        {
+            let mut frames = Vec::with_capacity(map.len());
+            for (name, value) in map
+            {
+                let mut frame: RawFrame = serde_json::from_value(value)?;
+                frame.filename = Some(name);
+                frames.push(frame);
+            }
+            frames
+        }
+    };
+
+    let mut frames = Vec::with_capacity(raw_frames.len());
+    let mut names = Vec::with_capacity(raw_frames.len());
+    let mut durations = Vec::with_capacity(raw_frames.len());
+    for (i, raw) in raw_frames.iter().enumerate()
+    {
+        let rect = (raw.frame.x, raw.frame.y, raw.frame.w, raw.frame.h);
+        let trim = raw.sprite_source_size.as_ref();
+        frames.push(Frame
+        {
+            rect,
+            rotated: raw.rotated,
+            offset: trim.map_or((0.0, 0.0), |trim| (trim.x, trim.y)),
+            source_size: raw.source_size.as_ref().map_or((rect.2, rect.3), |size| (size.w, size.h)),
+            pivot: raw.pivot.as_ref().map(|pivot| (pivot.x, pivot.y))
+        });
+        names.push(raw.filename.clone().unwrap_or_else(|| i.to_string()));
+        durations.push(raw.duration.map_or(DEFAULT_FRAME_DURATION, |ms| ms / 1000.0));
+    }
+
+    // A slice pivot holds from its key until the next key
+    for slice in &file.meta.slices
+    {
+        for (k, key) in slice.keys.iter().enumerate()
+        {
+            let Some(pivot) = &key.pivot else { continue };
+            let end = slice.keys.get(k + 1).map_or(frames.len(), |next| next.frame.min(frames.len()));
+
+            for frame in frames.iter_mut().take(end).skip(key.frame)
+            {
+                if frame.pivot.is_none()
+                {
+                    frame.pivot = Some(((key.bounds.x + pivot.x) / frame.source_size.0, (key.bounds.y + pivot.y) / frame.source_size.1));
+                }
+            }
+        }
+    }
+
+    let mut clips = Vec::new();
+    for tag in &file.meta.frame_tags
+    {
+        if tag.from > tag.to || tag.to >= frames.len()
+        {
+            bail!("Frame tag {} goes past the last frame", tag.name);
+        }
+
+        let mut clip_frames: Vec<usize> = (tag.from..=tag.to).collect();
+        if tag.direction.ends_with("reverse")
+        {
+            clip_frames.reverse();
+        }
+
+        // Only playing once or forever is supported, every other repeat count loops
+        let mode = match (tag.direction.starts_with("pingpong"), tag.repeat.as_deref())
+        {
+            (true, _) => PlayMode::PingPong,
+            (false, Some("1")) => PlayMode::Once,
+            (false, _) => PlayMode::Loop
+        };
+
+        let clip_durations = clip_frames.iter().map(|frame| durations[*frame]).collect();
+        clips.push((tag.name.clone(), AnimationClip::new(clip_frames, DEFAULT_FRAME_DURATION, mode).with_durations(clip_durations)));
+    }
+
+    for (name, frame_names) in &file.animations
+    {
+        let mut clip_frames = Vec::with_capacity(frame_names.len());
+        for frame_name in frame_names
+        {
+            match names.iter().position(|name| name == frame_name)
+            {
+                Some(frame) => clip_frames.push(frame),
+                None => bail!("Animation {} uses the unknown frame {}", name, frame_name)
+            }
+        }
+        if clip_frames.is_empty()
+        {
+            bail!("Animation {} has no frames", name);
+        }
+        clips.push((name.clone(), AnimationClip::new(clip_frames, DEFAULT_FRAME_DURATION, PlayMode::Loop)));
+    }
+
+    let is_aseprite = raw_frames.iter().any(|frame| frame.duration.is_some());
+    if is_aseprite && clips.is_empty() && !frames.is_empty()
+    {
+        clips.push(("default".to_string(), AnimationClip::new((0..frames.len()).collect(), DEFAULT_FRAME_DURATION, PlayMode::Loop).with_durations(durations)));
+    }
+
+    Ok(Atlas { image: file.meta.image, frames, names, clips })
+}
+
+fn parse_dragonbones_atlas(file: DragonBonesAtlas) -> Result<Atlas>
+{
+    let mut frames = Vec::with_capacity(file.sub_textures.len());
+    let mut names = Vec::with_capacity(file.sub_textures.len());
+    for sub in file.sub_textures
+    {
+        if sub.rotated
+        {
+            bail!("Rotated region {} is not supported, export the atlas without rotation", sub.name);
+        }
+
+        frames.push(Frame
+        {
+            rect: (sub.x, sub.y, sub.width, sub.height),
+            rotated: false,
+            offset: (-sub.frame_x, -sub.frame_y),
+            source_size: (sub.frame_width.unwrap_or(sub.width), sub.frame_height.unwrap_or(sub.height)),
+            pivot: None
+        });
+        names.push(sub.name);
+    }
+
+    Ok(Atlas { image: file.image_path, frames, names, clips: Vec::new() })
+}
+
+// Spine (libGDX) text atlas (*.atlas), the old indented and the new compact format, only with a single page
+pub fn parse_spine_atlas(text: &str) -> Result<Atlas>
+{
+    let mut image = None;
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut names = Vec::new();
+    // Bottom left offset of the current region, turned into the top left offset once the region is done
+    let mut bottom_offset = None;
+    let mut page_done = false;
+
+    let finish = |frame: Option<&mut Frame>, bottom_offset: Option<(f32, f32)>|
+    {
+        if let (Some(frame), Some((x, y))) = (frame, bottom_offset)
+        {
+            frame.offset = (x, frame.source_size.1 - frame.rect.3 - y);
+        }
+    };
+
+    for line in text.lines()
+    {
+        let line = line.trim();
+        if line.is_empty()
+        {
+            // A blank line after the regions starts the next page
+            page_done = image.is_some() && !frames.is_empty();
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else
+        {
+            if image.is_none()
+            {
+                image = Some(line.to_string());
+            }
+            else if page_done
+            {
+                bail!("Atlas has more than one page ({}), pack it into a single texture", line);
+            }
+            else
+            {
+                finish(frames.last_mut(), bottom_offset.take());
+                frames.push(Frame::new((0.0, 0.0, 0.0, 0.0)));
+                names.push(line.to_string());
+            }
+            continue;
+        };
+
+        // Page settings (size, format, filter, repeat, pma) come before the first region
+        let Some(frame) = frames.last_mut() else { continue };
+        let numbers: Vec<f32> = value.split(',').filter_map(|number| number.trim().parse().ok()).collect();
+        let name = names.last().map_or("", String::as_str);
+        match (key.trim(), numbers.as_slice())
+        {
+            ("xy", [x, y]) => (frame.rect.0, frame.rect.1) = (*x, *y),
+            ("size", [width, height]) =>
+            {
+                (frame.rect.2, frame.rect.3) = (*width, *height);
+                frame.source_size = (*width, *height);
+            }
+            ("bounds", [x, y, width, height]) =>
+            {
+                frame.rect = (*x, *y, *width, *height);
+                frame.source_size = (*width, *height);
+            }
+            ("orig", [width, height]) => frame.source_size = (*width, *height),
+            ("offset", [x, y]) => bottom_offset = Some((*x, *y)),
+            ("offsets", [x, y, width, height]) =>
+            {
+                bottom_offset = Some((*x, *y));
+                frame.source_size = (*width, *height);
+            }
+            ("rotate", _) if !matches!(value.trim(), "false" | "0") => bail!("Rotated region {} is not supported, export the atlas without rotation", name),
+            _ => {}
+        }
+    }
+    finish(frames.last_mut(), bottom_offset);
+
+    Ok(Atlas { image, frames, names, clips: Vec::new() })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const TEXTURE_PACKER: &str = r#"{
+        "frames": {
+            "run_1.png": { "frame": { "x": 0, "y": 0, "w": 10, "h": 20 }, "rotated": false, "trimmed": true, "spriteSourceSize": { "x": 3, "y": 2, "w": 10, "h": 20 }, "sourceSize": { "w": 16, "h": 24 }, "pivot": { "x": 0.5, "y": 1.0 } },
+            "run_0.png": { "frame": { "x": 10, "y": 0, "w": 16, "h": 24 }, "rotated": true }
+        },
+        "animations": { "run": ["run_0.png", "run_1.png"] },
+        "meta": { "image": "run.png" }
+    }"#;
+
+    const ASEPRITE: &str = r#"{
+        "frames": [
+            { "filename": "a 0", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "duration": 100 },
+            { "filename": "a 1", "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "duration": 200 },
+            { "filename": "a 2", "frame": { "x": 16, "y": 0, "w": 8, "h": 8 }, "duration": 300 }
+        ],
+        "meta": {
+            "image": "a.png",
+            "frameTags": [
+                { "name": "back", "from": 0, "to": 2, "direction": "reverse", "repeat": "1" },
+                { "name": "bounce", "from": 1, "to": 2, "direction": "pingpong" }
+            ],
+            "slices": [{ "keys": [{ "frame": 1, "bounds": { "x": 0, "y": 0, "w": 8, "h": 8 }, "pivot": { "x": 4, "y": 8 } }] }]
+        }
+    }"#;
+
+    fn clip<'a>(atlas: &'a Atlas, name: &str) -> &'a AnimationClip
+    {
+        &atlas.clips.iter().find(|(clip, _)| clip == name).unwrap().1
+    }
+
+    #[test]
+    fn texture_packer_hash_keeps_the_file_order()
+    {
+        let atlas = parse_atlas(TEXTURE_PACKER).unwrap();
+        assert_eq!(atlas.image.as_deref(), Some("run.png"));
+        assert_eq!(atlas.names, vec!["run_1.png", "run_0.png"]);
+
+        let trimmed = atlas.frames[0];
+        assert_eq!(trimmed.offset, (3.0, 2.0));
+        assert_eq!(trimmed.source_size, (16.0, 24.0));
+        assert_eq!(trimmed.pivot, Some((0.5, 1.0)));
+        assert!(atlas.frames[1].rotated);
+
+        let run = clip(&atlas, "run");
+        assert_eq!(run.frames, vec![1, 0]);
+        assert_eq!(run.durations, vec![DEFAULT_FRAME_DURATION; 2]);
+    }
+
+    #[test]
+    fn aseprite_tags_become_clips_with_frame_durations()
+    {
+        let atlas = parse_atlas(ASEPRITE).unwrap();
+
+        let back = clip(&atlas, "back");
+        assert_eq!(back.frames, vec![2, 1, 0]);
+        assert_eq!(back.durations, vec![0.3, 0.2, 0.1]);
+        assert_eq!(back.mode, PlayMode::Once);
+        assert_eq!(clip(&atlas, "bounce").mode, PlayMode::PingPong);
+
+        // The slice pivot holds from its key to the last frame
+        assert_eq!(atlas.frames[0].pivot, None);
+        assert_eq!(atlas.frames[1].pivot, Some((0.5, 1.0)));
+        assert_eq!(atlas.frames[2].pivot, Some((0.5, 1.0)));
+    }
+
+    #[test]
+    fn aseprite_without_tags_gets_a_default_clip()
+    {
+        let json = ASEPRITE.replace("\"frameTags\"", "\"unusedTags\"");
+        let atlas = parse_atlas(&json).unwrap();
+        assert_eq!(clip(&atlas, "default").frames, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn broken_clips_are_errors()
+    {
+        assert!(parse_atlas(&ASEPRITE.replace("\"to\": 2, \"direction\": \"pingpong\"", "\"to\": 3, \"direction\": \"pingpong\"")).is_err());
+        assert!(parse_atlas(&TEXTURE_PACKER.replace("[\"run_0.png\", \"run_1.png\"]", "[\"run_2.png\"]")).is_err());
+        assert!(parse_atlas(&TEXTURE_PACKER.replace("[\"run_0.png\", \"run_1.png\"]", "[]")).is_err());
+    }
+
+    #[test]
+    fn spine_atlas_offsets_are_measured_from_the_top()
+    {
+        let text = "
+hero.png
+size: 64, 64
+filter: Linear, Linear
+
+arm
+  rotate: false
+  xy: 2, 4
+  size: 10, 12
+  orig: 16, 20
+  offset: 1, 3
+leg
+bounds: 20, 4, 8, 8
+";
+        let atlas = parse_spine_atlas(text).unwrap();
+        assert_eq!(atlas.image.as_deref(), Some("hero.png"));
+        assert_eq!(atlas.names, vec!["arm", "leg"]);
+        assert_eq!(atlas.frames[0].rect, (2.0, 4.0, 10.0, 12.0));
+        assert_eq!(atlas.frames[0].source_size, (16.0, 20.0));
+        assert_eq!(atlas.frames[0].offset, (1.0, 5.0));
+        assert_eq!(atlas.frames[1].offset, (0.0, 0.0));
+
+        assert!(parse_spine_atlas(&text.replace("rotate: false", "rotate: 90")).is_err());
+        assert!(parse_spine_atlas(&format!("{}\nsecond.png\nsize: 8, 8\n", text)).is_err());
+    }
+
+    #[test]
+    fn dragonbones_atlas_untrims_with_the_frame()
+    {
+        let json = r#"{ "imagePath": "hero_tex.png", "SubTexture": [{ "name": "arm", "x": 2, "y": 4, "width": 10, "height": 12, "frameX": -3, "frameY": -1, "frameWidth": 16, "frameHeight": 14 }] }"#;
+        let atlas = parse_atlas(json).unwrap();
+        assert_eq!(atlas.image.as_deref(), Some("hero_tex.png"));
+        assert_eq!(atlas.frames[0].offset, (3.0, 1.0));
+        assert_eq!(atlas.frames[0].source_size, (16.0, 14.0));
+        assert!(atlas.clips.is_empty());
+    }
+}
@@ -5,7 +5,8 @@ use winit::{event::MouseButton, keyboard::KeyCode};
 
 struct App 
 {
-    rotation: f32,
+    tweens: TweenManager,
+    rotation: usize,
     x: f32,
     y: f32,
     cheetah: usize,
@@ -26,11 +27,14 @@ impl EngineEvent for App
         // {
         //     self.chars.push(loader.load_char(c).unwrap());
         // }
-        let test = loader.load_text("Test:qle-|p!", 200.0);
+        let _ = loader.load_text("Test:qle-|p!", 200.0);
         let _ = loader.load_text("It_Really_Works!", 150.0);
+
+        // One turn every 36 seconds
+        self.rotation = self.tweens.add(Tween::new(0.0, 2.0*PI, 36.0).with_repeat(tween::REPEAT_FOREVER));
     }
     
-    fn update(&mut self, input: &Input, dt: f64) 
+    fn update(&mut self, input: &Input, _dt: f64) 
     {
         if input.is_mouse_pressed(MouseButton::Left)
         {
@@ -44,23 +48,23 @@ impl EngineEvent for App
 
         // if input
 
-        self.rotation += 0.1745329*dt as f32;
-        if self.rotation >= 2.0*PI
-        {
-            self.rotation = 0.0;
-        }
-
         self.x = input.mouse_position().0 as f32;
         self.y = input.mouse_position().1 as f32;
     }
+    fn tweens(&mut self) -> Option<&mut TweenManager>
+    {
+        Some(&mut self.tweens)
+    }
+
     fn render(&self, renderer: &mut Renderer) //Column-major layout
     {  
+        let rotation: f32 = self.tweens.value(self.rotation).unwrap();
         // renderer.draw(0, [[scale*self.rotation.cos(), self.rotation.sin(), 0.0, 0.0], [scale*-self.rotation.sin(), self.rotation.cos(), 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]], [0.0, 0.0, 1.0, 1.0]);
         // renderer.draw(0, [[scale*(-self.rotation).cos(), (-self.rotation).sin(), 0.0, 0.0], [scale*-(-self.rotation).sin(), (-self.rotation).cos(), 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]], [1.0, 0.0, 0.0, 1.0]);
         // renderer.draw(0, [[scale*1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [self.x, self.y, 0.0, 1.0]], [0.0, 1.0, 0.0, 1.0]);
         renderer.draw_texture(0, renderer.matrix((renderer.virtual_size.0/2.0, renderer.virtual_size.1/2.0), (renderer.window_size.0, renderer.virtual_size.1), 0.0), self.cheetah, 0);
-        renderer.draw(0, renderer.matrix((renderer.virtual_size.0/2.0, renderer.virtual_size.1/2.0), (renderer.virtual_size.1/2.0, renderer.virtual_size.1/2.0), self.rotation), [0.0, 0.0, 1.0, 0.5], 1);
-        renderer.draw(0, renderer.matrix((renderer.virtual_size.0/2.0, renderer.virtual_size.1/2.0), (renderer.virtual_size.1/2.0, renderer.virtual_size.1/2.0), -self.rotation), [1.0, 0.0, 0.0, 0.5], 2);
+        renderer.draw(0, renderer.matrix((renderer.virtual_size.0/2.0, renderer.virtual_size.1/2.0), (renderer.virtual_size.1/2.0, renderer.virtual_size.1/2.0), rotation), [0.0, 0.0, 1.0, 0.5], 1);
+        renderer.draw(0, renderer.matrix((renderer.virtual_size.0/2.0, renderer.virtual_size.1/2.0), (renderer.virtual_size.1/2.0, renderer.virtual_size.1/2.0), -rotation), [1.0, 0.0, 0.0, 0.5], 2);
         renderer.draw(0, renderer.matrix((self.x, self.y), (100.0, 100.0), 0.0), [0.0, 1.0, 0.0, 1.0], 3);
         renderer.draw_texture(0, renderer.texture_matrix((100.0, 100.0), (0.5, 0.5), 0.0, (1920.0, 1014.0)), self.owl, 4);
         renderer.draw_texture(0, renderer.texture_matrix((500.0, 500.0), (1.0, 1.0), 0.0, (24.0, 39.0)), self.char, 4);
@@ -82,7 +86,8 @@ impl App
     {
         Self 
         { 
-            rotation: 0.0, 
+            tweens: TweenManager::new(),
+            rotation: 0, 
             x: 0.0, 
             y: 0.0,
             cheetah: 0,
@@ -0,0 +1,174 @@
+use cgmath::{Matrix4, Rad, SquareMatrix, Vector3, Vector4};
+
+use crate::{transform, viewport::Viewport};
+
+// World units are pixels at zoom 1 with y pointing down, like the screen
+// position is the world point in the center of the view, viewport the visible size at zoom 1 (normally virtual_size)
+// Owned by the game, updated in update and passed to Renderer::with_camera in render
+#[derive(Copy, Clone, Debug)]
+pub struct Camera2D
+{
+    pub position: (f32, f32),
+    pub zoom: f32,
+    pub rotation: f32, // Radians, positive turns the camera counter-clockwise, so the world turns clockwise
+    pub viewport: (f32, f32),
+    shake_intensity: f32,
+    shake_duration: f32,
+    shake_time: f32,
+    shake_offset: (f32, f32),
+    time: f32
+}
+
+impl Camera2D
+{
+    pub fn new(viewport: (f32, f32)) -> Self
+    {
+        Self
+        {
+            position: (viewport.0 / 2.0, viewport.1 / 2.0),
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport,
+            shake_intensity: 0.0,
+            shake_duration: 0.0,
+            shake_time: 0.0,
+            shake_offset: (0.0, 0.0),
+            time: 0.0
+        }
+    }
+
+    // Moves towards target, higher smoothing = faster, independent of the frame rate
+    pub fn follow(&mut self, target: (f32, f32), smoothing: f32, dt: f64)
+    {
+        let t = 1.0 - (-smoothing * dt as f32).exp();
+        self.position.0 += (target.0 - self.position.0) * t;
+        self.position.1 += (target.1 - self.position.1) * t;
+    }
+
+    // intensity is the maximum offset in pixels, fades out over duration (seconds)
+    pub fn shake(&mut self, intensity: f32, duration: f32)
+    {
+        self.shake_intensity = intensity;
+        self.shake_duration = duration;
+        self.shake_time = duration;
+    }
+
+    // Has to be called every frame for shake to work
+    pub fn update(&mut self, dt: f64)
+    {
+        self.time += dt as f32;
+
+        if self.shake_time > 0.0
+        {
+            self.shake_time = (self.shake_time - dt as f32).max(0.0);
+            let fade = self.shake_time / self.shake_duration;
+            let strength = self.shake_intensity * fade * fade;
+
+            // Sum of sines instead of random noise, so there is no need for a random dependency
+            let t = self.time;
+            self.shake_offset =
+            (
+                ((t * 47.3).sin() + (t * 91.7).sin() * 0.5) / 1.5 * strength,
+                ((t * 53.9).cos() + (t * 83.1).sin() * 0.5) / 1.5 * strength
+            );
+        }
+        else
+        {
+            self.shake_offset = (0.0, 0.0);
+        }
+    }
+
+    // World to screen pixels (in viewport size), includes shake
+    pub fn view_matrix(&self) -> Matrix4<f32>
+    {
+        let center = Vector3::new(self.position.0 + self.shake_offset.0, self.position.1 + self.shake_offset.1, 0.0);
+
+        Matrix4::from_translation(Vector3::new(self.viewport.0 / 2.0, self.viewport.1 / 2.0, 0.0))
+            * Matrix4::from_scale(self.zoom)
+            * Matrix4::from_angle_z(Rad(self.rotation))
+            * Matrix4::from_translation(-center)
+    }
+
+    // World to clip space, the view ends in virtual pixels, which then get fitted into the window by viewport like everything else
+    // snap scrolls the world in whole pixels, otherwise snapped sprites would still shimmer while the camera moves (pixel art)
+    pub fn view_projection(&self, viewport: &Viewport, snap: bool) -> [[f32; 4]; 4]
+    {
+        let projection = Matrix4::from(transform::to_model(transform::virtual_projection(viewport)));
+
+        let mut view = self.view_matrix();
+        if snap
+        {
+            view.w.x = view.w.x.round();
+            view.w.y = view.w.y.round();
+        }
+
+        (projection * view).into()
+    }
+
+    // Screen position in the same coordinates as Input::mouse_position
+    pub fn world_to_screen(&self, world: (f32, f32)) -> (f32, f32)
+    {
+        let screen = self.view_matrix() * Vector4::new(world.0, world.1, 0.0, 1.0);
+        (screen.x, screen.y)
+    }
+
+    pub fn screen_to_world(&self, screen: (f32, f32)) -> (f32, f32)
+    {
+        let inverse = self.view_matrix().invert().unwrap_or(Matrix4::identity());
+        let world = inverse * Vector4::new(screen.0, screen.1, 0.0, 1.0);
+        (world.x, world.y)
+    }
+
+    // Size of the visible world area
+    pub fn visible_size(&self) -> (f32, f32)
+    {
+        (self.viewport.0 / self.zoom, self.viewport.1 / self.zoom)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::viewport::ScalingPolicy;
+
+    fn clip(m: [[f32; 4]; 4], world: (f32, f32)) -> (f32, f32)
+    {
+        let clip = Matrix4::from(m) * Vector4::new(world.0, world.1, 0.0, 1.0);
+        (clip.x, clip.y)
+    }
+
+    #[test]
+    fn the_camera_position_is_the_center_of_the_view()
+    {
+        let mut camera = Camera2D::new((200.0, 100.0));
+        camera.position = (500.0, 300.0);
+        let viewport = Viewport::new((400.0, 200.0), (200.0, 100.0), ScalingPolicy::Letterbox);
+
+        let m = camera.view_projection(&viewport, false);
+        assert_eq!(clip(m, (500.0, 300.0)), (0.0, 0.0));
+        assert_eq!(clip(m, (400.0, 250.0)), (-1.0, 1.0));
+
+        camera.zoom = 2.0;
+        assert_eq!(clip(camera.view_projection(&viewport, false), (450.0, 275.0)), (-1.0, 1.0));
+    }
+
+    #[test]
+    fn the_view_follows_the_scaling_policy()
+    {
+        let camera = Camera2D::new((200.0, 100.0));
+        // Twice as wide as needed, letterboxing leaves bars of 100 pixels on both sides
+        let viewport = Viewport::new((400.0, 100.0), (200.0, 100.0), ScalingPolicy::Letterbox);
+        assert_eq!(clip(camera.view_projection(&viewport, false), (0.0, 0.0)), (-0.5, 1.0));
+    }
+
+    #[test]
+    fn snapping_scrolls_in_whole_pixels()
+    {
+        let mut camera = Camera2D::new((200.0, 100.0));
+        camera.position = (100.4, 49.6);
+        let viewport = Viewport::new((200.0, 100.0), (200.0, 100.0), ScalingPolicy::Stretch);
+        assert_eq!(clip(camera.view_projection(&viewport, true), (0.0, 0.0)), (-1.0, 1.0));
+        assert_ne!(clip(camera.view_projection(&viewport, false), (0.0, 0.0)), (-1.0, 1.0));
+    }
+}
\ No newline at end of file
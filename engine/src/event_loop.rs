@@ -1,12 +1,24 @@
-use winit::{dpi::LogicalSize, event::*, event_loop::EventLoop, window::WindowBuilder};
+use winit::{dpi::LogicalSize, event::*, event_loop::EventLoop, keyboard::KeyCode, window::WindowBuilder};
 
-use crate::{input::Input, renderer::Renderer, state::{Loader, LoadingContext, State}};
+use crate::{input::Input, preview::{PreviewPlayer, PreviewSettings}, renderer::Renderer, state::{Loader, LoadingContext, State}, tween::TweenManager};
 
 pub trait EngineEvent 
 {
     fn setup(&mut self, loader: &mut dyn Loader);
     fn update(&mut self, input: &Input, dt: f64);
     fn render(&self, renderer: &mut Renderer);
+
+    // Key that saves the current frame as a png into the working directory, None to disable
+    fn screenshot_key(&self) -> Option<KeyCode> { Some(KeyCode::F12) }
+
+    // Gets updated with dt right before update, so tween values are already advanced in there
+    fn tweens(&mut self) -> Option<&mut TweenManager> { None }
+
+    // Some turns game_loop into an animation preview, update and tweens are not called in there, the preview moves time with seek
+    fn preview(&self) -> Option<PreviewSettings> { None }
+
+    // Puts every animation of the game at time seconds, for the preview
+    fn seek(&mut self, _time: f64) {}
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
@@ -48,8 +60,15 @@ pub async fn game_loop<T: EngineEvent + 'static>(mut game: Box<T>, title: &str,
     let size = window.inner_size();
     let mut input = Input::new((size.width as f64, size.height as f64));
 
-    let mut loader = LoadingContext::new(&mut state.renderer, &mut state.device, &mut state.queue);
+    let mut loader = LoadingContext::new(&mut state.renderer, &state.device, &state.queue);
     game.setup(&mut loader);
+    // setup can change the scaling, the first update already needs it for mouse_position
+    input.update_viewport(state.renderer.viewport());
+    let mut preview = game.preview().map(PreviewPlayer::new);
+    if preview.is_some()
+    {
+        game.seek(0.0);
+    }
 
     let mut last_frame_time = std::time::Instant::now();
 
@@ -64,7 +83,7 @@ pub async fn game_loop<T: EngineEvent + 'static>(mut game: Box<T>, title: &str,
             }
             if window_id == state.window().id() => 
             {
-                input.update_inputs(&event);
+                input.update_inputs(event);
                 if !state.input(event){
                 match event
                 {
@@ -90,10 +109,28 @@ pub async fn game_loop<T: EngineEvent + 'static>(mut game: Box<T>, title: &str,
                         state.update(); // Temporary
                         // state.input(&event); //Temporary
                         ///////////////////////////////////////////////////
-                        match state.render(|renderer|
+                        let result = state.render(|renderer|
                         {
-                            game.render(renderer);
-                        })
+                            match &preview
+                            {
+                                Some(preview) =>
+                                {
+                                    // Onion skins first, draws with the same z_index stay in this order
+                                    for (time, tint) in preview.onion_frames()
+                                    {
+                                        game.seek(time);
+                                        renderer.with_tint(tint, |renderer| game.render(renderer));
+                                    }
+                                    game.seek(preview.time());
+                                    game.render(renderer);
+                                    preview.draw_timeline(renderer);
+                                }
+                                None => game.render(renderer)
+                            }
+                        });
+                        input.update_viewport(state.renderer.viewport());
+
+                        match result
                         {
                             Ok(_) => {}
                             Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => state.resize(state.size),
@@ -117,7 +154,26 @@ pub async fn game_loop<T: EngineEvent + 'static>(mut game: Box<T>, title: &str,
                 let now = std::time::Instant::now();
                 let dt = (now - last_frame_time).as_secs_f64();
                 
-                game.update(&input, dt);
+                if let Some(preview) = &mut preview
+                {
+                    preview.update(&input, dt, (state.size.width as f32, state.size.height as f32));
+                    game.seek(preview.time());
+                }
+                else
+                {
+                    if let Some(tweens) = game.tweens()
+                    {
+                        tweens.update(dt);
+                    }
+                    game.update(&input, dt);
+                }
+
+                if game.screenshot_key().is_some_and(|key| input.is_key_pressed(key))
+                {
+                    let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+                    state.request_screenshot(&format!("screenshot_{}.png", timestamp));
+                }
+
                 state.window().request_redraw();
                 input.prev_update();
 
@@ -2,6 +2,8 @@ use std::collections::HashSet;
 
 use winit::{event::{ElementState, KeyEvent, MouseButton, WindowEvent}, keyboard::{KeyCode, PhysicalKey}};
 
+use crate::viewport::{ScalingPolicy, Viewport};
+
 pub struct Input
 {
     keys_pressed: HashSet<KeyCode>,
@@ -9,8 +11,7 @@ pub struct Input
     mouse_pressed: HashSet<MouseButton>,
     prev_mouse_pressed: HashSet<MouseButton>,
     mouse_position: Option<(f64, f64)>,
-    window_size: (f64, f64),
-    virtual_size: (f64, f64)
+    viewport: Viewport
 }
 
 impl Input
@@ -24,8 +25,7 @@ impl Input
             mouse_pressed: HashSet::new(),
             prev_mouse_pressed: HashSet::new(),
             mouse_position: None,
-            window_size,
-            virtual_size: window_size
+            viewport: Viewport::new((window_size.0 as f32, window_size.1 as f32), (window_size.0 as f32, window_size.1 as f32), ScalingPolicy::default())
         }
     }
 
@@ -77,7 +77,13 @@ impl Input
 
     pub(crate) fn update_screen(&mut self, size: (f64, f64))
     {
-        self.window_size = size;
+        self.viewport.window_size = (size.0 as f32, size.1 as f32);
+    }
+
+    // Virtual size and scaling policy of the renderer, they can change every frame
+    pub(crate) fn update_viewport(&mut self, viewport: Viewport)
+    {
+        self.viewport = viewport;
     }
 
     pub fn is_key_hold(&self, key: KeyCode) -> bool
@@ -106,15 +112,17 @@ impl Input
         {
             return mouse_pos;
         }
-        return (0.0, 0.0);
+        (0.0, 0.0)
     }
 
+    // In virtual pixels, can be outside of virtual_size with bars or Expand
     pub fn mouse_position(&self) -> (f64, f64)
     {
         if let Some(mouse_pos) = self.mouse_position
         {
-            return (mouse_pos.0/self.window_size.0*self.virtual_size.0, mouse_pos.1/self.window_size.1*self.virtual_size.1);
+            let pos = self.viewport.window_to_virtual((mouse_pos.0 as f32, mouse_pos.1 as f32));
+            return (pos.0 as f64, pos.1 as f64);
         }
-        return (0.0, 0.0);
+        (0.0, 0.0)
     }
 }
\ No newline at end of file
@@ -6,7 +6,43 @@ pub mod renderer;
 pub mod input;
 pub mod shader;
 pub mod text;
+pub mod postprocess;
+pub mod pipeline;
+pub mod camera;
+pub mod transform;
+pub mod viewport;
+pub mod nine_slice;
+pub mod sprite;
+pub mod atlas;
+pub mod tween;
+pub mod timeline;
+pub mod animation_file;
+pub mod skeleton;
+pub mod skeleton_import;
+pub mod animator;
+pub mod path;
+pub mod morph;
+pub mod lottie;
+pub mod motion_path;
+pub mod preview;
 
 pub use event_loop::{EngineEvent, game_loop};
 pub use renderer::Renderer;
-pub use input::Input;
\ No newline at end of file
+pub use input::Input;
+pub use camera::Camera2D;
+pub use transform::{Anchor, CoordinateSpace, Transform2D};
+pub use viewport::ScalingPolicy;
+pub use texture::TextureOptions;
+pub use nine_slice::NineSlice;
+pub use tween::{Ease, Lerp, Tween, TweenManager};
+pub use animation_file::{Animation, AnimationFile};
+pub use timeline::{Interpolation, Keyframe, Property, Timeline, Track, TrackValue};
+pub use sprite::{AnimatedSprite, AnimationClip, Frame, FrameEvent, PlayMode, SpriteSheet};
+pub use skeleton::{Attachment, Skeleton, SkeletonClip, SkinVertex, SkinnedMesh};
+pub use skeleton_import::SkeletonRig;
+pub use animator::{AnimationController, Condition, Motion, MotionSample, Transition};
+pub use path::{FillRule, Path, PathMesh};
+pub use morph::ShapeMorph;
+pub use lottie::{Lottie, LottiePlayer};
+pub use motion_path::{MotionPath, PathFollower};
+pub use preview::{PreviewControls, PreviewSettings};
\ No newline at end of file
@@ -0,0 +1,988 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use cgmath::{Matrix3, SquareMatrix, Vector3};
+use serde_json::Value;
+
+use crate::{path::{FillRule, Path, PathMesh, DEFAULT_TOLERANCE}, renderer::Renderer, state::Loader, transform::{CoordinateSpace, Transform2D}, tween};
+
+// Lottie (Bodymovin json) subset:
+// - Layers: shape, solid, null and precomp, with parenting, in and out points, start time and time stretch
+// - Transforms: anchor, position (also split), scale, rotation and opacity, with linear, bezier and hold keyframes
+// - Shapes: groups, paths, rectangles, ellipses, fills (nonzero and even-odd), strokes and trim paths
+// Everything else (masks, mattes, effects, gradients, text, images, ...) is listed in Lottie::unsupported
+
+// Every value is a list of numbers, paths are [closed, then x, y, in x, in y, out x, out y per vertex]
+#[derive(Clone, Debug)]
+struct Key
+{
+    time: f32, // Frames
+    value: Vec<f32>,
+    end: Option<Vec<f32>>, // Older files store the end of the segment in the key instead of taking the next value
+    hold: bool,
+    ease_out: (f32, f32), // Bezier handles of the segment to the next key
+    ease_in: (f32, f32)
+}
+
+#[derive(Clone, Debug)]
+enum Animated
+{
+    Static(Vec<f32>),
+    Keys(Vec<Key>)
+}
+
+impl Animated
+{
+    fn value(&self, frame: f32) -> Vec<f32>
+    {
+        let keys = match self
+        {
+            Animated::Static(value) => return value.clone(),
+            Animated::Keys(keys) => keys
+        };
+
+        let next = keys.partition_point(|key| key.time <= frame);
+        if next == 0
+        {
+            return keys[0].value.clone();
+        }
+
+        let key = &keys[next - 1];
+        let end = match (&key.end, keys.get(next))
+        {
+            (_, None) => return key.value.clone(),
+            (Some(end), Some(_)) => end,
+            (None, Some(next)) => &next.value
+        };
+        let next_time = keys[next].time;
+        if key.hold || end.len() != key.value.len() || next_time <= key.time
+        {
+            return key.value.clone();
+        }
+
+        let t = (frame - key.time) / (next_time - key.time);
+        let t = tween::cubic_bezier(key.ease_out.0, key.ease_out.1, key.ease_in.0, key.ease_in.1, t);
+        key.value.iter().zip(end).map(|(a, b)| a + (b - a) * t).collect()
+    }
+
+    fn number(&self, frame: f32) -> f32
+    {
+        self.value(frame).first().copied().unwrap_or(0.0)
+    }
+
+    fn pair(&self, frame: f32) -> (f32, f32)
+    {
+        let value = self.value(frame);
+        (value.first().copied().unwrap_or(0.0), value.get(1).copied().unwrap_or(0.0))
+    }
+
+    fn is_zero(&self) -> bool
+    {
+        matches!(self, Animated::Static(value) if value.iter().all(|v| *v == 0.0))
+    }
+}
+
+fn numbers(value: &Value) -> Option<Vec<f32>>
+{
+    match value
+    {
+        Value::Number(number) => Some(vec![number.as_f64()? as f32]),
+        Value::Array(list) => list.iter().map(|v| v.as_f64().map(|v| v as f32)).collect(),
+        _ => None
+    }
+}
+
+// Bezier path of a shape, keyframes wrap it into a list of one
+fn path_values(value: &Value) -> Option<Vec<f32>>
+{
+    let value = value.as_array().and_then(|list| list.first()).unwrap_or(value);
+    let closed = value.get("c").and_then(Value::as_bool).unwrap_or(false);
+    let points = |key: &str| value.get(key).and_then(Value::as_array).map(|list| list.iter().filter_map(numbers).collect::<Vec<_>>());
+    let (v, i, o) = (points("v")?, points("i")?, points("o")?);
+
+    let mut values = vec![if closed { 1.0 } else { 0.0 }];
+    for n in 0..v.len()
+    {
+        let get = |list: &Vec<Vec<f32>>, axis: usize| list.get(n).and_then(|p| p.get(axis)).copied().unwrap_or(0.0);
+        values.extend([get(&v, 0), get(&v, 1), get(&i, 0), get(&i, 1), get(&o, 0), get(&o, 1)]);
+    }
+    Some(values)
+}
+
+// Ease handles are numbers or one number per dimension, only the first one is used
+fn handle(value: Option<&Value>, default: (f32, f32)) -> (f32, f32)
+{
+    let first = |axis: &str| value?.get(axis).and_then(|v| numbers(v)?.first().copied());
+    (first("x").unwrap_or(default.0), first("y").unwrap_or(default.1))
+}
+
+fn animated(property: Option<&Value>, default: &[f32], values: fn(&Value) -> Option<Vec<f32>>) -> Animated
+{
+    let Some(property) = property else { return Animated::Static(default.to_vec()) };
+    let k = property.get("k").unwrap_or(property);
+
+    let Some(list) = k.as_array().filter(|list| list.first().is_some_and(|key| key.get("t").is_some())) else
+    {
+        return Animated::Static(values(k).unwrap_or_else(|| default.to_vec()));
+    };
+
+    let mut keys: Vec<Key> = Vec::with_capacity(list.len());
+    for key in list
+    {
+        let time = key.get("t").and_then(Value::as_f64).unwrap_or(0.0) as f32;
+        // The last key of older files only has a time
+        let value = key.get("s").and_then(values)
+            .or_else(|| keys.last().and_then(|previous| previous.end.clone().or_else(|| Some(previous.value.clone()))))
+            .unwrap_or_else(|| default.to_vec());
+
+        keys.push(Key
+        {
+            time,
+            value,
+            end: key.get("e").and_then(values),
+            hold: key.get("h").and_then(Value::as_f64) == Some(1.0),
+            ease_out: handle(key.get("o"), (0.0, 0.0)),
+            ease_in: handle(key.get("i"), (1.0, 1.0))
+        });
+    }
+    Animated::Keys(keys)
+}
+
+fn number_property(value: &Value, key: &str, default: f32) -> Animated
+{
+    animated(value.get(key), &[default], numbers)
+}
+
+#[derive(Clone, Debug)]
+enum Position
+{
+    Combined(Animated),
+    Split(Animated, Animated)
+}
+
+#[derive(Clone, Debug)]
+struct LottieTransform
+{
+    anchor: Animated,
+    position: Position,
+    scale: Animated, // Percent
+    rotation: Animated, // Degrees clockwise
+    opacity: Animated // Percent
+}
+
+impl LottieTransform
+{
+    fn parse(value: Option<&Value>, context: &str, unsupported: &mut Vec<String>) -> Self
+    {
+        let empty = Value::Null;
+        let value = value.unwrap_or(&empty);
+
+        let position = value.get("p");
+        let position = if position.and_then(|p| p.get("s")).and_then(Value::as_bool) == Some(true)
+        {
+            let p = position.unwrap();
+            Position::Split(number_property(p, "x", 0.0), number_property(p, "y", 0.0))
+        }
+        else
+        {
+            if position.and_then(|p| p.get("k")).and_then(Value::as_array).is_some_and(|keys| keys.iter().any(has_spatial_tangents))
+            {
+                report(unsupported, format!("Curved motion path in {}", context));
+            }
+            Position::Combined(animated(position, &[0.0, 0.0], numbers))
+        };
+
+        if !number_property(value, "sk", 0.0).is_zero()
+        {
+            report(unsupported, format!("Skew in {}", context));
+        }
+
+        Self
+        {
+            anchor: animated(value.get("a"), &[0.0, 0.0], numbers),
+            position,
+            scale: animated(value.get("s"), &[100.0, 100.0], numbers),
+            rotation: animated(value.get("r").or(value.get("rz")), &[0.0], numbers),
+            opacity: number_property(value, "o", 100.0)
+        }
+    }
+
+    fn matrix(&self, frame: f32) -> Matrix3<f32>
+    {
+        let translation = match &self.position
+        {
+            Position::Combined(position) => position.pair(frame),
+            Position::Split(x, y) => (x.number(frame), y.number(frame))
+        };
+        let scale = self.scale.pair(frame);
+
+        // Transform2D turns counter-clockwise
+        Transform2D::new(translation, -self.rotation.number(frame).to_radians(), (scale.0 / 100.0, scale.1 / 100.0), CoordinateSpace::Virtual)
+            .with_pivot(self.anchor.pair(frame))
+            .matrix()
+    }
+
+    fn opacity(&self, frame: f32) -> f32
+    {
+        self.opacity.number(frame) / 100.0
+    }
+}
+
+fn has_spatial_tangents(key: &Value) -> bool
+{
+    ["ti", "to"].iter().any(|tangent| key.get(tangent).and_then(numbers).is_some_and(|values| values.iter().any(|v| *v != 0.0)))
+}
+
+#[derive(Clone, Debug)]
+enum ShapeItem
+{
+    Group { items: Vec<ShapeItem>, transform: LottieTransform },
+    Path(Animated),
+    Rect { position: Animated, size: Animated, roundness: Animated, clockwise: bool },
+    Ellipse { position: Animated, size: Animated, clockwise: bool },
+    Fill { color: Animated, opacity: Animated, rule: FillRule },
+    Stroke { color: Animated, opacity: Animated, width: Animated },
+    Trim { start: Animated, end: Animated, offset: Animated }
+}
+
+fn parse_shapes(items: Option<&Value>, context: &str, unsupported: &mut Vec<String>) -> Vec<ShapeItem>
+{
+    let mut shapes = Vec::new();
+    for item in items.and_then(Value::as_array).into_iter().flatten()
+    {
+        if item.get("hd").and_then(Value::as_bool) == Some(true)
+        {
+            continue;
+        }
+
+        let kind = item.get("ty").and_then(Value::as_str).unwrap_or("");
+        let name = item.get("nm").and_then(Value::as_str).unwrap_or(kind);
+        // Direction of rectangles and ellipses, 3 = counter-clockwise
+        let clockwise = item.get("d").and_then(Value::as_f64) != Some(3.0);
+        let shape = match kind
+        {
+            "gr" =>
+            {
+                let items = item.get("it");
+                let transform = items.and_then(Value::as_array).and_then(|items| items.iter().find(|item| item.get("ty").and_then(Value::as_str) == Some("tr")));
+                ShapeItem::Group { items: parse_shapes(items, context, unsupported), transform: LottieTransform::parse(transform, context, unsupported) }
+            }
+            "sh" => ShapeItem::Path(animated(item.get("ks"), &[0.0], path_values)),
+            "rc" => ShapeItem::Rect { position: animated(item.get("p"), &[0.0, 0.0], numbers), size: animated(item.get("s"), &[0.0, 0.0], numbers), roundness: number_property(item, "r", 0.0), clockwise },
+            "el" => ShapeItem::Ellipse { position: animated(item.get("p"), &[0.0, 0.0], numbers), size: animated(item.get("s"), &[0.0, 0.0], numbers), clockwise },
+            "fl" =>
+            {
+                let rule = if item.get("r").and_then(Value::as_f64) == Some(2.0) { FillRule::EvenOdd } else { FillRule::NonZero };
+                ShapeItem::Fill { color: animated(item.get("c"), &[0.0, 0.0, 0.0, 1.0], numbers), opacity: number_property(item, "o", 100.0), rule }
+            }
+            "st" =>
+            {
+                if item.get("d").and_then(Value::as_array).is_some_and(|dashes| !dashes.is_empty())
+                {
+                    report(unsupported, format!("Dashed stroke {} in {}", name, context));
+                }
+                if item.get("lc").and_then(Value::as_f64).is_some_and(|cap| cap != 1.0) || item.get("lj").and_then(Value::as_f64).is_some_and(|join| join != 1.0)
+                {
+                    report(unsupported, format!("Round or square line ends of stroke {} in {} (drawn flat and mitered)", name, context));
+                }
+                ShapeItem::Stroke { color: animated(item.get("c"), &[0.0, 0.0, 0.0, 1.0], numbers), opacity: number_property(item, "o", 100.0), width: number_property(item, "w", 1.0) }
+            }
+            "tm" =>
+            {
+                if item.get("m").and_then(Value::as_f64) == Some(2.0)
+                {
+                    report(unsupported, format!("Trimming paths one after another in {} (each path gets trimmed on its own)", context));
+                }
+                ShapeItem::Trim { start: number_property(item, "s", 0.0), end: number_property(item, "e", 100.0), offset: number_property(item, "o", 0.0) }
+            }
+            "tr" => continue, // Read by the group
+            _ =>
+            {
+                report(unsupported, format!("Shape {} ({}) in {}", name, kind, context));
+                continue;
+            }
+        };
+        shapes.push(shape);
+    }
+    shapes
+}
+
+#[derive(Clone, Debug)]
+enum LayerKind
+{
+    Shapes(Vec<ShapeItem>),
+    Solid { color: [f32; 4], size: (f32, f32) },
+    Precomp(String),
+    Null
+}
+
+#[derive(Clone, Debug)]
+struct Layer
+{
+    index: Option<i64>,
+    parent: Option<i64>,
+    in_frame: f32,
+    out_frame: f32,
+    start: f32,
+    stretch: f32,
+    transform: LottieTransform,
+    kind: LayerKind
+}
+
+fn parse_layers(layers: Option<&Value>, context: &str, unsupported: &mut Vec<String>) -> Vec<Layer>
+{
+    let mut parsed = Vec::new();
+    for layer in layers.and_then(Value::as_array).into_iter().flatten()
+    {
+        let name = layer.get("nm").and_then(Value::as_str).unwrap_or("unnamed");
+        let context = format!("layer {} of {}", name, context);
+        if layer.get("hd").and_then(Value::as_bool) == Some(true)
+        {
+            continue;
+        }
+
+        let flag = |key: &str| layer.get(key).and_then(Value::as_f64).is_some_and(|v| v != 0.0);
+        for (key, feature) in [("tt", "Track matte"), ("ao", "Auto orient"), ("ddd", "3D"), ("bm", "Blend mode"), ("td", "Matte source")]
+        {
+            if flag(key)
+            {
+                report(unsupported, format!("{} in {}", feature, context));
+            }
+        }
+        for (key, feature) in [("masksProperties", "Masks"), ("ef", "Effects")]
+        {
+            if layer.get(key).and_then(Value::as_array).is_some_and(|list| !list.is_empty())
+            {
+                report(unsupported, format!("{} in {}", feature, context));
+            }
+        }
+        if layer.get("tm").is_some()
+        {
+            report(unsupported, format!("Time remapping in {}", context));
+        }
+
+        let kind = match layer.get("ty").and_then(Value::as_i64)
+        {
+            Some(0) => LayerKind::Precomp(layer.get("refId").and_then(Value::as_str).unwrap_or("").to_string()),
+            Some(1) =>
+            {
+                let size = (layer.get("sw").and_then(Value::as_f64).unwrap_or(0.0) as f32, layer.get("sh").and_then(Value::as_f64).unwrap_or(0.0) as f32);
+                LayerKind::Solid { color: hex_color(layer.get("sc").and_then(Value::as_str).unwrap_or("#000000")), size }
+            }
+            Some(3) => LayerKind::Null,
+            Some(4) => LayerKind::Shapes(parse_shapes(layer.get("shapes"), &context, unsupported)),
+            kind =>
+            {
+                let kind = match kind { Some(2) => "Image", Some(5) => "Text", Some(6) => "Audio", _ => "Unknown" };
+                report(unsupported, format!("{} {}", kind, context));
+                continue;
+            }
+        };
+
+        parsed.push(Layer
+        {
+            index: layer.get("ind").and_then(Value::as_i64),
+            parent: layer.get("parent").and_then(Value::as_i64),
+            in_frame: layer.get("ip").and_then(Value::as_f64).unwrap_or(0.0) as f32,
+            out_frame: layer.get("op").and_then(Value::as_f64).unwrap_or(f64::MAX) as f32,
+            start: layer.get("st").and_then(Value::as_f64).unwrap_or(0.0) as f32,
+            stretch: layer.get("sr").and_then(Value::as_f64).unwrap_or(1.0) as f32,
+            transform: LottieTransform::parse(layer.get("ks"), &context, unsupported),
+            kind
+        });
+    }
+    parsed
+}
+
+fn hex_color(hex: &str) -> [f32; 4]
+{
+    let hex = hex.trim_start_matches('#');
+    let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok()).unwrap_or(0) as f32 / 255.0;
+    [channel(0), channel(2), channel(4), 1.0]
+}
+
+// Without duplicates, the same feature is often used on many layers
+fn report(unsupported: &mut Vec<String>, feature: String)
+{
+    if !unsupported.contains(&feature)
+    {
+        unsupported.push(feature);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Paint
+{
+    Fill([f32; 4], FillRule),
+    Stroke([f32; 4], f32) // Color and width
+}
+
+// One fill or stroke of a frame, in the order they are drawn (bottom first)
+#[derive(Clone, Debug, PartialEq)]
+pub struct LottieShape
+{
+    pub polylines: Vec<(Vec<(f32, f32)>, bool)>, // Points and whether the polyline is closed, before matrix
+    pub matrix: Matrix3<f32>, // Into composition pixels, y down
+    pub paint: Paint
+}
+
+// Parsed Lottie file, evaluated at any frame
+pub struct Lottie
+{
+    pub width: f32,
+    pub height: f32,
+    pub frame_rate: f32,
+    pub in_frame: f32,
+    pub out_frame: f32,
+    pub unsupported: Vec<String>, // Compatibility report, empty if everything in the file is supported
+    layers: Vec<Layer>,
+    precomps: HashMap<String, Vec<Layer>>
+}
+
+impl Lottie
+{
+    pub fn load(path: &str) -> Result<Self>
+    {
+        let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        Self::parse(&json)
+    }
+
+    pub fn parse(json: &str) -> Result<Self>
+    {
+        let root: Value = serde_json::from_str(json)?;
+        let number = |key: &str| root.get(key).and_then(Value::as_f64).map(|v| v as f32).ok_or_else(|| anyhow!("Lottie file has no {}", key));
+
+        let mut unsupported = Vec::new();
+        let mut precomps = HashMap::new();
+        for asset in root.get("assets").and_then(Value::as_array).into_iter().flatten()
+        {
+            let id = asset.get("id").and_then(Value::as_str).unwrap_or("");
+            match asset.get("layers")
+            {
+                Some(layers) => { precomps.insert(id.to_string(), parse_layers(Some(layers), &format!("precomp {}", id), &mut unsupported)); }
+                None => report(&mut unsupported, format!("Image asset {}", id))
+            }
+        }
+        let layers = parse_layers(root.get("layers"), "the composition", &mut unsupported);
+
+        Ok(Self
+        {
+            width: number("w")?,
+            height: number("h")?,
+            frame_rate: number("fr")?,
+            in_frame: number("ip")?,
+            out_frame: number("op")?,
+            unsupported,
+            layers,
+            precomps
+        })
+    }
+
+    // Seconds from in_frame to out_frame
+    pub fn duration(&self) -> f32
+    {
+        (self.out_frame - self.in_frame) / self.frame_rate
+    }
+
+    // Every fill and stroke at frame (of the file, not from in_frame), bottom first
+    pub fn evaluate(&self, frame: f32) -> Vec<LottieShape>
+    {
+        let mut shapes = Vec::new();
+        self.evaluate_layers(&self.layers, frame, Matrix3::identity(), 1.0, 0, &mut shapes);
+        shapes
+    }
+
+    fn evaluate_layers(&self, layers: &[Layer], frame: f32, matrix: Matrix3<f32>, opacity: f32, depth: usize, shapes: &mut Vec<LottieShape>)
+    {
+        // Precomps that contain themselves
+        if depth > 16
+        {
+            return;
+        }
+
+        // The first layer is on top
+        for layer in layers.iter().rev()
+        {
+            if frame < layer.in_frame || frame >= layer.out_frame
+            {
+                continue;
+            }
+
+            let local = (frame - layer.start) / layer.stretch;
+            let layer_matrix = matrix * self.layer_matrix(layers, layer, frame, 0);
+            let opacity = opacity * layer.transform.opacity(local);
+
+            match &layer.kind
+            {
+                LayerKind::Shapes(items) => render_items(items, local, layer_matrix, opacity, shapes),
+                LayerKind::Solid { color, size } =>
+                {
+                    let polylines = Path::rect((0.0, 0.0, size.0, size.1)).flatten_polylines(DEFAULT_TOLERANCE);
+                    shapes.push(LottieShape { polylines, matrix: layer_matrix, paint: Paint::Fill([color[0], color[1], color[2], color[3] * opacity], FillRule::NonZero) });
+                }
+                LayerKind::Precomp(id) =>
+                {
+                    if let Some(precomp) = self.precomps.get(id)
+                    {
+                        self.evaluate_layers(precomp, local, layer_matrix, opacity, depth + 1, shapes);
+                    }
+                }
+                LayerKind::Null => {}
+            }
+        }
+    }
+
+    // Local matrix with the ones of its parents, parents do not pass on their opacity
+    // frame is the one of the composition, every layer has its own start and stretch
+    fn layer_matrix(&self, layers: &[Layer], layer: &Layer, frame: f32, depth: usize) -> Matrix3<f32>
+    {
+        let local = layer.transform.matrix((frame - layer.start) / layer.stretch);
+        let parent = layer.parent.and_then(|parent| layers.iter().find(|other| other.index == Some(parent)));
+        match parent
+        {
+            Some(parent) if depth < 32 => self.layer_matrix(layers, parent, frame, depth + 1) * local,
+            _ => local
+        }
+    }
+}
+
+// Items further down the list are below, a fill or stroke paints every path above it in its group, including the ones of inner groups
+fn render_items(items: &[ShapeItem], frame: f32, matrix: Matrix3<f32>, opacity: f32, shapes: &mut Vec<LottieShape>)
+{
+    for (i, item) in items.iter().enumerate().rev()
+    {
+        match item
+        {
+            ShapeItem::Group { items, transform } => render_items(items, frame, matrix * transform.matrix(frame), opacity * transform.opacity(frame), shapes),
+            ShapeItem::Fill { color, opacity: fill_opacity, rule } =>
+            {
+                let polylines = collect_paths(&items[..i], frame);
+                shapes.push(LottieShape { polylines, matrix, paint: Paint::Fill(paint_color(color, fill_opacity, opacity, frame), *rule) });
+            }
+            ShapeItem::Stroke { color, opacity: stroke_opacity, width } =>
+            {
+                let polylines = collect_paths(&items[..i], frame);
+                shapes.push(LottieShape { polylines, matrix, paint: Paint::Stroke(paint_color(color, stroke_opacity, opacity, frame), width.number(frame)) });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn paint_color(color: &Animated, paint_opacity: &Animated, opacity: f32, frame: f32) -> [f32; 4]
+{
+    let color = color.value(frame);
+    // Some exporters write 0..255
+    let divisor = if color.iter().take(3).any(|c| *c > 1.0) { 255.0 } else { 1.0 };
+    let channel = |i: usize| color.get(i).copied().unwrap_or(0.0) / divisor;
+    [channel(0), channel(1), channel(2), opacity * paint_opacity.number(frame) / 100.0]
+}
+
+// Paths of the items in the space of their group, trimmed by the trim paths after them
+fn collect_paths(items: &[ShapeItem], frame: f32) -> Vec<(Vec<(f32, f32)>, bool)>
+{
+    let mut polylines = Vec::new();
+    for item in items
+    {
+        match item
+        {
+            ShapeItem::Group { items, transform } =>
+            {
+                let matrix = transform.matrix(frame);
+                for (points, closed) in collect_paths(items, frame)
+                {
+                    let points = points.iter().map(|p|
+                    {
+                        let p = matrix * Vector3::new(p.0, p.1, 1.0);
+                        (p.x, p.y)
+                    }).collect();
+                    polylines.push((points, closed));
+                }
+            }
+            ShapeItem::Path(data) => polylines.extend(bezier_path(&data.value(frame)).flatten_polylines(DEFAULT_TOLERANCE)),
+            ShapeItem::Rect { position, size, roundness, clockwise } => polylines.extend(rect_path(position.pair(frame), size.pair(frame), roundness.number(frame), *clockwise).flatten_polylines(DEFAULT_TOLERANCE)),
+            ShapeItem::Ellipse { position, size, clockwise } => polylines.extend(ellipse_path(position.pair(frame), size.pair(frame), *clockwise).flatten_polylines(DEFAULT_TOLERANCE)),
+            ShapeItem::Trim { start, end, offset } =>
+            {
+                let (start, end) = (start.number(frame) / 100.0, end.number(frame) / 100.0);
+                let offset = offset.number(frame) / 360.0;
+                polylines = polylines.iter().flat_map(|(points, closed)| trim(points, *closed, start.min(end) + offset, start.max(end) + offset)).collect();
+            }
+            ShapeItem::Fill { .. } | ShapeItem::Stroke { .. } => {}
+        }
+    }
+    polylines
+}
+
+fn bezier_path(values: &[f32]) -> Path
+{
+    let closed = values.first() == Some(&1.0);
+    let vertices: Vec<&[f32]> = values.get(1..).unwrap_or(&[]).chunks_exact(6).collect();
+    let Some(first) = vertices.first() else { return Path::new() };
+
+    let mut path = Path::new().move_to((first[0], first[1]));
+    let segments = if closed { vertices.len() } else { vertices.len() - 1 };
+    for n in 0..segments
+    {
+        let (a, b) = (vertices[n], vertices[(n + 1) % vertices.len()]);
+        // Tangents are relative to their vertex, without them the segment is a line
+        path = if [a[4], a[5], b[2], b[3]] == [0.0; 4]
+        {
+            path.line_to((b[0], b[1]))
+        }
+        else
+        {
+            path.cubic_to((a[0] + a[4], a[1] + a[5]), (b[0] + b[2], b[1] + b[3]), (b[0], b[1]))
+        };
+    }
+    if closed { path.close() } else { path }
+}
+
+// Closed path through the vertices (x, y, in x, in y, out x, out y), given clockwise on screen
+// Counter-clockwise keeps the first vertex and goes around the other way, like After Effects
+fn closed_path(mut vertices: Vec<[f32; 6]>, clockwise: bool) -> Path
+{
+    if !clockwise
+    {
+        vertices[1..].reverse();
+        for vertex in &mut vertices
+        {
+            vertex.swap(2, 4);
+            vertex.swap(3, 5);
+        }
+    }
+    let values: Vec<f32> = std::iter::once(1.0).chain(vertices.into_iter().flatten()).collect();
+    bezier_path(&values)
+}
+
+// Rectangle around position with rounded corners, starts at the top right corner and goes down first when clockwise
+fn rect_path(position: (f32, f32), size: (f32, f32), roundness: f32, clockwise: bool) -> Path
+{
+    let (x, y) = (position.0 - size.0 / 2.0, position.1 - size.1 / 2.0);
+    let (right, bottom) = (x + size.0, y + size.1);
+    let r = roundness.min(size.0 / 2.0).min(size.1 / 2.0);
+    if r <= 0.0
+    {
+        return closed_path(vec![[right, y, 0.0, 0.0, 0.0, 0.0], [right, bottom, 0.0, 0.0, 0.0, 0.0], [x, bottom, 0.0, 0.0, 0.0, 0.0], [x, y, 0.0, 0.0, 0.0, 0.0]], clockwise);
+    }
+
+    // Handle length of a quarter circle
+    let k = r * 0.552_284_8;
+    closed_path(vec!
+    [
+        [right, y + r, 0.0, -k, 0.0, 0.0],
+        [right, bottom - r, 0.0, 0.0, 0.0, k],
+        [right - r, bottom, k, 0.0, 0.0, 0.0],
+        [x + r, bottom, 0.0, 0.0, -k, 0.0],
+        [x, bottom - r, 0.0, k, 0.0, 0.0],
+        [x, y + r, 0.0, 0.0, 0.0, -k],
+        [x + r, y, -k, 0.0, 0.0, 0.0],
+        [right - r, y, 0.0, 0.0, k, 0.0]
+    ], clockwise)
+}
+
+// Ellipse of size around position, starts at the top and goes right first when clockwise
+fn ellipse_path(position: (f32, f32), size: (f32, f32), clockwise: bool) -> Path
+{
+    let (cx, cy) = position;
+    let (rx, ry) = (size.0 / 2.0, size.1 / 2.0);
+    let (kx, ky) = (rx * 0.552_284_8, ry * 0.552_284_8);
+    closed_path(vec!
+    [
+        [cx, cy - ry, -kx, 0.0, kx, 0.0],
+        [cx + rx, cy, 0.0, -ky, 0.0, ky],
+        [cx, cy + ry, kx, 0.0, -kx, 0.0],
+        [cx - rx, cy, 0.0, ky, 0.0, -ky]
+    ], clockwise)
+}
+
+// Part of the polyline from start to end, both as a fraction of its length, can wrap around the end of closed polylines
+fn trim(points: &[(f32, f32)], closed: bool, start: f32, end: f32) -> Vec<(Vec<(f32, f32)>, bool)>
+{
+    if end - start >= 1.0
+    {
+        return vec![(points.to_vec(), closed)];
+    }
+    if end <= start || points.len() < 2
+    {
+        return Vec::new();
+    }
+
+    let mut line = points.to_vec();
+    if closed
+    {
+        line.push(points[0]);
+    }
+    let lengths: Vec<f32> = line.windows(2).map(|w| ((w[1].0 - w[0].0).powi(2) + (w[1].1 - w[0].1).powi(2)).sqrt()).collect();
+    let total: f32 = lengths.iter().sum();
+    if total <= 0.0
+    {
+        return Vec::new();
+    }
+
+    // Points between two distances along the polyline
+    let part = |from: f32, to: f32|
+    {
+        let mut result = Vec::new();
+        let mut walked = 0.0;
+        for (i, length) in lengths.iter().enumerate()
+        {
+            let (a, b) = (line[i], line[i + 1]);
+            let at = |distance: f32|
+            {
+                let t = ((distance - walked) / length.max(f32::EPSILON)).clamp(0.0, 1.0);
+                (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+            };
+
+            if walked + length >= from && (walked < to || result.is_empty())
+            {
+                if result.is_empty()
+                {
+                    result.push(at(from));
+                }
+                result.push(at(to.min(walked + length)));
+            }
+            walked += length;
+        }
+        result
+    };
+
+    // The offset can move the part past the end
+    let (start, end) = (start.rem_euclid(1.0), start.rem_euclid(1.0) + end - start);
+    if end <= 1.0
+    {
+        vec![(part(start * total, end * total), false)]
+    }
+    else if closed
+    {
+        // Across the start point, one piece
+        let mut wrapped = part(start * total, total);
+        wrapped.extend(part(0.0, (end - 1.0) * total).into_iter().skip(1));
+        vec![(wrapped, false)]
+    }
+    else
+    {
+        vec![(part(start * total, total), false), (part(0.0, (end - 1.0) * total), false)]
+    }
+}
+
+// Plays a Lottie file, owned by the game, updated in update and drawn in render
+pub struct LottiePlayer
+{
+    pub animation: Lottie,
+    pub speed: f32,
+    pub looping: bool,
+    time: f32, // Seconds since in_frame
+    playing: bool,
+    meshes: Vec<PathMesh> // One per shape, the most any frame has
+}
+
+impl LottiePlayer
+{
+    pub fn new(loader: &mut dyn Loader, animation: Lottie) -> Self
+    {
+        // Every frame is evaluated once to know how many meshes are needed
+        let frames = (animation.out_frame - animation.in_frame).max(0.0) as usize;
+        let mut sizes: Vec<usize> = Vec::new();
+        for frame in 0..=frames
+        {
+            for (i, shape) in animation.evaluate(animation.in_frame + frame as f32).iter().enumerate()
+            {
+                let points: usize = shape.polylines.iter().map(|(points, _)| points.len()).sum();
+                let vertices = if matches!(shape.paint, Paint::Stroke(..)) { points * 2 } else { points };
+                match sizes.get_mut(i)
+                {
+                    Some(size) => *size = (*size).max(vertices),
+                    None => sizes.push(vertices)
+                }
+            }
+        }
+
+        let meshes = sizes.into_iter().map(|size| PathMesh::new(loader, size)).collect();
+        Self { animation, speed: 1.0, looping: true, time: 0.0, playing: true, meshes }
+    }
+
+    pub fn play(&mut self)
+    {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self)
+    {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool
+    {
+        self.playing
+    }
+
+    pub fn time(&self) -> f32
+    {
+        self.time
+    }
+
+    pub fn seek(&mut self, time: f32)
+    {
+        self.time = time.clamp(0.0, self.animation.duration());
+    }
+
+    // Frame of the file, with fractions between frames
+    pub fn frame(&self) -> f32
+    {
+        self.animation.in_frame + self.time * self.animation.frame_rate
+    }
+
+    pub fn update(&mut self, dt: f64)
+    {
+        if !self.playing
+        {
+            return;
+        }
+
+        let duration = self.animation.duration();
+        self.time += dt as f32 * self.speed;
+        if self.looping && duration > 0.0
+        {
+            self.time = self.time.rem_euclid(duration);
+        }
+        // Only the end it is heading to stops it, so playing from the other end works
+        else if (self.speed > 0.0 && self.time >= duration) || (self.speed < 0.0 && self.time <= 0.0)
+        {
+            self.time = self.time.clamp(0.0, duration);
+            self.playing = false;
+        }
+    }
+
+    // root places the composition, scale 1 = its pixels, everything at z_index in the order of the file
+    pub fn draw(&self, renderer: &mut Renderer, root: &Transform2D, z_index: u32)
+    {
+        // The last frame is out_frame - 1, out_frame itself is already empty
+        let frame = self.frame().min(self.animation.out_frame - 0.001);
+        let root_matrix = root.matrix();
+
+        for (shape, mesh) in self.animation.evaluate(frame).iter().zip(&self.meshes)
+        {
+            let transform = Transform2D { parent: root_matrix * shape.matrix, ..Transform2D::identity(root.space) };
+            match shape.paint
+            {
+                Paint::Fill(color, rule) =>
+                {
+                    let contours: Vec<Vec<(f32, f32)>> = shape.polylines.iter().map(|(points, _)| points.clone()).collect();
+                    mesh.draw_contours_with_holes(renderer, &contours, rule, transform, color, z_index);
+                }
+                Paint::Stroke(color, width) => mesh.draw_stroke(renderer, &shape.polylines, width, transform, color, z_index)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // One shape layer that moves from 0,0 to 30,60 over the second
+    fn composition(shapes: &str) -> Lottie
+    {
+        Lottie::parse(&format!(r#"{{
+            "w": 100, "h": 100, "fr": 30, "ip": 0, "op": 30,
+            "layers": [{{ "ty": 4, "nm": "shapes", "ks": {{ "p": {{ "a": 1, "k": [{{ "t": 0, "s": [0, 0] }}, {{ "t": 30, "s": [30, 60] }}] }} }}, "shapes": {} }}]
+        }}"#, shapes)).unwrap()
+    }
+
+    const STROKE: &str = r#"{ "ty": "st", "c": { "k": [0, 0, 0, 1] }, "o": { "k": 100 }, "w": { "k": 2 } }"#;
+    const FILL: &str = r#"{ "ty": "fl", "c": { "k": [1, 0, 0, 1] }, "o": { "k": 50 } }"#;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool
+    {
+        (a.0 - b.0).abs() < 0.5 && (a.1 - b.1).abs() < 0.5
+    }
+
+    #[test]
+    fn unsupported_features_are_reported()
+    {
+        let lottie = composition(r#"[{ "ty": "gf", "nm": "Glow" }]"#);
+        assert_eq!(lottie.duration(), 1.0);
+        assert_eq!(lottie.unsupported, vec!["Shape Glow (gf) in layer shapes of the composition".to_string()]);
+        assert!(lottie.evaluate(0.0).is_empty());
+    }
+
+    #[test]
+    fn keyframes_move_the_layer()
+    {
+        let lottie = composition(&format!(r#"[{{ "ty": "rc", "p": {{ "k": [0, 0] }}, "s": {{ "k": [10, 10] }} }}, {}]"#, FILL));
+        let shapes = lottie.evaluate(15.0);
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(shapes[0].paint, Paint::Fill([1.0, 0.0, 0.0, 0.5], FillRule::NonZero));
+
+        let origin = shapes[0].matrix * Vector3::new(0.0, 0.0, 1.0);
+        assert!(close((origin.x, origin.y), (15.0, 30.0)));
+    }
+
+    #[test]
+    fn rectangles_start_at_the_top_right()
+    {
+        let rect = |direction: u32| composition(&format!(r#"[{{ "ty": "rc", "d": {}, "p": {{ "k": [50, 50] }}, "s": {{ "k": [20, 10] }} }}, {}]"#, direction, FILL)).evaluate(0.0)[0].polylines[0].0.clone();
+        assert_eq!(rect(1)[..2], [(60.0, 45.0), (60.0, 55.0)]);
+        assert_eq!(rect(3)[..2], [(60.0, 45.0), (40.0, 45.0)]);
+    }
+
+    #[test]
+    fn trim_paths_start_at_the_top_of_ellipses()
+    {
+        let trimmed = |direction: u32|
+        {
+            let shapes = format!(r#"[{{ "ty": "el", "d": {}, "p": {{ "k": [50, 50] }}, "s": {{ "k": [40, 20] }} }}, {{ "ty": "tm", "s": {{ "k": 0 }}, "e": {{ "k": 25 }}, "o": {{ "k": 0 }} }}, {}]"#, direction, STROKE);
+            let (points, closed) = composition(&shapes).evaluate(0.0)[0].polylines[0].clone();
+            assert!(!closed);
+            (points[0], *points.last().unwrap())
+        };
+
+        let (start, end) = trimmed(1);
+        assert!(close(start, (50.0, 40.0)) && close(end, (70.0, 50.0)), "{:?} {:?}", start, end);
+        let (start, end) = trimmed(3);
+        assert!(close(start, (50.0, 40.0)) && close(end, (30.0, 50.0)), "{:?} {:?}", start, end);
+    }
+
+    #[test]
+    fn fills_keep_their_rule()
+    {
+        let ring = r#"{ "ty": "el", "p": { "k": [50, 50] }, "s": { "k": [40, 40] } }, { "ty": "el", "p": { "k": [50, 50] }, "s": { "k": [20, 20] } }"#;
+        let shapes = composition(&format!(r#"[{}, {{ "ty": "fl", "r": 2, "c": {{ "k": [1, 1, 1, 1] }} }}]"#, ring)).evaluate(0.0);
+        assert_eq!(shapes[0].polylines.len(), 2);
+        assert_eq!(shapes[0].paint, Paint::Fill([1.0; 4], FillRule::EvenOdd));
+    }
+
+    #[test]
+    fn parents_use_their_own_start()
+    {
+        // The parent starts 10 frames later, so at frame 20 it is only a third of the way
+        let lottie = Lottie::parse(r#"{
+            "w": 100, "h": 100, "fr": 30, "ip": 0, "op": 30,
+            "layers": [
+                { "ty": 4, "nm": "child", "parent": 1, "ks": {}, "shapes": [{ "ty": "rc", "p": { "k": [0, 0] }, "s": { "k": [10, 10] } }, { "ty": "fl", "c": { "k": [1, 1, 1, 1] } }] },
+                { "ty": 3, "nm": "parent", "ind": 1, "st": 10, "ks": { "p": { "a": 1, "k": [{ "t": 0, "s": [0, 0] }, { "t": 30, "s": [30, 0] }] } } }
+            ]
+        }"#).unwrap();
+
+        let origin = lottie.evaluate(20.0)[0].matrix * Vector3::new(0.0, 0.0, 1.0);
+        assert!(close((origin.x, origin.y), (10.0, 0.0)), "{:?}", origin);
+    }
+
+    #[test]
+    fn players_stop_at_the_end_they_play_towards()
+    {
+        let mut player = LottiePlayer { animation: composition("[]"), speed: 1.0, looping: false, time: 0.0, playing: true, meshes: Vec::new() };
+        player.update(0.0);
+        assert!(player.is_playing());
+
+        player.speed = -1.0;
+        player.seek(1.0);
+        player.update(0.0);
+        assert!(player.is_playing());
+        player.update(2.0);
+        assert_eq!(player.time, 0.0);
+        assert!(!player.is_playing());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{path::{self, Path, PathMesh, DEFAULT_TOLERANCE}, renderer::Renderer, transform::DrawTransform, tween::{Ease, Lerp, Tween}};
+
+// Points along the closed contour at the same distance from each other, starting at its first point
+pub fn resample(contour: &[(f32, f32)], count: usize) -> Vec<(f32, f32)>
+{
+    let edge = |i: usize| (contour[i], contour[(i + 1) % contour.len()]);
+    let edge_length = |(a, b): ((f32, f32), (f32, f32))| ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+    let total: f32 = (0..contour.len()).map(|i| edge_length(edge(i))).sum();
+    if contour.len() < 2 || total <= 0.0
+    {
+        return vec![contour.first().copied().unwrap_or((0.0, 0.0)); count];
+    }
+
+    let step = total / count as f32;
+    let mut points = Vec::with_capacity(count);
+    let (mut i, mut walked) = (0, 0.0); // Edge and the length of the edges before it
+    for n in 0..count
+    {
+        let distance = n as f32 * step;
+        while i < contour.len() - 1 && walked + edge_length(edge(i)) < distance
+        {
+            walked += edge_length(edge(i));
+            i += 1;
+        }
+
+        let (a, b) = edge(i);
+        let t = ((distance - walked) / edge_length(edge(i)).max(f32::EPSILON)).clamp(0.0, 1.0);
+        points.push(a.lerp(&b, t));
+    }
+    points
+}
+
+// Turns to the same direction as reference and starts at the point closest to the first point of reference, so the morph does not twist
+fn align(contour: &mut [(f32, f32)], reference: &[(f32, f32)])
+{
+    if (path::signed_area(contour) >= 0.0) != (path::signed_area(reference) >= 0.0)
+    {
+        contour.reverse();
+    }
+
+    let count = contour.len();
+    let distance = |offset: usize| (0..count).map(|i|
+    {
+        let (a, b) = (contour[(i + offset) % count], reference[i]);
+        (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+    }).sum::<f32>();
+
+    if let Some(offset) = (0..count).min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+    {
+        contour.rotate_left(offset);
+    }
+}
+
+fn centroid(contour: &[(f32, f32)]) -> (f32, f32)
+{
+    let sum = contour.iter().fold((0.0, 0.0), |sum, p| (sum.0 + p.0, sum.1 + p.1));
+    (sum.0 / contour.len().max(1) as f32, sum.1 / contour.len().max(1) as f32)
+}
+
+// Morph between two paths with any number of points, contours are paired in order
+// A contour without a partner grows out of or shrinks into its own center
+pub struct ShapeMorph
+{
+    from: Vec<Vec<(f32, f32)>>,
+    to: Vec<Vec<(f32, f32)>>,
+    pub tween: Tween<f32> // Progress from 0 to 1, for easing, delay, repeat and yoyo
+}
+
+impl ShapeMorph
+{
+    // samples = points per contour, more keep curves smooth during the morph
+    pub fn new(from: &Path, to: &Path, samples: usize, duration: f32) -> Self
+    {
+        let from_contours = from.flatten(DEFAULT_TOLERANCE);
+        let to_contours = to.flatten(DEFAULT_TOLERANCE);
+
+        let mut from = Vec::new();
+        let mut to = Vec::new();
+        for i in 0..from_contours.len().max(to_contours.len())
+        {
+            let (start, mut end) = match (from_contours.get(i), to_contours.get(i))
+            {
+                (Some(a), Some(b)) => (resample(a, samples), resample(b, samples)),
+                (Some(a), None) => (resample(a, samples), vec![centroid(a); samples]),
+                (None, Some(b)) => (vec![centroid(b); samples], resample(b, samples)),
+                (None, None) => unreachable!()
+            };
+            align(&mut end, &start);
+            from.push(start);
+            to.push(end);
+        }
+
+        Self { from, to, tween: Tween::new(0.0, 1.0, duration) }
+    }
+
+    pub fn with_ease(mut self, ease: Ease) -> Self
+    {
+        self.tween = self.tween.with_ease(ease);
+        self
+    }
+
+    // Morphs back and forth, see Tween::with_repeat and Tween::with_yoyo
+    pub fn with_yoyo(mut self, repeat: u32) -> Self
+    {
+        self.tween = self.tween.with_repeat(repeat).with_yoyo(true);
+        self
+    }
+
+    pub fn update(&mut self, dt: f64)
+    {
+        self.tween.update(dt);
+    }
+
+    pub fn is_finished(&self) -> bool
+    {
+        self.tween.is_finished()
+    }
+
+    // Eased progress right now
+    pub fn progress(&self) -> f32
+    {
+        self.tween.value()
+    }
+
+    // Shape at the current progress
+    pub fn contours(&self) -> Vec<Vec<(f32, f32)>>
+    {
+        self.contours_at(self.progress())
+    }
+
+    // Shape at t, 0 = from, 1 = to
+    pub fn contours_at(&self, t: f32) -> Vec<Vec<(f32, f32)>>
+    {
+        self.from.iter().zip(&self.to).map(|(from, to)| from.lerp(to, t)).collect()
+    }
+
+    // Points of the shape, enough room for PathMesh::new
+    pub fn point_count(&self) -> usize
+    {
+        self.from.iter().map(Vec::len).sum()
+    }
+
+    // Tessellates the current shape into mesh and fills it with color
+    pub fn draw(&self, renderer: &mut Renderer, mesh: &PathMesh, transform: impl DrawTransform, color: [f32; 4], z_index: u32)
+    {
+        mesh.draw_contours(renderer, &self.contours(), transform, color, z_index);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const SQUARE: [(f32, f32); 4] = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+
+    fn distance(a: (f32, f32), b: (f32, f32)) -> f32
+    {
+        ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+    }
+
+    #[test]
+    fn resampled_points_are_equally_spaced()
+    {
+        assert_eq!(resample(&SQUARE, 8), vec![(0.0, 0.0), (5.0, 0.0), (10.0, 0.0), (10.0, 5.0), (10.0, 10.0), (5.0, 10.0), (0.0, 10.0), (0.0, 5.0)]);
+
+        // Edges of different lengths, including the closing one
+        let triangle = [(0.0, 0.0), (30.0, 0.0), (0.0, 40.0)];
+        let points = resample(&triangle, 12);
+        for i in 0..points.len()
+        {
+            assert!((distance(points[i], points[(i + 1) % points.len()]) - 10.0).abs() < 1e-3, "{:?}", points);
+        }
+    }
+
+    #[test]
+    fn resampling_keeps_the_count()
+    {
+        for count in [0, 1, 3, 7, 100]
+        {
+            assert_eq!(resample(&SQUARE, count).len(), count);
+        }
+    }
+
+    #[test]
+    fn degenerate_contours_resample_to_one_point()
+    {
+        assert_eq!(resample(&[], 3), vec![(0.0, 0.0); 3]);
+        assert_eq!(resample(&[(4.0, 2.0)], 3), vec![(4.0, 2.0); 3]);
+        assert_eq!(resample(&[(4.0, 2.0); 5], 3), vec![(4.0, 2.0); 3]);
+
+        // Edges without length are skipped
+        let doubled = [(0.0, 0.0), (10.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 10.0)];
+        assert_eq!(resample(&doubled, 8), resample(&SQUARE, 8));
+    }
+
+    #[test]
+    fn aligned_contours_turn_the_same_way_and_start_at_the_closest_point()
+    {
+        let reference = resample(&SQUARE, 8);
+
+        let mut rotated = reference.clone();
+        rotated.rotate_left(3);
+        align(&mut rotated, &reference);
+        assert_eq!(rotated, reference);
+
+        let mut reversed = reference.clone();
+        reversed.reverse();
+        reversed.rotate_left(5);
+        align(&mut reversed, &reference);
+        assert_eq!(reversed, reference);
+    }
+
+    #[test]
+    fn contours_without_a_partner_collapse_into_their_center()
+    {
+        let small = [(20.0, 20.0), (24.0, 20.0), (24.0, 24.0), (20.0, 24.0)];
+        let from = Path::polygon(&SQUARE);
+        let to = Path::polygon(&small);
+        let two = Path::new().move_to(SQUARE[0]).line_to(SQUARE[1]).line_to(SQUARE[2]).line_to(SQUARE[3]).close()
+            .move_to(small[0]).line_to(small[1]).line_to(small[2]).line_to(small[3]).close();
+
+        let morph = ShapeMorph::new(&two, &from, 8, 1.0);
+        assert_eq!(morph.point_count(), 16);
+        assert_eq!(morph.contours_at(0.0)[1], resample(&small, 8));
+        assert_eq!(morph.contours_at(1.0), vec![resample(&SQUARE, 8), vec![(22.0, 22.0); 8]]);
+
+        // Grows out of the center the other way around
+        let morph = ShapeMorph::new(&to, &two, 8, 1.0);
+        assert_eq!(morph.contours_at(0.0)[1], vec![(22.0, 22.0); 8]);
+        assert_eq!(morph.contours_at(0.5)[1][0], (21.0, 21.0));
+    }
+}
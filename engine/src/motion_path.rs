@@ -0,0 +1,267 @@
+use std::f32::consts::PI;
+
+use crate::{path::Path, transform::Transform2D, tween::{Ease, Lerp, Tween}};
+
+// Path flattening for motion, finer than for filling so the speed stays even on tight curves
+pub const MOTION_TOLERANCE: f32 = 0.05;
+
+// Polyline measured along its length, so a distance or fraction of it is a point at constant speed
+// Contours of a path are followed one after another, the jump between them takes no time
+pub struct MotionPath
+{
+    segments: Vec<((f32, f32), (f32, f32))>,
+    distances: Vec<f32>, // Length of the segments before each segment
+    length: f32
+}
+
+impl MotionPath
+{
+    pub fn new(path: &Path) -> Self
+    {
+        Self::from_polylines(&path.flatten_polylines(MOTION_TOLERANCE))
+    }
+
+    // Points in pixels, y down, closed goes back to the first point at the end
+    pub fn polyline(points: &[(f32, f32)], closed: bool) -> Self
+    {
+        Self::from_polylines(&[(points.to_vec(), closed)])
+    }
+
+    fn from_polylines(polylines: &[(Vec<(f32, f32)>, bool)]) -> Self
+    {
+        let mut segments = Vec::new();
+        for (points, closed) in polylines
+        {
+            segments.extend(points.windows(2).map(|w| (w[0], w[1])));
+            if *closed && let (Some(last), Some(first)) = (points.last(), points.first())
+            {
+                segments.push((*last, *first));
+            }
+        }
+        // Points on top of each other have no direction
+        segments.retain(|(a, b)| a != b);
+
+        let mut distances = Vec::with_capacity(segments.len());
+        let mut length = 0.0;
+        for (a, b) in &segments
+        {
+            distances.push(length);
+            length += ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        }
+
+        Self { segments, distances, length }
+    }
+
+    pub fn length(&self) -> f32
+    {
+        self.length
+    }
+
+    // Point and direction (radians, counter-clockwise on screen like Transform2D::rotation) distance pixels along the path
+    pub fn sample_distance(&self, distance: f32) -> ((f32, f32), f32)
+    {
+        if self.segments.is_empty()
+        {
+            return ((0.0, 0.0), 0.0);
+        }
+
+        let distance = distance.clamp(0.0, self.length);
+        let i = self.distances.partition_point(|start| *start <= distance).max(1) - 1;
+        let (a, b) = self.segments[i];
+        let segment = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        let t = ((distance - self.distances[i]) / segment).clamp(0.0, 1.0);
+
+        // y is down, so up on screen is negative
+        (a.lerp(&b, t), (a.1 - b.1).atan2(b.0 - a.0))
+    }
+
+    // Point and direction at t, 0 = start, 1 = end
+    pub fn sample(&self, t: f32) -> ((f32, f32), f32)
+    {
+        self.sample_distance(t * self.length)
+    }
+
+    pub fn point_at(&self, t: f32) -> (f32, f32)
+    {
+        self.sample(t).0
+    }
+
+    pub fn angle_at(&self, t: f32) -> f32
+    {
+        self.sample(t).1
+    }
+}
+
+// Moves along a motion path, the tween is the progress from 0 to 1 so it can be eased, delayed, repeated and played back and forth
+// Works for anything placed by a point, like sprites, projectiles, text or a camera on a rail
+pub struct PathFollower
+{
+    pub path: MotionPath,
+    pub tween: Tween<f32>,
+    pub auto_rotate: bool,
+    pub rotation_offset: f32 // Added to the path direction, for art that does not face right
+}
+
+impl PathFollower
+{
+    pub fn new(path: MotionPath, duration: f32) -> Self
+    {
+        Self { path, tween: Tween::new(0.0, 1.0, duration), auto_rotate: false, rotation_offset: 0.0 }
+    }
+
+    // Same speed for any path, duration = length / speed
+    pub fn with_speed(path: MotionPath, speed: f32) -> Self
+    {
+        let duration = path.length() / speed.max(f32::EPSILON);
+        Self::new(path, duration)
+    }
+
+    pub fn with_ease(mut self, ease: Ease) -> Self
+    {
+        self.tween = self.tween.with_ease(ease);
+        self
+    }
+
+    pub fn with_delay(mut self, delay: f32) -> Self
+    {
+        self.tween = self.tween.with_delay(delay);
+        self
+    }
+
+    pub fn with_repeat(mut self, repeat: u32) -> Self
+    {
+        self.tween = self.tween.with_repeat(repeat);
+        self
+    }
+
+    // Goes back along the path every second play, see Tween::with_yoyo
+    pub fn with_yoyo(mut self, yoyo: bool) -> Self
+    {
+        self.tween = self.tween.with_yoyo(yoyo);
+        self
+    }
+
+    // Turns with the path, offset in radians
+    pub fn with_auto_rotate(mut self, offset: f32) -> Self
+    {
+        self.auto_rotate = true;
+        self.rotation_offset = offset;
+        self
+    }
+
+    pub fn update(&mut self, dt: f64)
+    {
+        self.tween.update(dt);
+    }
+
+    pub fn is_finished(&self) -> bool
+    {
+        self.tween.is_finished()
+    }
+
+    pub fn restart(&mut self)
+    {
+        self.tween.restart();
+    }
+
+    // Eased fraction of the path covered right now
+    pub fn progress(&self) -> f32
+    {
+        self.tween.value()
+    }
+
+    pub fn position(&self) -> (f32, f32)
+    {
+        self.path.point_at(self.progress())
+    }
+
+    // Direction it moves in plus the offset, also without auto_rotate, turned around while going back with yoyo
+    pub fn rotation(&self) -> f32
+    {
+        let back = if self.tween.is_reversed() { PI } else { 0.0 };
+        self.path.angle_at(self.progress()) + back + self.rotation_offset
+    }
+
+    // Moves transform onto the path, and turns it with auto_rotate
+    pub fn apply(&self, transform: &mut Transform2D)
+    {
+        transform.translation = self.position();
+        if self.auto_rotate
+        {
+            transform.rotation = self.rotation();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::transform::CoordinateSpace;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool
+    {
+        (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4
+    }
+
+    #[test]
+    fn samples_are_spaced_by_length()
+    {
+        // A short segment and a long one, halfway is on the long one
+        let path = MotionPath::polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 30.0)], false);
+        assert_eq!(path.length(), 40.0);
+        assert_eq!(path.point_at(0.5), (10.0, 10.0));
+        assert_eq!(path.point_at(0.125), (5.0, 0.0));
+        assert_eq!(path.point_at(1.0), (10.0, 30.0));
+        assert_eq!(path.point_at(2.0), (10.0, 30.0));
+    }
+
+    #[test]
+    fn closed_paths_go_back_to_the_start()
+    {
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let open = MotionPath::polyline(&square, false);
+        let closed = MotionPath::polyline(&square, true);
+        assert_eq!((open.length(), closed.length()), (30.0, 40.0));
+        assert_eq!(closed.point_at(0.875), (0.0, 5.0));
+        assert_eq!(closed.point_at(1.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn angles_are_counter_clockwise_on_screen()
+    {
+        // Direction, angle, y is down so moving down the screen turns clockwise
+        let cases = [((10.0, 0.0), 0.0), ((0.0, -10.0), PI / 2.0), ((-10.0, 0.0), PI), ((0.0, 10.0), -PI / 2.0)];
+        for (direction, angle) in cases
+        {
+            let path = MotionPath::polyline(&[(0.0, 0.0), direction], false);
+            assert!((path.angle_at(0.5) - angle).abs() < 1e-5, "{direction:?}");
+        }
+    }
+
+    #[test]
+    fn empty_paths_stay_at_the_origin()
+    {
+        let path = MotionPath::polyline(&[(5.0, 5.0), (5.0, 5.0)], false);
+        assert_eq!(path.length(), 0.0);
+        assert_eq!(path.sample(0.5), ((0.0, 0.0), 0.0));
+    }
+
+    #[test]
+    fn followers_turn_around_when_going_back()
+    {
+        let path = MotionPath::polyline(&[(0.0, 0.0), (10.0, 0.0)], false);
+        let mut follower = PathFollower::new(path, 1.0).with_repeat(1).with_yoyo(true).with_auto_rotate(0.0);
+        let mut transform = Transform2D::identity(CoordinateSpace::Virtual);
+
+        follower.update(0.25);
+        follower.apply(&mut transform);
+        assert!(close(transform.translation, (2.5, 0.0)));
+        assert_eq!(transform.rotation, 0.0);
+
+        follower.update(1.0);
+        follower.apply(&mut transform);
+        assert!(close(transform.translation, (7.5, 0.0)));
+        assert!((transform.rotation - PI).abs() < 1e-5);
+    }
+}
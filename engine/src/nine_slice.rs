@@ -0,0 +1,176 @@
+// Borders of a texture that keep their size while the rest stretches, for UI panels and buttons
+// All values are in pixels of the texture
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct NineSlice
+{
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+    pub source: Option<(f32, f32, f32, f32)>, // Part of the texture (x, y, width, height) for sprites in an atlas, None = the whole texture
+    pub tile: bool // Repeats the edges and the center at their own size instead of stretching them
+}
+
+// Destination rect (x, y, width, height) of one piece with its uvs
+pub(crate) type Piece = ((f32, f32, f32, f32), [f32; 2], [f32; 2]);
+
+// Part of one axis: destination start and length, source start and length
+type Segment = (f32, f32, f32, f32);
+
+impl NineSlice
+{
+    pub fn new(left: f32, right: f32, top: f32, bottom: f32) -> Self
+    {
+        Self { left, right, top, bottom, source: None, tile: false }
+    }
+
+    // Same border on every side
+    pub fn uniform(border: f32) -> Self
+    {
+        Self::new(border, border, border, border)
+    }
+
+    pub fn with_source(mut self, source: (f32, f32, f32, f32)) -> Self
+    {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_tiling(mut self, tile: bool) -> Self
+    {
+        self.tile = tile;
+        self
+    }
+
+    // Every quad needed to fill rect, 9 when stretching, more when tiling
+    pub(crate) fn pieces(&self, texture_size: (f32, f32), rect: (f32, f32, f32, f32)) -> Vec<Piece>
+    {
+        let source = self.source.unwrap_or((0.0, 0.0, texture_size.0, texture_size.1));
+
+        let columns = Self::segments(rect.0, rect.2, source.0, source.2, self.left, self.right, self.tile);
+        let rows = Self::segments(rect.1, rect.3, source.1, source.3, self.top, self.bottom, self.tile);
+
+        let mut pieces = Vec::with_capacity(columns.len() * rows.len());
+        for &(y, height, src_y, src_height) in &rows
+        {
+            for &(x, width, src_x, src_width) in &columns
+            {
+                if width <= 0.0 || height <= 0.0
+                {
+                    continue;
+                }
+
+                let uv_min = [src_x / texture_size.0, src_y / texture_size.1];
+                let uv_max = [(src_x + src_width) / texture_size.0, (src_y + src_height) / texture_size.1];
+                pieces.push(((x, y, width, height), uv_min, uv_max));
+            }
+        }
+        pieces
+    }
+
+    fn segments(start: f32, length: f32, src_start: f32, src_length: f32, border_start: f32, border_end: f32, tile: bool) -> Vec<Segment>
+    {
+        // Borders shrink when the rect is smaller than both of them together
+        let shrink = (length / (border_start + border_end)).min(1.0);
+        let (dst_start, dst_end) = (border_start * shrink, border_end * shrink);
+        let middle = length - dst_start - dst_end;
+        let src_middle = src_length - border_start - border_end;
+
+        let mut segments = vec![(start, dst_start, src_start, border_start)];
+
+        if tile && src_middle > 0.0
+        {
+            // Whole tiles, the last one gets cut off
+            let mut offset = 0.0;
+            while offset < middle
+            {
+                let width = src_middle.min(middle - offset);
+                segments.push((start + dst_start + offset, width, src_start + border_start, width));
+                offset += src_middle;
+            }
+        }
+        else
+        {
+            segments.push((start + dst_start, middle, src_start + border_start, src_middle));
+        }
+
+        segments.push((start + length - dst_end, dst_end, src_start + src_length - border_end, border_end));
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const THIRD: f32 = 1.0 / 3.0;
+    const TWO_THIRDS: f32 = 2.0 / 3.0;
+
+    #[test]
+    fn stretching_keeps_the_borders_and_stretches_the_middle()
+    {
+        let pieces = NineSlice::uniform(10.0).pieces((30.0, 30.0), (0.0, 0.0, 100.0, 50.0));
+
+        assert_eq!(pieces.len(), 9);
+        assert_eq!(pieces[0], ((0.0, 0.0, 10.0, 10.0), [0.0, 0.0], [THIRD, THIRD]));
+        assert_eq!(pieces[1], ((10.0, 0.0, 80.0, 10.0), [THIRD, 0.0], [TWO_THIRDS, THIRD]));
+        assert_eq!(pieces[4], ((10.0, 10.0, 80.0, 30.0), [THIRD, THIRD], [TWO_THIRDS, TWO_THIRDS]));
+        assert_eq!(pieces[8], ((90.0, 40.0, 10.0, 10.0), [TWO_THIRDS, TWO_THIRDS], [1.0, 1.0]));
+    }
+
+    #[test]
+    fn borders_shrink_when_the_rect_is_too_small()
+    {
+        // Half the size of both borders together, the middle disappears
+        let pieces = NineSlice::uniform(10.0).pieces((30.0, 30.0), (0.0, 0.0, 10.0, 10.0));
+
+        assert_eq!(pieces, vec!
+        [
+            ((0.0, 0.0, 5.0, 5.0), [0.0, 0.0], [THIRD, THIRD]),
+            ((5.0, 0.0, 5.0, 5.0), [TWO_THIRDS, 0.0], [1.0, THIRD]),
+            ((0.0, 5.0, 5.0, 5.0), [0.0, TWO_THIRDS], [THIRD, 1.0]),
+            ((5.0, 5.0, 5.0, 5.0), [TWO_THIRDS, TWO_THIRDS], [1.0, 1.0])
+        ]);
+    }
+
+    #[test]
+    fn segments_of_one_axis()
+    {
+        // Start, length, tiling, segments (start, length, source start, source length)
+        let cases: [(f32, f32, bool, Vec<Segment>); 5] =
+        [
+            (0.0, 100.0, false, vec![(0.0, 10.0, 0.0, 10.0), (10.0, 80.0, 10.0, 10.0), (90.0, 10.0, 20.0, 10.0)]),
+            (0.0, 10.0, false, vec![(0.0, 5.0, 0.0, 10.0), (5.0, 0.0, 10.0, 10.0), (5.0, 5.0, 20.0, 10.0)]),
+            // The last tile gets cut off, both in the destination and in the source
+            (0.0, 45.0, true, vec![(0.0, 10.0, 0.0, 10.0), (10.0, 10.0, 10.0, 10.0), (20.0, 10.0, 10.0, 10.0), (30.0, 5.0, 10.0, 5.0), (35.0, 10.0, 20.0, 10.0)]),
+            (100.0, 40.0, true, vec![(100.0, 10.0, 0.0, 10.0), (110.0, 10.0, 10.0, 10.0), (120.0, 10.0, 10.0, 10.0), (130.0, 10.0, 20.0, 10.0)]),
+            // No room for a tile
+            (0.0, 20.0, true, vec![(0.0, 10.0, 0.0, 10.0), (10.0, 10.0, 20.0, 10.0)])
+        ];
+
+        for (start, length, tile, expected) in cases
+        {
+            assert_eq!(NineSlice::segments(start, length, 0.0, 30.0, 10.0, 10.0, tile), expected, "{start} {length} {tile}");
+        }
+    }
+
+    #[test]
+    fn tiling_repeats_the_middle_at_its_own_size()
+    {
+        let pieces = NineSlice::uniform(10.0).with_tiling(true).pieces((30.0, 30.0), (0.0, 0.0, 45.0, 30.0));
+
+        // 5 columns (border, 2 whole tiles, 1 cut off, border) times 3 rows
+        assert_eq!(pieces.len(), 15);
+        assert_eq!(pieces[3], ((30.0, 0.0, 5.0, 10.0), [THIRD, 0.0], [0.5, THIRD]));
+    }
+
+    #[test]
+    fn the_source_selects_a_sprite_in_an_atlas()
+    {
+        let pieces = NineSlice::uniform(10.0).with_source((30.0, 0.0, 30.0, 30.0)).pieces((60.0, 30.0), (0.0, 0.0, 30.0, 30.0));
+
+        assert_eq!(pieces[0], ((0.0, 0.0, 10.0, 10.0), [0.5, 0.0], [30.0 / 60.0 + 10.0 / 60.0, THIRD]));
+        assert_eq!(pieces[8].2, [1.0, 1.0]);
+    }
+}
@@ -0,0 +1,633 @@
+use crate::{renderer::Renderer, state::Loader, transform::DrawTransform, utility::Vertex};
+
+// Max distance in pixels between a curve and the lines it gets flattened into
+pub const DEFAULT_TOLERANCE: f32 = 0.25;
+
+// Meshes have u16 indices, shapes with more points than this get skipped
+pub const MAX_POINTS: usize = u16::MAX as usize + 1;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PathCommand
+{
+    MoveTo((f32, f32)),
+    LineTo((f32, f32)),
+    QuadTo((f32, f32), (f32, f32)), // Control point, end
+    CubicTo((f32, f32), (f32, f32), (f32, f32)), // Two control points, end
+    Close
+}
+
+// Vector shape in pixels with y down, like svg paths, every move_to starts a new contour and every contour gets closed when filled
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path
+{
+    pub commands: Vec<PathCommand>
+}
+
+impl Path
+{
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    pub fn move_to(mut self, point: (f32, f32)) -> Self
+    {
+        self.commands.push(PathCommand::MoveTo(point));
+        self
+    }
+
+    pub fn line_to(mut self, point: (f32, f32)) -> Self
+    {
+        self.commands.push(PathCommand::LineTo(point));
+        self
+    }
+
+    pub fn quad_to(mut self, control: (f32, f32), point: (f32, f32)) -> Self
+    {
+        self.commands.push(PathCommand::QuadTo(control, point));
+        self
+    }
+
+    pub fn cubic_to(mut self, control1: (f32, f32), control2: (f32, f32), point: (f32, f32)) -> Self
+    {
+        self.commands.push(PathCommand::CubicTo(control1, control2, point));
+        self
+    }
+
+    pub fn close(mut self) -> Self
+    {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    // Lines between the points, closed
+    pub fn polygon(points: &[(f32, f32)]) -> Self
+    {
+        let mut path = Self::new();
+        for (i, point) in points.iter().enumerate()
+        {
+            path = if i == 0 { path.move_to(*point) } else { path.line_to(*point) };
+        }
+        path.close()
+    }
+
+    // x, y of the top left corner
+    pub fn rect(rect: (f32, f32, f32, f32)) -> Self
+    {
+        let (x, y, width, height) = rect;
+        Self::polygon(&[(x, y), (x + width, y), (x + width, y + height), (x, y + height)])
+    }
+
+    pub fn ellipse(center: (f32, f32), radius: (f32, f32)) -> Self
+    {
+        // Control point distance of a quarter circle made of one cubic
+        let k = 0.552_284_8;
+        let (cx, cy) = center;
+        let (rx, ry) = radius;
+        Self::new()
+            .move_to((cx + rx, cy))
+            .cubic_to((cx + rx, cy + k * ry), (cx + k * rx, cy + ry), (cx, cy + ry))
+            .cubic_to((cx - k * rx, cy + ry), (cx - rx, cy + k * ry), (cx - rx, cy))
+            .cubic_to((cx - rx, cy - k * ry), (cx - k * rx, cy - ry), (cx, cy - ry))
+            .cubic_to((cx + k * rx, cy - ry), (cx + rx, cy - k * ry), (cx + rx, cy))
+            .close()
+    }
+
+    pub fn circle(center: (f32, f32), radius: f32) -> Self
+    {
+        Self::ellipse(center, (radius, radius))
+    }
+
+    // Curves become lines no further than tolerance pixels away from them, one polyline per contour without repeating the first point
+    pub fn flatten(&self, tolerance: f32) -> Vec<Vec<(f32, f32)>>
+    {
+        self.flatten_polylines(tolerance).into_iter().map(|(points, _)| points).collect()
+    }
+
+    // Like flatten, but keeps whether each contour was closed, for strokes
+    pub fn flatten_polylines(&self, tolerance: f32) -> Vec<(Vec<(f32, f32)>, bool)>
+    {
+        let mut contours: Vec<(Vec<(f32, f32)>, bool)> = Vec::new();
+        let mut current: Vec<(f32, f32)> = Vec::new();
+        let mut last = (0.0, 0.0);
+
+        for command in &self.commands
+        {
+            // Drawing on after a close starts at the start of the closed contour
+            if current.is_empty() && !matches!(command, PathCommand::MoveTo(_) | PathCommand::Close)
+            {
+                current.push(last);
+            }
+
+            match *command
+            {
+                PathCommand::MoveTo(point) =>
+                {
+                    if current.len() > 1
+                    {
+                        contours.push((std::mem::take(&mut current), false));
+                    }
+                    current = vec![point];
+                    last = point;
+                    continue;
+                }
+                PathCommand::LineTo(point) => current.push(point),
+                PathCommand::QuadTo(control, point) =>
+                {
+                    let deviation = length(add(sub(last, control), sub(point, control)));
+                    let segments = segment_count(deviation / 8.0, tolerance);
+                    for i in 1..=segments
+                    {
+                        let t = i as f32 / segments as f32;
+                        let u = 1.0 - t;
+                        current.push(add(add(scale(last, u * u), scale(control, 2.0 * u * t)), scale(point, t * t)));
+                    }
+                }
+                PathCommand::CubicTo(control1, control2, point) =>
+                {
+                    let deviation = length(add(sub(last, control1), sub(control2, control1))).max(length(add(sub(control1, control2), sub(point, control2))));
+                    let segments = segment_count(deviation * 3.0 / 4.0, tolerance);
+                    for i in 1..=segments
+                    {
+                        let t = i as f32 / segments as f32;
+                        let u = 1.0 - t;
+                        let a = add(scale(last, u * u * u), scale(control1, 3.0 * u * u * t));
+                        let b = add(scale(control2, 3.0 * u * t * t), scale(point, t * t * t));
+                        current.push(add(a, b));
+                    }
+                }
+                PathCommand::Close =>
+                {
+                    if current.len() > 1
+                    {
+                        let first = current[0];
+                        contours.push((std::mem::take(&mut current), true));
+                        last = first;
+                    }
+                    continue;
+                }
+            }
+            last = *current.last().unwrap();
+        }
+
+        if current.len() > 1
+        {
+            contours.push((current, false));
+        }
+
+        // Closing lines back to the start are implied
+        for (contour, closed) in &mut contours
+        {
+            while *closed && contour.len() > 1 && contour.first() == contour.last()
+            {
+                contour.pop();
+            }
+        }
+        contours
+    }
+}
+
+fn segment_count(deviation: f32, tolerance: f32) -> usize
+{
+    ((deviation / tolerance.max(0.001)).sqrt().ceil() as usize).clamp(1, 128)
+}
+
+fn add(a: (f32, f32), b: (f32, f32)) -> (f32, f32)
+{
+    (a.0 + b.0, a.1 + b.1)
+}
+
+fn sub(a: (f32, f32), b: (f32, f32)) -> (f32, f32)
+{
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn scale(a: (f32, f32), s: f32) -> (f32, f32)
+{
+    (a.0 * s, a.1 * s)
+}
+
+fn length(a: (f32, f32)) -> f32
+{
+    (a.0 * a.0 + a.1 * a.1).sqrt()
+}
+
+// Twice the area, positive for clockwise contours on screen (y down)
+pub fn signed_area(contour: &[(f32, f32)]) -> f32
+{
+    (0..contour.len()).map(|i|
+    {
+        let (a, b) = (contour[i], contour[(i + 1) % contour.len()]);
+        a.0 * b.1 - b.0 * a.1
+    }).sum()
+}
+
+// Which parts of overlapping contours get filled, like fill-rule in svg
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FillRule
+{
+    #[default]
+    NonZero, // Inside if the contours around a point turn more often one way than the other, holes have to turn the other way
+    EvenOdd // Inside if a point is inside of an odd number of contours
+}
+
+// Ear clipping of every contour on its own, so contours can overlap but not cut holes into each other
+// Returns triangles as indices into the contours put one after another, none if there are more than MAX_POINTS points
+pub fn tessellate(contours: &[Vec<(f32, f32)>]) -> Vec<u16>
+{
+    let points: Vec<(f32, f32)> = contours.iter().flatten().copied().collect();
+    if points.len() > MAX_POINTS
+    {
+        return Vec::new();
+    }
+
+    let mut indices = Vec::new();
+    let mut start = 0;
+    for contour in contours
+    {
+        clip_ears(&points, (start..start + contour.len()).collect(), &mut indices);
+        start += contour.len();
+    }
+    indices
+}
+
+// Like tessellate, but contours inside of others cut holes into them following rule
+// Contours are expected not to cross each other, only to be nested, whether one is inside of another is decided by its first point
+pub fn tessellate_with_holes(contours: &[Vec<(f32, f32)>], rule: FillRule) -> Vec<u16>
+{
+    let points: Vec<(f32, f32)> = contours.iter().flatten().copied().collect();
+    if points.len() > MAX_POINTS
+    {
+        return Vec::new();
+    }
+
+    let ranges: Vec<std::ops::Range<usize>> = contours.iter().scan(0, |start, contour|
+    {
+        *start += contour.len();
+        Some(*start - contour.len()..*start)
+    }).collect();
+
+    let contains = |outer: usize, inner: usize| outer != inner && contours[inner].len() > 2 && inside_polygon(contours[inner][0], &contours[outer]);
+    let turn = |contour: usize| match rule
+    {
+        FillRule::NonZero => if signed_area(&contours[contour]) >= 0.0 { 1 } else { -1 },
+        FillRule::EvenOdd => 1
+    };
+    let filled = |winding: i32| match rule
+    {
+        FillRule::NonZero => winding != 0,
+        FillRule::EvenOdd => winding % 2 != 0
+    };
+
+    // Contours around each one, and whether it is the edge of a filled part (outer) or of an empty part inside of one (hole)
+    let parents: Vec<Vec<usize>> = (0..contours.len()).map(|inner| (0..contours.len()).filter(|&outer| contains(outer, inner)).collect()).collect();
+    let outside: Vec<i32> = parents.iter().map(|parents| parents.iter().map(|&parent| turn(parent)).sum()).collect();
+    let is_outer = |contour: usize| filled(outside[contour] + turn(contour)) && !filled(outside[contour]);
+    let is_hole = |contour: usize| !filled(outside[contour] + turn(contour)) && filled(outside[contour]);
+
+    let mut indices = Vec::new();
+    for outer in (0..contours.len()).filter(|&contour| is_outer(contour))
+    {
+        // Holes belong to the innermost outer contour around them
+        let mut holes: Vec<usize> = (0..contours.len()).filter(|&hole| is_hole(hole) && parents[hole].iter().copied().filter(|&parent| is_outer(parent)).max_by_key(|&parent| parents[parent].len()) == Some(outer)).collect();
+        let max_x = |hole: &usize| points[ranges[*hole].clone()].iter().map(|p| p.0).fold(f32::MIN, f32::max);
+        holes.sort_by(|a, b| max_x(b).total_cmp(&max_x(a)));
+
+        let clockwise = signed_area(&contours[outer]) >= 0.0;
+        let mut polygon: Vec<usize> = ranges[outer].clone().collect();
+        for (h, &hole) in holes.iter().enumerate()
+        {
+            // The hole has to turn the other way than the outer contour
+            let mut ring: Vec<usize> = ranges[hole].clone().collect();
+            if (signed_area(&contours[hole]) >= 0.0) == clockwise
+            {
+                ring.reverse();
+            }
+            let right = (0..ring.len()).max_by(|a, b| points[ring[*a]].0.total_cmp(&points[ring[*b]].0)).unwrap_or(0);
+            ring.rotate_left(right);
+            let from = points[ring[0]];
+
+            // Bridge from the rightmost point of the hole to the closest point of the polygon it can see
+            let edges = |ring: &[usize]| (0..ring.len()).map(|i| (points[ring[i]], points[ring[(i + 1) % ring.len()]])).collect::<Vec<_>>();
+            let mut blocking = edges(&polygon);
+            for &other in &holes[h..]
+            {
+                blocking.extend(edges(&ranges[other].clone().collect::<Vec<_>>()));
+            }
+            let mut candidates: Vec<usize> = (0..polygon.len()).collect();
+            let distance = |i: &usize| { let p = points[polygon[*i]]; (p.0 - from.0).powi(2) + (p.1 - from.1).powi(2) };
+            candidates.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+            let bridge = candidates.iter().copied().find(|&i| !blocking.iter().any(|&(a, b)| segments_cross((from, points[polygon[i]]), (a, b)))).unwrap_or(candidates[0]);
+
+            // polygon up to the bridge, around the hole and back over the bridge
+            let mut merged = polygon[..=bridge].to_vec();
+            merged.extend(&ring);
+            merged.push(ring[0]);
+            merged.extend(&polygon[bridge..]);
+            polygon = merged;
+        }
+        clip_ears(&points, polygon, &mut indices);
+    }
+    indices
+}
+
+// Triangles of the polygon made of the points at remaining, appended to indices, points has at most MAX_POINTS points
+fn clip_ears(points: &[(f32, f32)], mut remaining: Vec<usize>, indices: &mut Vec<u16>)
+{
+    let polygon: Vec<(f32, f32)> = remaining.iter().map(|&i| points[i]).collect();
+    let clockwise = signed_area(&polygon) >= 0.0;
+
+    while remaining.len() > 3
+    {
+        let count = remaining.len();
+        let is_ear = |i: usize|
+        {
+            let (a, b, c) = (points[remaining[(i + count - 1) % count]], points[remaining[i]], points[remaining[(i + 1) % count]]);
+            let cross = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+            // Corners pointing inwards are no ears, collinear points are dropped as ears
+            if (cross > 0.0) != clockwise && cross != 0.0
+            {
+                return false;
+            }
+            remaining.iter().all(|&other|
+            {
+                let p = points[other];
+                p == a || p == b || p == c || !inside_triangle(p, a, b, c)
+            })
+        };
+
+        // Self intersecting contours (like halfway through a morph) can run out of ears, then any corner gets cut off
+        let ear = (0..count).find(|&i| is_ear(i)).unwrap_or(0);
+        let (a, b, c) = (remaining[(ear + count - 1) % count], remaining[ear], remaining[(ear + 1) % count]);
+        indices.extend([a, b, c].map(|i| i as u16));
+        remaining.remove(ear);
+    }
+
+    if remaining.len() == 3
+    {
+        indices.extend(remaining.iter().map(|&i| i as u16));
+    }
+}
+
+// Even-odd test, a ray to the right crosses the edges
+fn inside_polygon(p: (f32, f32), polygon: &[(f32, f32)]) -> bool
+{
+    let mut inside = false;
+    for i in 0..polygon.len()
+    {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < a.0 + (p.1 - a.1) / (b.1 - a.1) * (b.0 - a.0)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Whether the segments cross somewhere other than at a point they share
+fn segments_cross(first: ((f32, f32), (f32, f32)), second: ((f32, f32), (f32, f32))) -> bool
+{
+    let ((a, b), (c, d)) = (first, second);
+    if a == c || a == d || b == c || b == d
+    {
+        return false;
+    }
+    let side = |p: (f32, f32), q: (f32, f32), r: (f32, f32)| (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0);
+    (side(a, b, c) > 0.0) != (side(a, b, d) > 0.0) && (side(c, d, a) > 0.0) != (side(c, d, b) > 0.0)
+}
+
+fn inside_triangle(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> bool
+{
+    let side = |a: (f32, f32), b: (f32, f32)| (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+    let (d1, d2, d3) = (side(a, b), side(b, c), side(c, a));
+    let negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(negative && positive)
+}
+
+// Band of width along the polyline with miter joins (limited to 4 times the width) and flat ends
+// Returns the points and triangles between them, two points per point of the polyline, nothing if that is more than MAX_POINTS
+pub fn stroke(polyline: &[(f32, f32)], closed: bool, width: f32) -> (Vec<(f32, f32)>, Vec<u16>)
+{
+    let mut line: Vec<(f32, f32)> = Vec::with_capacity(polyline.len());
+    for point in polyline
+    {
+        if line.last() != Some(point)
+        {
+            line.push(*point);
+        }
+    }
+    if closed && line.len() > 1 && line.first() == line.last()
+    {
+        line.pop();
+    }
+
+    let count = line.len();
+    if count < 2 || count * 2 > MAX_POINTS
+    {
+        return (Vec::new(), Vec::new());
+    }
+
+    let half = width / 2.0;
+    let normal = |a: (f32, f32), b: (f32, f32)|
+    {
+        let d = sub(b, a);
+        scale((-d.1, d.0), 1.0 / length(d).max(f32::EPSILON))
+    };
+
+    let mut points = Vec::with_capacity(count * 2);
+    for i in 0..count
+    {
+        let before = if i > 0 { Some(line[i - 1]) } else if closed { Some(line[count - 1]) } else { None };
+        let after = if i + 1 < count { Some(line[i + 1]) } else if closed { Some(line[0]) } else { None };
+
+        let (n_in, n_out) = match (before, after)
+        {
+            (Some(before), Some(after)) => (normal(before, line[i]), normal(line[i], after)),
+            (Some(before), None) => (normal(before, line[i]), normal(before, line[i])),
+            (None, Some(after)) => (normal(line[i], after), normal(line[i], after)),
+            (None, None) => unreachable!()
+        };
+
+        // The miter is as long as it takes to keep both edges half the width away
+        let miter = add(n_in, n_out);
+        let miter = scale(miter, 1.0 / length(miter).max(f32::EPSILON));
+        let along = (miter.0 * n_out.0 + miter.1 * n_out.1).max(0.25);
+        let offset = scale(miter, half / along);
+
+        points.push(add(line[i], offset));
+        points.push(sub(line[i], offset));
+    }
+
+    let segments = if closed { count } else { count - 1 };
+    let mut indices = Vec::with_capacity(segments * 6);
+    for i in 0..segments
+    {
+        let j = (i + 1) % count;
+        let (a, b, c, d) = (2 * i, 2 * i + 1, 2 * j, 2 * j + 1);
+        indices.extend([a, b, c, b, d, c].map(|index| index as u16));
+    }
+    (points, indices)
+}
+
+// Strokes of all polylines in one mesh, the ones that do not fit into MAX_POINTS anymore get skipped
+fn strokes(polylines: &[(Vec<(f32, f32)>, bool)], width: f32) -> (Vec<(f32, f32)>, Vec<u16>)
+{
+    let mut points = Vec::new();
+    let mut indices = Vec::new();
+    for (polyline, closed) in polylines
+    {
+        let (band, triangles) = stroke(polyline, *closed, width);
+        if points.len() + band.len() > MAX_POINTS
+        {
+            log::warn!("Skipped a stroke with {} points, meshes can only have {}", band.len(), MAX_POINTS);
+            continue;
+        }
+
+        let start = points.len() as u16;
+        indices.extend(triangles.iter().map(|index| start + index));
+        points.extend(band);
+    }
+    (points, indices)
+}
+
+// Dynamic mesh that gets filled with a new shape before every draw, it can be drawn several times a frame with different shapes
+pub struct PathMesh
+{
+    pub mesh_id: usize
+}
+
+impl PathMesh
+{
+    // Room for vertices to start with, the mesh grows for bigger shapes
+    // Fills need one vertex per point after flattening, strokes two
+    pub fn new(loader: &mut dyn Loader, vertices: usize) -> Self
+    {
+        let mesh_id = loader.create_dynamic_mesh(vertices, vertices * 3);
+        Self { mesh_id }
+    }
+
+    // Fills the path with color, the points are in pixels, so transform keeps its own scale like for skinned meshes
+    pub fn draw(&self, renderer: &mut Renderer, path: &Path, transform: impl DrawTransform, color: [f32; 4], z_index: u32)
+    {
+        self.draw_contours(renderer, &path.flatten(DEFAULT_TOLERANCE), transform, color, z_index);
+    }
+
+    // Already flattened contours, like the ones of a ShapeMorph
+    pub fn draw_contours(&self, renderer: &mut Renderer, contours: &[Vec<(f32, f32)>], transform: impl DrawTransform, color: [f32; 4], z_index: u32)
+    {
+        let points: Vec<(f32, f32)> = contours.iter().flatten().copied().collect();
+        self.upload(renderer, &points, tessellate(contours), transform, color, z_index);
+    }
+
+    // Like draw_contours, but contours inside of others are holes following rule
+    pub fn draw_contours_with_holes(&self, renderer: &mut Renderer, contours: &[Vec<(f32, f32)>], rule: FillRule, transform: impl DrawTransform, color: [f32; 4], z_index: u32)
+    {
+        let points: Vec<(f32, f32)> = contours.iter().flatten().copied().collect();
+        self.upload(renderer, &points, tessellate_with_holes(contours, rule), transform, color, z_index);
+    }
+
+    // Outlines the polylines (points and whether they are closed) with lines width pixels wide
+    pub fn draw_stroke(&self, renderer: &mut Renderer, polylines: &[(Vec<(f32, f32)>, bool)], width: f32, transform: impl DrawTransform, color: [f32; 4], z_index: u32)
+    {
+        let (points, indices) = strokes(polylines, width);
+        self.upload(renderer, &points, indices, transform, color, z_index);
+    }
+
+    fn upload(&self, renderer: &mut Renderer, points: &[(f32, f32)], mut indices: Vec<u16>, transform: impl DrawTransform, color: [f32; 4], z_index: u32)
+    {
+        if points.len() > MAX_POINTS
+        {
+            log::warn!("Skipped a path with {} points, meshes can only have {}", points.len(), MAX_POINTS);
+            return;
+        }
+
+        // Counter-clockwise with y up, otherwise the triangle gets culled
+        for triangle in indices.chunks_exact_mut(3)
+        {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| points[i as usize]);
+            if (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0) > 0.0
+            {
+                triangle.swap(1, 2);
+            }
+        }
+
+        // Texture coordinates over the bounds, for materials
+        let (min, max) = points.iter().fold(((f32::MAX, f32::MAX), (f32::MIN, f32::MIN)), |(min, max), p| ((min.0.min(p.0), min.1.min(p.1)), (max.0.max(p.0), max.1.max(p.1))));
+        let size = ((max.0 - min.0).max(f32::EPSILON), (max.1 - min.1).max(f32::EPSILON));
+        let vertices: Vec<Vertex> = points.iter().map(|p| Vertex::new([p.0, -p.1, 0.0], [(p.0 - min.0) / size.0, (p.1 - min.1) / size.1])).collect();
+
+        renderer.set_mesh(self.mesh_id, &vertices, &indices);
+        renderer.draw(self.mesh_id, transform, color, z_index);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn square(center: (f32, f32), half: f32) -> Vec<(f32, f32)>
+    {
+        let (x, y) = center;
+        vec![(x - half, y - half), (x + half, y - half), (x + half, y + half), (x - half, y + half)]
+    }
+
+    // Area covered by the triangles, counting overlaps twice
+    fn area(contours: &[Vec<(f32, f32)>], indices: &[u16]) -> f32
+    {
+        let points: Vec<(f32, f32)> = contours.iter().flatten().copied().collect();
+        indices.chunks_exact(3).map(|triangle| signed_area(&triangle.iter().map(|&i| points[i as usize]).collect::<Vec<_>>()).abs() / 2.0).sum()
+    }
+
+    #[test]
+    fn flattening_keeps_lines_and_drops_the_closing_point()
+    {
+        let contours = Path::rect((0.0, 0.0, 10.0, 20.0)).flatten_polylines(DEFAULT_TOLERANCE);
+        assert_eq!(contours, vec![(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 20.0), (0.0, 20.0)], true)]);
+
+        let circle = Path::circle((0.0, 0.0), 50.0).flatten(DEFAULT_TOLERANCE);
+        assert!(circle[0].iter().all(|p| ((p.0 * p.0 + p.1 * p.1).sqrt() - 50.0).abs() < 0.5));
+    }
+
+    #[test]
+    fn contours_inside_of_others_cut_holes()
+    {
+        let mut inner = square((0.0, 0.0), 5.0);
+        let contours = vec![square((0.0, 0.0), 10.0), inner.clone()];
+        assert_eq!(area(&contours, &tessellate(&contours)), 500.0);
+        assert_eq!(area(&contours, &tessellate_with_holes(&contours, FillRule::EvenOdd)), 300.0);
+        // Turning the same way the inner square adds to the winding instead
+        assert_eq!(area(&contours, &tessellate_with_holes(&contours, FillRule::NonZero)), 400.0);
+
+        inner.reverse();
+        let contours = vec![square((0.0, 0.0), 10.0), inner, square((20.0, 0.0), 1.0)];
+        assert_eq!(area(&contours, &tessellate_with_holes(&contours, FillRule::NonZero)), 304.0);
+
+        let contours = vec![square((0.0, 0.0), 10.0), square((-5.0, 0.0), 2.0), square((5.0, 0.0), 2.0)];
+        assert_eq!(area(&contours, &tessellate_with_holes(&contours, FillRule::EvenOdd)), 368.0);
+    }
+
+    #[test]
+    fn islands_inside_of_holes_are_filled()
+    {
+        let contours = vec![square((0.0, 0.0), 10.0), square((0.0, 0.0), 6.0), square((0.0, 0.0), 2.0), square((4.0, 4.0), 1.0)];
+        // The outer square without the hole, plus the two islands in it
+        assert_eq!(area(&contours, &tessellate_with_holes(&contours, FillRule::EvenOdd)), 400.0 - 144.0 + 16.0 + 4.0);
+    }
+
+    #[test]
+    fn shapes_that_do_not_fit_u16_indices_are_skipped()
+    {
+        let line = |count: usize, y: f32| ((0..count).map(|x| (x as f32, y)).collect::<Vec<_>>(), false);
+        let (long, short) = (line(20000, 0.0), line(10, 5.0));
+
+        // The second long one would go over MAX_POINTS, the short one after it still fits
+        let (points, indices) = strokes(&[long.clone(), long.clone(), short], 1.0);
+        assert_eq!(points.len(), 40020);
+        assert_eq!(indices.iter().copied().max(), Some(40019));
+
+        assert!(stroke(&line(MAX_POINTS, 0.0).0, false, 1.0).0.is_empty());
+        assert!(tessellate(&[square((0.0, 0.0), 1.0), vec![(0.0, 0.0); MAX_POINTS]]).is_empty());
+    }
+}
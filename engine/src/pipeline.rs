@@ -0,0 +1,200 @@
+use crate::{shader::Shader, utility::{InstanceData, Vertex}};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode
+{
+    #[default]
+    Alpha,
+    Additive, // Lights, particles
+    Multiply, // Shadows, a black color with alpha 0.5 halves the brightness below
+    Screen,
+    Premultiplied // For colors that are already multiplied by alpha, like the content of render targets
+}
+
+// Added to InstanceData::mode for blend modes that need the shader output multiplied by alpha
+pub const PREMULTIPLY_MODE: u32 = 4;
+
+impl BlendMode
+{
+    // Multiply and screen can not weight the source by its alpha with blend factors alone, so the shader does it first
+    // Material shaders used with them have to do the same when in.mode has PREMULTIPLY_MODE set
+    pub fn premultiplies(&self) -> bool
+    {
+        matches!(self, BlendMode::Multiply | BlendMode::Screen)
+    }
+
+    pub fn blend_state(&self) -> wgpu::BlendState
+    {
+        match self
+        {
+            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::Additive => wgpu::BlendState
+            {
+                color: wgpu::BlendComponent
+                {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add
+                },
+                alpha: wgpu::BlendComponent::OVER
+            },
+            // src * dst + dst * (1 - alpha), src is premultiplied
+            BlendMode::Multiply => wgpu::BlendState
+            {
+                color: wgpu::BlendComponent
+                {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                    operation: wgpu::BlendOperation::Add
+                },
+                alpha: wgpu::BlendComponent::OVER
+            },
+            // src * (1 - dst) + dst, src is premultiplied
+            BlendMode::Screen => wgpu::BlendState
+            {
+                color: wgpu::BlendComponent
+                {
+                    src_factor: wgpu::BlendFactor::OneMinusDst,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add
+                },
+                alpha: wgpu::BlendComponent::OVER
+            },
+            BlendMode::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING
+        }
+    }
+}
+
+// Everything a render pipeline depends on, the renderer creates one pipeline per key and caches it
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PipelineKey
+{
+    pub shader: usize,
+    pub blend: BlendMode,
+    pub topology: wgpu::PrimitiveTopology,
+    pub texture_count: Option<usize> // Textures of the material in group 2, None if the material has no group 2
+}
+
+pub fn create_pipeline(device: &wgpu::Device, shader: &Shader, bind_group_layouts: &[&wgpu::BindGroupLayout], format: wgpu::TextureFormat, key: &PipelineKey) -> wgpu::RenderPipeline
+{
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor
+    {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[]
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor
+    {
+        label: Some("Render Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState
+        {
+            module: &shader.module,
+            entry_point: Some(&shader.vs_entry),
+            buffers: &[Vertex::desc(), InstanceData::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default()
+        },
+        fragment: Some(wgpu::FragmentState
+        {
+            module: &shader.module,
+            entry_point: Some(&shader.fs_entry),
+            targets: &[Some(wgpu::ColorTargetState
+            {
+                format,
+                blend: Some(key.blend.blend_state()),
+                write_mask: wgpu::ColorWrites::ALL
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default()
+        }),
+        primitive: wgpu::PrimitiveState
+        {
+            topology: key.topology,
+            strip_index_format: if key.topology.is_strip() { Some(wgpu::IndexFormat::Uint16) } else { None },
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill, //::Line only work with required_features: wgpu::Features::POLYGON_MODE_LINE in request device
+            unclipped_depth: false,
+            conservative: false
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState
+        {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false
+        },
+        multiview: None,
+        cache: None
+    })
+}
+
+// View-projection of the camera, one 256 byte aligned slot per camera of the frame, selected with a dynamic offset
+pub fn camera_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout
+{
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor
+    {
+        label: Some("Camera Bind Group Layout"),
+        entries:
+        &[
+            wgpu::BindGroupLayoutEntry
+            {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer
+                {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<[[f32; 4]; 4]>() as u64)
+                },
+                count: None
+            }
+        ]
+    })
+}
+
+// Group 2 of a material shader: uniform buffer at binding 0, then texture and sampler for every texture
+pub fn material_bind_group_layout(device: &wgpu::Device, texture_count: usize) -> wgpu::BindGroupLayout
+{
+    let mut entries = vec![wgpu::BindGroupLayoutEntry
+    {
+        binding: 0,
+        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+        ty: wgpu::BindingType::Buffer
+        {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None
+        },
+        count: None
+    }];
+
+    for i in 0..texture_count as u32
+    {
+        entries.push(wgpu::BindGroupLayoutEntry
+        {
+            binding: 1 + i * 2,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture
+            {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        });
+        entries.push(wgpu::BindGroupLayoutEntry
+        {
+            binding: 2 + i * 2,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None
+        });
+    }
+
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor
+    {
+        label: Some("Material Bind Group Layout"),
+        entries: &entries
+    })
+}
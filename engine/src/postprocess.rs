@@ -0,0 +1,389 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+
+use crate::{shader, texture::{TextureHandler, TextureOptions}};
+
+const PRELUDE: &str = include_str!("shaders/post/prelude.wgsl");
+
+// Every built-in effect with its default params, all of them start disabled
+const BUILT_IN_EFFECTS: &[(&str, &str, [f32; 8])] =
+&[
+    ("bloom", include_str!("shaders/post/bloom.wgsl"), [0.7, 0.8, 8.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    ("blur", include_str!("shaders/post/blur.wgsl"), [2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    ("chromatic_aberration", include_str!("shaders/post/chromatic_aberration.wgsl"), [3.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    ("color_grading", include_str!("shaders/post/color_grading.wgsl"), [1.0, 16.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    ("crt", include_str!("shaders/post/crt.wgsl"), [0.1, 0.3, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    ("vignette", include_str!("shaders/post/vignette.wgsl"), [0.8, 0.85, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0]),
+];
+
+const LUT_SIZE: u32 = 16;
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct EffectUniforms
+{
+    resolution: [f32; 2],
+    time: f32,
+    _padding: f32,
+    params: [f32; 8] // params0 and params1 in the shader
+}
+
+pub struct Effect
+{
+    pub name: String,
+    pub enabled: bool,
+    pub params: [f32; 8],
+    pipeline: wgpu::RenderPipeline,
+    uniform_buf: wgpu::Buffer,
+    extra_texture: Arc<TextureHandler>,
+    bind_groups: Option<[wgpu::BindGroup; 2]> // One for reading from each of the ping-pong textures
+}
+
+// Chain of full-screen passes that run after the scene got rendered into an intermediate texture
+// Only active when at least one effect is enabled, otherwise the scene gets rendered straight to the screen
+pub struct PostProcess
+{
+    effects: Vec<Effect>,
+    bindgroup_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    identity_lut: Arc<TextureHandler>,
+    targets: Option<[TextureHandler; 2]>,
+    size: (u32, u32),
+    start: std::time::Instant
+}
+
+impl PostProcess
+{
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self
+    {
+        let bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor
+        {
+            label: Some("Post Process Bind Group Layout"),
+            entries:
+            &[
+                wgpu::BindGroupLayoutEntry
+                {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture
+                    {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry
+                {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry
+                {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer
+                    {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry
+                {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture
+                    {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry
+                {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None
+                }
+            ]
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor
+        {
+            label: Some("Post Process Pipeline Layout"),
+            bind_group_layouts: &[&bindgroup_layout],
+            push_constant_ranges: &[]
+        });
+
+        // 256x16 strip that maps every color to itself
+        let lut = image::RgbaImage::from_fn(LUT_SIZE * LUT_SIZE, LUT_SIZE, |x, y|
+        {
+            let step = 255 / (LUT_SIZE - 1);
+            image::Rgba([((x % LUT_SIZE) * step) as u8, (y * step) as u8, ((x / LUT_SIZE) * step) as u8, 255])
+        });
+        // No mipmaps, smaller levels would mix neighbouring slices of the LUT
+        let identity_lut = TextureHandler::from_image_with_options(device, queue, &image::DynamicImage::ImageRgba8(lut), Some("Identity LUT"), TextureOptions::default().with_mipmaps(false)).expect("Failed to create identity LUT");
+
+        let mut post_process = Self
+        {
+            effects: Vec::new(),
+            bindgroup_layout,
+            pipeline_layout,
+            format,
+            identity_lut: Arc::new(identity_lut),
+            targets: None,
+            size: (0, 0),
+            start: std::time::Instant::now()
+        };
+
+        for (name, source, params) in BUILT_IN_EFFECTS
+        {
+            post_process.add_effect(device, name, source).expect("Built-in effects always compile");
+            let effect = post_process.effects.last_mut().unwrap();
+            effect.params = *params;
+            effect.enabled = false;
+        }
+
+        post_process
+    }
+
+    // source only needs an fs_main, everything declared in shaders/post/prelude.wgsl is available
+    // New effects get added to the end of the chain and start enabled, one that does not compile is an error and does not get added
+    pub fn add_effect(&mut self, device: &wgpu::Device, name: &str, source: &str) -> Result<()>
+    {
+        let pipeline = shader::validated(device, ||
+        {
+            let module = device.create_shader_module(wgpu::ShaderModuleDescriptor
+            {
+                label: Some(name),
+                source: wgpu::ShaderSource::Wgsl(format!("{}\n{}", PRELUDE, source).into()),
+            });
+
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor
+            {
+                label: Some(name),
+                layout: Some(&self.pipeline_layout),
+                vertex: wgpu::VertexState
+                {
+                    module: &module,
+                    entry_point: Some("vs_fullscreen"),
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default()
+                },
+                fragment: Some(wgpu::FragmentState
+                {
+                    module: &module,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState
+                    {
+                        format: self.format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default()
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None
+            })
+        }).map_err(|e| anyhow!("Effect {} does not compile: {}", name, e))?;
+
+        let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor
+        {
+            label: Some("Effect Uniform Buffer"),
+            size: std::mem::size_of::<EffectUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        self.effects.push(Effect
+        {
+            name: name.to_string(),
+            enabled: true,
+            params: [0.0; 8],
+            pipeline,
+            uniform_buf,
+            extra_texture: Arc::clone(&self.identity_lut),
+            bind_groups: None
+        });
+        Ok(())
+    }
+
+    pub fn effect(&self, name: &str) -> Option<&Effect>
+    {
+        self.effects.iter().find(|effect| effect.name == name)
+    }
+
+    pub fn effect_mut(&mut self, name: &str) -> Option<&mut Effect>
+    {
+        self.effects.iter_mut().find(|effect| effect.name == name)
+    }
+
+    pub fn effects(&self) -> &[Effect]
+    {
+        &self.effects
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool)
+    {
+        if let Some(effect) = self.effect_mut(name)
+        {
+            effect.enabled = enabled;
+        }
+    }
+
+    // Every effect has 8 params (params0 and params1 in the shader)
+    pub fn set_param(&mut self, name: &str, index: usize, value: f32) -> Result<()>
+    {
+        let effect = self.effect_mut(name).ok_or_else(|| anyhow!("No post-processing effect named {}", name))?;
+        let param = effect.params.get_mut(index).ok_or_else(|| anyhow!("Effect {} has no param {}, there are 8", name, index))?;
+        *param = value;
+        Ok(())
+    }
+
+    // Texture available as extra_texture in the shader, for color_grading this is the LUT
+    pub fn set_effect_texture(&mut self, name: &str, texture: TextureHandler)
+    {
+        if let Some(effect) = self.effect_mut(name)
+        {
+            effect.extra_texture = Arc::new(texture);
+            effect.bind_groups = None;
+        }
+    }
+
+    pub fn is_active(&self) -> bool
+    {
+        self.effects.iter().any(|effect| effect.enabled)
+    }
+
+    // (Re)creates the intermediate textures and bind groups if needed and uploads the params
+    pub(crate) fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, size: (u32, u32))
+    {
+        if !self.is_active()
+        {
+            return;
+        }
+
+        if self.targets.is_none() || self.size != size
+        {
+            self.targets = Some(
+            [
+                TextureHandler::render_target(device, size.0, size.1, self.format, TextureOptions::default()),
+                TextureHandler::render_target(device, size.0, size.1, self.format, TextureOptions::default())
+            ]);
+            self.size = size;
+
+            for effect in &mut self.effects
+            {
+                effect.bind_groups = None;
+            }
+        }
+
+        let targets = self.targets.as_ref().unwrap();
+        let time = self.start.elapsed().as_secs_f32();
+
+        for effect in self.effects.iter_mut().filter(|effect| effect.enabled)
+        {
+            if effect.bind_groups.is_none()
+            {
+                let bind_group = |input: &TextureHandler| device.create_bind_group(&wgpu::BindGroupDescriptor
+                {
+                    label: Some("Effect Bind Group"),
+                    layout: &self.bindgroup_layout,
+                    entries:
+                    &[
+                        wgpu::BindGroupEntry
+                        {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&input.view),
+                        },
+                        wgpu::BindGroupEntry
+                        {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&input.sampler),
+                        },
+                        wgpu::BindGroupEntry
+                        {
+                            binding: 2,
+                            resource: effect.uniform_buf.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry
+                        {
+                            binding: 3,
+                            resource: wgpu::BindingResource::TextureView(&effect.extra_texture.view),
+                        },
+                        wgpu::BindGroupEntry
+                        {
+                            binding: 4,
+                            resource: wgpu::BindingResource::Sampler(&effect.extra_texture.sampler),
+                        }
+                    ]
+                });
+                effect.bind_groups = Some([bind_group(&targets[0]), bind_group(&targets[1])]);
+            }
+
+            let uniforms = EffectUniforms
+            {
+                resolution: [size.0 as f32, size.1 as f32],
+                time,
+                _padding: 0.0,
+                params: effect.params
+            };
+            queue.write_buffer(&effect.uniform_buf, 0, bytemuck::cast_slice(&[uniforms]));
+        }
+    }
+
+    // Where the scene has to be rendered to, before apply gets called
+    pub(crate) fn scene_view(&self) -> &wgpu::TextureView
+    {
+        &self.targets.as_ref().expect("PostProcess::prepare was not called")[0].view
+    }
+
+    // Runs every enabled effect, ping-ponging between the two intermediate textures, the last one writes into view
+    pub(crate) fn apply(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView)
+    {
+        let targets = self.targets.as_ref().expect("PostProcess::prepare was not called");
+        let enabled: Vec<&Effect> = self.effects.iter().filter(|effect| effect.enabled).collect();
+
+        let mut input = 0;
+        for (index, effect) in enabled.iter().enumerate()
+        {
+            let output = if index == enabled.len() - 1 { view } else { &targets[1 - input].view };
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor
+            {
+                label: Some("Post Process Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment
+                {
+                    view: output,
+                    resolve_target: None,
+                    ops: wgpu::Operations
+                    {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            let bind_groups = effect.bind_groups.as_ref().expect("PostProcess::prepare was not called");
+            render_pass.set_pipeline(&effect.pipeline);
+            render_pass.set_bind_group(0, &bind_groups[input], &[]);
+            render_pass.draw(0..3, 0..1);
+
+            input = 1 - input;
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+use crate::{input::Input, renderer::Renderer, transform::{Anchor, CoordinateSpace, Transform2D}};
+
+// Height of the timeline at the bottom of the window, in window pixels
+const TIMELINE_HEIGHT: f32 = 24.0;
+
+// Keys of the preview player, the timeline gets scrubbed by holding the left mouse button on it
+#[derive(Copy, Clone, Debug)]
+pub struct PreviewControls
+{
+    pub play_pause: KeyCode,
+    pub step_back: KeyCode,
+    pub step_forward: KeyCode,
+    pub to_start: KeyCode,
+    pub to_end: KeyCode,
+    pub toggle_onion_skin: KeyCode
+}
+
+impl Default for PreviewControls
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            play_pause: KeyCode::Space,
+            step_back: KeyCode::ArrowLeft,
+            step_forward: KeyCode::ArrowRight,
+            to_start: KeyCode::Home,
+            to_end: KeyCode::End,
+            toggle_onion_skin: KeyCode::KeyO
+        }
+    }
+}
+
+// Returned from EngineEvent::preview to run game_loop as an animation preview
+// The game is not updated, the player moves time and puts the game there with EngineEvent::seek
+#[derive(Copy, Clone, Debug)]
+pub struct PreviewSettings
+{
+    pub duration: f64, // Seconds on the timeline
+    pub frame_rate: f64, // Frames per second, for stepping and onion skins
+    pub looping: bool,
+    pub onion_before: usize, // Previous frames drawn under the current one
+    pub onion_after: usize, // Next frames
+    pub before_tint: [f32; 4], // Alpha is for the closest frame, further ones fade out
+    pub after_tint: [f32; 4],
+    pub show_timeline: bool,
+    pub controls: PreviewControls
+}
+
+impl PreviewSettings
+{
+    pub fn new(duration: f64, frame_rate: f64) -> Self
+    {
+        Self
+        {
+            duration,
+            frame_rate,
+            looping: true,
+            onion_before: 0,
+            onion_after: 0,
+            before_tint: [1.0, 0.3, 0.3, 0.5],
+            after_tint: [0.3, 1.0, 0.3, 0.5],
+            show_timeline: true,
+            controls: PreviewControls::default()
+        }
+    }
+
+    pub fn with_onion_skin(mut self, before: usize, after: usize) -> Self
+    {
+        self.onion_before = before;
+        self.onion_after = after;
+        self
+    }
+
+    pub fn with_onion_tints(mut self, before: [f32; 4], after: [f32; 4]) -> Self
+    {
+        self.before_tint = before;
+        self.after_tint = after;
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self
+    {
+        self.looping = looping;
+        self
+    }
+
+    pub fn with_controls(mut self, controls: PreviewControls) -> Self
+    {
+        self.controls = controls;
+        self
+    }
+
+    pub fn with_timeline(mut self, show: bool) -> Self
+    {
+        self.show_timeline = show;
+        self
+    }
+}
+
+// Time of the preview, driven by its controls instead of the game
+pub(crate) struct PreviewPlayer
+{
+    settings: PreviewSettings,
+    time: f64,
+    playing: bool,
+    onion_skin: bool,
+    scrubbing: bool
+}
+
+impl PreviewPlayer
+{
+    pub(crate) fn new(settings: PreviewSettings) -> Self
+    {
+        Self { settings, time: 0.0, playing: false, onion_skin: true, scrubbing: false }
+    }
+
+    pub(crate) fn time(&self) -> f64
+    {
+        self.time
+    }
+
+    fn frame_time(&self) -> f64
+    {
+        1.0 / self.settings.frame_rate.max(1.0)
+    }
+
+    // Time of the frame the preview is on, frames are counted from 0
+    fn frame(&self) -> i64
+    {
+        (self.time / self.frame_time() + 1e-6).floor() as i64
+    }
+
+    // Looping previews have no frame at duration, it would be the same as frame 0, so time stays below duration
+    fn last_frame(&self) -> i64
+    {
+        let frames = self.settings.duration / self.frame_time();
+        if self.settings.looping && self.settings.duration > 0.0 { (frames - 1e-6).ceil() as i64 - 1 } else { (frames + 1e-6).floor() as i64 }
+    }
+
+    fn seek_frame(&mut self, frame: i64)
+    {
+        let frame = if self.settings.looping { frame.rem_euclid(self.last_frame() + 1) } else { frame.clamp(0, self.last_frame()) };
+        self.time = frame as f64 * self.frame_time();
+    }
+
+    pub(crate) fn update(&mut self, input: &Input, dt: f64, window_size: (f32, f32))
+    {
+        let controls = self.settings.controls;
+        if input.is_key_pressed(controls.play_pause)
+        {
+            self.playing = !self.playing;
+        }
+        if input.is_key_pressed(controls.toggle_onion_skin)
+        {
+            self.onion_skin = !self.onion_skin;
+        }
+
+        // Stepping always lands on a frame and pauses
+        if input.is_key_pressed(controls.step_back)
+        {
+            self.playing = false;
+            self.seek_frame(self.frame() - 1);
+        }
+        if input.is_key_pressed(controls.step_forward)
+        {
+            self.playing = false;
+            self.seek_frame(self.frame() + 1);
+        }
+        if input.is_key_pressed(controls.to_start)
+        {
+            self.seek_frame(0);
+        }
+        if input.is_key_pressed(controls.to_end)
+        {
+            self.seek_frame(self.last_frame());
+        }
+
+        let mouse = input.actual_mouse_position();
+        let on_timeline = self.settings.show_timeline && mouse.1 as f32 >= window_size.1 - TIMELINE_HEIGHT;
+        if input.is_mouse_pressed(MouseButton::Left) && on_timeline
+        {
+            self.scrubbing = true;
+            self.playing = false;
+        }
+        if !input.is_mouse_hold(MouseButton::Left)
+        {
+            self.scrubbing = false;
+        }
+        if self.scrubbing
+        {
+            let t = (mouse.0 as f32 / window_size.0.max(1.0)).clamp(0.0, 1.0) as f64;
+            self.seek_frame(((t * self.settings.duration / self.frame_time()).round() as i64).min(self.last_frame()));
+            return;
+        }
+
+        if self.playing
+        {
+            self.time += dt;
+            if self.settings.looping && self.settings.duration > 0.0
+            {
+                self.time = self.time.rem_euclid(self.settings.duration);
+            }
+            else if self.time > self.settings.duration
+            {
+                self.time = self.settings.duration;
+                self.playing = false;
+            }
+        }
+    }
+
+    // Times and tints of the onion skins, furthest first so the closer ones end up on top
+    pub(crate) fn onion_frames(&self) -> Vec<(f64, [f32; 4])>
+    {
+        if !self.onion_skin || self.playing
+        {
+            return Vec::new();
+        }
+
+        let settings = &self.settings;
+        let faded = |tint: [f32; 4], distance: usize, count: usize| [tint[0], tint[1], tint[2], tint[3] * (count + 1 - distance) as f32 / count as f32];
+        let mut frames = Vec::new();
+        for (count, direction, tint) in [(settings.onion_before, -1.0, settings.before_tint), (settings.onion_after, 1.0, settings.after_tint)]
+        {
+            for distance in (1..=count).rev()
+            {
+                let time = self.time + direction * distance as f64 * self.frame_time();
+                let time = if settings.looping && settings.duration > 0.0 { time.rem_euclid(settings.duration) } else { time };
+                if (0.0..=settings.duration).contains(&time)
+                {
+                    frames.push((time, faded(tint, distance, count)));
+                }
+            }
+        }
+        frames
+    }
+
+    // Bar over the whole width with the current position, over everything the game draws
+    pub(crate) fn draw_timeline(&self, renderer: &mut Renderer)
+    {
+        if !self.settings.show_timeline
+        {
+            return;
+        }
+
+        let window = renderer.viewport().window_size;
+        let bar = |x: f32, width: f32| Transform2D::new((x, window.1), 0.0, (width, TIMELINE_HEIGHT), CoordinateSpace::Screen).with_anchor(Anchor::BottomLeft);
+        let progress = if self.settings.duration > 0.0 { (self.time / self.settings.duration) as f32 } else { 0.0 };
+
+        renderer.draw(0, bar(0.0, window.0), [0.1, 0.1, 0.1, 0.8], u32::MAX);
+        renderer.draw(0, bar(0.0, window.0 * progress), [0.3, 0.5, 0.9, 0.8], u32::MAX);
+
+        // One tick per frame while there is room for them
+        let frames = self.last_frame();
+        if frames > 0 && self.settings.duration > 0.0 && window.0 / frames as f32 >= 4.0
+        {
+            for frame in 0..=frames
+            {
+                let x = (frame as f64 * self.frame_time() / self.settings.duration) as f32 * window.0;
+                let tick = Transform2D::new((x, window.1), 0.0, (1.0, TIMELINE_HEIGHT / 3.0), CoordinateSpace::Screen).with_anchor(Anchor::BottomCenter);
+                renderer.draw(0, tick, [0.6, 0.6, 0.6, 0.8], u32::MAX);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool
+    {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn looping_previews_stay_below_the_duration()
+    {
+        let mut player = PreviewPlayer::new(PreviewSettings::new(1.0, 10.0).with_onion_skin(1, 1));
+        assert_eq!(player.last_frame(), 9);
+
+        player.seek_frame(10);
+        assert!(close(player.time(), 0.0));
+        player.seek_frame(-1);
+        assert!(close(player.time(), 0.9));
+
+        // The frame after the last one is frame 0, like when stepping
+        let onion: Vec<f64> = player.onion_frames().iter().map(|(time, _)| *time).collect();
+        assert!(close(onion[0], 0.8) && close(onion[1], 0.0), "{:?}", onion);
+    }
+
+    #[test]
+    fn previews_without_looping_end_on_the_duration()
+    {
+        let mut player = PreviewPlayer::new(PreviewSettings::new(1.0, 10.0).with_looping(false).with_onion_skin(2, 2));
+        assert_eq!(player.last_frame(), 10);
+
+        player.seek_frame(12);
+        assert!(close(player.time(), 1.0));
+
+        // Only the frames before the end exist, furthest first
+        let onion = player.onion_frames();
+        assert_eq!(onion.len(), 2);
+        assert!(close(onion[0].0, 0.8) && close(onion[1].0, 0.9));
+        assert!(onion[0].1[3] < onion[1].1[3]);
+    }
+}
@@ -1,8 +1,9 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
+use anyhow::{anyhow, bail, Context, Result};
 use wgpu::util::DeviceExt;
 
-use crate::{shader::Shader, texture::TextureHandler, utility::{DrawCommand, InstanceData, Material, MaterialType, Mesh, Vertex}};
+use crate::{animation_file::{Animation, AnimationFile, AnimationFileError, AssetKind}, atlas, camera::Camera2D, nine_slice::NineSlice, pipeline::{self, BlendMode, PipelineKey}, postprocess::PostProcess, shader::{self, Shader}, skeleton_import::{self, SkeletonRig}, texture::{TextureHandler, TextureOptions}, sprite::SpriteSheet, transform::{self, Anchor, CoordinateSpace, DrawTransform, Transform2D}, viewport::{ScalingPolicy, Viewport}, utility::{DrawCommand, InstanceData, Material, MaterialResources, MaterialType, Mesh, RenderTargetId, Vertex}};
 
 
 
@@ -20,83 +21,88 @@ pub const QUAD_INDICES: &[u16] =
     2, 3, 0
 ];
 
+const IDENTITY: [[f32; 4]; 4] =
+[
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0]
+];
+
+
+struct RenderTarget
+{
+    texture: Arc<TextureHandler>,
+    size: (f32, f32)
+}
+
+// Low resolution target of pixel art mode, the scene gets rendered at virtual_size into it
+struct PixelTarget
+{
+    texture: TextureHandler,
+    bind_group: wgpu::BindGroup,
+    size: (u32, u32)
+}
 
 
 pub struct Renderer
 {
-    pub pipeline: wgpu::RenderPipeline,
+    pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+    shaders: Vec<Shader>, // 0 is the default shader
+    materials: Vec<Arc<Material>>,
+    material_layouts: HashMap<usize, wgpu::BindGroupLayout>, // By texture count
+    pending_uniforms: Vec<(usize, Vec<u8>)>,
+    pending_vertices: Vec<(usize, Vec<Vertex>)>,
+    pending_geometry: HashMap<usize, (Vec<Vertex>, Vec<u16>)>, // Everything set_mesh got this frame, one shape after another
     pub draw_commands: Vec<DrawCommand>,
     instance_buf: Option<wgpu::Buffer>,
     meshes: Vec<Mesh>, // Simple for now, later gonna change it, so it does not load all meshes ni the beginning, but only creates a mesh the first time it is requested
     pub window_size: (f32, f32),
-    pub virtual_size: (f32, f32),
+    pub virtual_size: (f32, f32), // Design resolution, can be changed at any time
+    pub scaling_policy: ScalingPolicy,
     textures: Vec<Arc<wgpu::BindGroup>>,
-    texture_bindgroup_layout: wgpu::BindGroupLayout
+    texture_handlers: Vec<Arc<TextureHandler>>, // Same ids as textures
+    white_texture: Arc<wgpu::BindGroup>, // Bound for everything that does not use a texture
+    texture_bindgroup_layout: wgpu::BindGroupLayout,
+    camera_bindgroup_layout: wgpu::BindGroupLayout,
+    camera_buf: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    camera_stride: u64,
+    cameras: Vec<[[f32; 4]; 4]>, // View-projections of this frame, 0 is the identity
+    current_camera: usize,
+    screenshot_path: Option<String>,
+    format: wgpu::TextureFormat,
+    render_targets: Vec<RenderTarget>,
+    current_target: Option<RenderTargetId>,
+    current_blend: BlendMode,
+    current_tint: [f32; 4],
+    pub pixel_art: bool, // Renders at virtual_size, snaps draws to whole pixels and upscales with ScalingPolicy::Integer
+    pixel_target: Option<PixelTarget>,
+    pub post_process: PostProcess
     // diffuse_bind_group: wgpu::BindGroup,
     // texture_bind_groups: Vec<wgpu::BindGroup>
 }
 
 impl Renderer
 {
-    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, window_size: (f32, f32)) -> Self
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, config: &wgpu::SurfaceConfiguration, window_size: (f32, f32)) -> Self
     {
-        let texture_bindgroup_layout = TextureHandler::bind_group_layout(&device);
+        let texture_bindgroup_layout = TextureHandler::bind_group_layout(device);
 
         let shader = Shader::default(device);
 
-        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor
-        {
-            label: Some("Render Pipeline Layout"),
-            bind_group_layouts: 
-            &[
-                &texture_bindgroup_layout
-            ],
-            push_constant_ranges: &[]
-        });
+        let default_material = Material::color([0.0, 0.0, 0.0, 1.0]);
+        let key = default_material.pipeline_key();
+        let camera_bindgroup_layout = pipeline::camera_bind_group_layout(device);
+        let camera_stride = device.limits().min_uniform_buffer_offset_alignment.max(std::mem::size_of::<[[f32; 4]; 4]>() as u32) as u64;
+        let (camera_buf, camera_bind_group) = Self::create_camera_buffer(device, &camera_bindgroup_layout, camera_stride, 1);
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor
-        {
-            label: Some("Render Pipeline"),
-            layout: Some(&layout),
-            vertex: wgpu::VertexState 
-            {
-                module: &shader.module,
-                entry_point: Some(&shader.vs_entry),
-                buffers: &[Vertex::desc(), InstanceData::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default()
-            },
-            fragment: Some(wgpu::FragmentState
-            {
-                module: &shader.module,
-                entry_point: Some(&shader.fs_entry),
-                targets: &[Some(wgpu::ColorTargetState
-                {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default()
-            }),
-            primitive: wgpu::PrimitiveState
-            {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill, //::Line only work with required_features: wgpu::Features::POLYGON_MODE_LINE in request device
-                unclipped_depth: false,
-                conservative: false
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState
-            {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false
-            },
-            multiview: None,
-            cache: None
-        });
+        let pipeline = pipeline::create_pipeline(device, &shader, &[&texture_bindgroup_layout, &camera_bindgroup_layout], config.format, &key);
+        let mut pipelines = HashMap::new();
+        pipelines.insert(key, pipeline);
+
+        let white_texture = TextureHandler::white(device, queue).expect("Failed to create white texture");
+        let white_texture = Arc::new(white_texture.bind_group(device, &texture_bindgroup_layout));
 
         let vertex_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor 
         {
@@ -118,21 +124,47 @@ impl Renderer
         {
             vertex_buf,
             index_buf,
-            index_count
+            index_count,
+            first_index: 0,
+            base_vertex: 0
         };
 
         let meshes = vec![quad_mesh];
 
         Self 
         { 
-            pipeline,
+            pipelines,
+            shaders: vec![shader],
+            materials: Vec::new(),
+            material_layouts: HashMap::new(),
+            pending_uniforms: Vec::new(),
+            pending_vertices: Vec::new(),
+            pending_geometry: HashMap::new(),
             draw_commands: Vec::new(),
             instance_buf: None,
             meshes,
             window_size,
             virtual_size: window_size,
+            scaling_policy: ScalingPolicy::default(),
             textures: Vec::new(),
-            texture_bindgroup_layout
+            texture_handlers: Vec::new(),
+            white_texture,
+            texture_bindgroup_layout,
+            camera_bindgroup_layout,
+            camera_buf,
+            camera_bind_group,
+            camera_stride,
+            cameras: vec![IDENTITY],
+            current_camera: 0,
+            screenshot_path: None,
+            format: config.format,
+            render_targets: Vec::new(),
+            current_target: None,
+            current_blend: BlendMode::Alpha,
+            current_tint: [1.0, 1.0, 1.0, 1.0],
+            pixel_art: false,
+            pixel_target: None,
+            post_process: PostProcess::new(device, queue, config.format)
             // diffuse_bind_group
             // texture_bind_groups
         }
@@ -140,11 +172,102 @@ impl Renderer
 
     pub fn load_texture(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str) -> usize
     {
-        let error = format!("Failed to load texture with path: {}", path);
-        let texture = TextureHandler::new(device, queue, path).expect(&error);
+        self.load_texture_with_options(device, queue, path, TextureOptions::default())
+    }
+
+    // Like load_texture, but with its own filtering and address mode, for example TextureOptions::pixel_art()
+    pub fn load_texture_with_options(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str, options: TextureOptions) -> usize
+    {
+        self.try_load_texture(device, queue, path, options).unwrap_or_else(|e| panic!("{:#}", e))
+    }
+
+    fn try_load_texture(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str, options: TextureOptions) -> Result<usize>
+    {
+        let texture = TextureHandler::new_with_options(device, queue, path, options).with_context(|| format!("Failed to load texture with path: {}", path))?;
+        Ok(self.add_texture(device, texture))
+    }
+
+    // Aseprite, TexturePacker or DragonBones json, or Spine .atlas, together with its image, every frame gets the name from the file and every tag/animation becomes a clip
+    // The image gets no mipmaps (TextureOptions::sprite_sheet), so frames do not bleed into each other when drawn smaller
+    pub fn load_atlas(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str) -> SpriteSheet
+    {
+        self.load_atlas_with_options(device, queue, path, TextureOptions::sprite_sheet())
+    }
+
+    pub fn load_atlas_with_options(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str, options: TextureOptions) -> SpriteSheet
+    {
+        self.try_load_atlas(device, queue, path, options).unwrap_or_else(|e| panic!("{:#}", e))
+    }
+
+    fn try_load_atlas(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str, options: TextureOptions) -> Result<SpriteSheet>
+    {
+        let error = || format!("Failed to load atlas with path: {}", path);
+        let text = std::fs::read_to_string(path).with_context(error)?;
+        let path = std::path::Path::new(path);
+        let atlas = if path.extension().is_some_and(|extension| extension == "atlas") { atlas::parse_spine_atlas(&text) } else { atlas::parse_atlas(&text) }.with_context(error)?;
+
+        // The image is next to the atlas file, same name with png if the file does not say
+        let image = match &atlas.image
+        {
+            Some(image) => path.with_file_name(image),
+            None => path.with_extension("png")
+        };
+
+        let texture = self.try_load_texture(device, queue, &image.to_string_lossy(), options)?;
+        Ok(atlas.into_sprite_sheet(texture))
+    }
+
+    // Animation file (see animation_file.rs) with every asset it uses, the error has the location and line of the first problem
+    // Assets that can not be loaded and frame keys outside of their atlas are errors too
+    pub fn load_animation(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str) -> Result<Animation, AnimationFileError>
+    {
+        let path = std::path::Path::new(path);
+        let json = std::fs::read_to_string(path).map_err(|error| AnimationFileError::Io { path: path.to_path_buf(), error })?;
+        let file = AnimationFile::parse(&json, path)?;
+        file.validate_asset_files(path).map_err(|error| error.with_line(&json))?;
+
+        let mut textures = HashMap::new();
+        let mut sheets = HashMap::new();
+        for (a, asset) in file.assets.iter().enumerate()
+        {
+            let asset_path = AnimationFile::asset_path(path, asset);
+            let asset_path = asset_path.to_string_lossy();
+            let loaded = match asset.kind
+            {
+                AssetKind::Texture => self.try_load_texture(device, queue, &asset_path, TextureOptions::default()).map(|texture| { textures.insert(asset.name.clone(), texture); }),
+                AssetKind::Atlas => self.try_load_atlas(device, queue, &asset_path, TextureOptions::sprite_sheet()).map(|sheet| { sheets.insert(asset.name.clone(), Arc::new(sheet)); })
+            };
+            loaded.map_err(|e| AnimationFileError::Asset { path: path.to_path_buf(), location: format!("assets[{}].path", a), line: None, message: format!("{:#}", e) }.with_line(&json))?;
+        }
+
+        file.validate_frames(path, |name| sheets.get(name).map(|sheet: &Arc<SpriteSheet>| sheet.frames.len())).map_err(|error| error.with_line(&json))?;
+
+        Ok(Animation { file, textures, sheets })
+    }
+
+    // Spine or DragonBones skeleton json with its atlas, panics if they can not be loaded, logs every feature that got skipped
+    pub fn load_skeleton(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str, atlas_path: &str) -> SkeletonRig
+    {
+        let error = format!("Failed to load skeleton with path: {}", path);
+        let json = std::fs::read_to_string(path).expect(&error);
+        let file = skeleton_import::parse_skeleton(&json).unwrap_or_else(|e| panic!("{}: {}", error, e));
+
+        let sheet = self.load_atlas(device, queue, atlas_path);
+        let rig = file.into_rig(&sheet).unwrap_or_else(|e| panic!("{}: {}", error, e));
+        for feature in &rig.unsupported
+        {
+            log::warn!("{}: {} is not supported", path, feature);
+        }
+        rig
+    }
+
+    // Registers the texture so it can be used with draw_texture and materials, returns its id
+    pub fn add_texture(&mut self, device: &wgpu::Device, texture: TextureHandler) -> usize
+    {
         let bindgroup = Arc::new(texture.bind_group(device, &self.texture_bindgroup_layout));
         let id = self.textures.len();
         self.textures.push(bindgroup);
+        self.texture_handlers.push(Arc::new(texture));
         id
     }
 
@@ -153,10 +276,7 @@ impl Renderer
         if let Ok(text) = crate::text::rasterize_char("engine/src/image/Montserrat-Bold.ttf", char)
         {
             let texture = TextureHandler::from_alpha_bitmap(device, queue, &text.0, text.1, text.2, Some("char")).expect("Failed to create Texture");
-            let bindgroup = Arc::new(texture.bind_group(device, &self.texture_bindgroup_layout));
-            let id = self.textures.len();
-            self.textures.push(bindgroup);
-            Some(id)
+            Some(self.add_texture(device, texture))
         }
         else
         {
@@ -184,10 +304,7 @@ impl Renderer
                 //
 
                 let texture = TextureHandler::from_alpha_bitmap(device, queue, &text.0, text.1, text.2, Some("text")).expect("Failed to create Texture");
-                let bindgroup = Arc::new(texture.bind_group(device, &self.texture_bindgroup_layout));
-                let id = self.textures.len();
-                self.textures.push(bindgroup);
-                Some(id)
+                Some(self.add_texture(device, texture))
             }
             Err(e) => 
             {
@@ -197,24 +314,138 @@ impl Renderer
         }
     }
 
+    // Offscreen texture with the same format as the screen, registered in textures so it can be drawn with draw_texture
+    pub fn create_render_target(&mut self, device: &wgpu::Device, width: u32, height: u32) -> RenderTargetId
+    {
+        let texture = TextureHandler::render_target(device, width, height, self.format, TextureOptions::default());
+        let texture_id = self.add_texture(device, texture);
+
+        let index = self.render_targets.len();
+        self.render_targets.push(RenderTarget { texture: Arc::clone(&self.texture_handlers[texture_id]), size: (width as f32, height as f32) });
+        RenderTargetId { index, texture_id }
+    }
+
+    // Everything drawn inside of draw goes into the target instead of the screen
+    // While drawing, window_size and virtual_size are the size of the target, so the matrix helpers work in target pixels
+    // Targets get rendered before the screen in order of creation, so a target can draw targets created before it
+    // A target only gets cleared and redrawn in frames where something is drawn into it, otherwise it keeps its content
+    // Drawing the texture of the target itself inside of draw panics, a texture can not be read and written in the same pass
+    pub fn with_target<T>(&mut self, target: RenderTargetId, draw: T) where T: FnOnce(&mut Renderer)
+    {
+        let size = self.render_targets[target.index].size;
+        let prev_sizes = (self.window_size, self.virtual_size);
+        let prev_target = self.current_target;
+
+        self.window_size = size;
+        self.virtual_size = size;
+        self.current_target = Some(target);
+
+        draw(self);
+
+        (self.window_size, self.virtual_size) = prev_sizes;
+        self.current_target = prev_target;
+    }
+
+    // The texture of a target can not be sampled while it is being drawn into, wgpu would fail validation at the end of the frame
+    fn check_target_texture(&self, texture_id: usize)
+    {
+        if let Some(target) = self.current_target && target.texture_id == texture_id
+        {
+            panic!("Render target {} samples its own texture {}, draw it into another target or the screen instead", target.index, texture_id);
+        }
+    }
+
     pub fn begin_pass(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView)
+    {
+        for (index, target) in self.render_targets.iter().enumerate()
+        {
+            if self.draw_commands.iter().any(|cmd| cmd.target.is_some_and(|t| t.index == index))
+            {
+                self.draw_pass(encoder, &target.texture.view, Some(index), wgpu::Color::TRANSPARENT);
+            }
+        }
+
+        let output = if self.post_process.is_active() { self.post_process.scene_view() } else { view };
+
+        if let Some(pixel_target) = &self.pixel_target
+        {
+            self.draw_pass(encoder, &pixel_target.texture.view, None, wgpu::Color::BLACK);
+            self.blit_pass(encoder, output, pixel_target);
+        }
+        else
+        {
+            self.draw_pass(encoder, output, None, wgpu::Color::BLACK);
+        }
+
+        if self.post_process.is_active()
+        {
+            self.post_process.apply(encoder, view);
+        }
+    }
+
+    // Upscales the low resolution target of pixel art mode into the window, uses the extra instance after the draw commands
+    fn blit_pass(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, pixel_target: &PixelTarget)
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor
+        {
+            label: Some("Pixel Art Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment
+            {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations
+                {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        if let Some(ref instance_buf) = self.instance_buf
+        {
+            let mesh = &self.meshes[0];
+            let instance_id = self.draw_commands.len() as u32;
+
+            render_pass.set_pipeline(&self.pipelines[&Material::color([0.0, 0.0, 0.0, 1.0]).pipeline_key()]);
+            render_pass.set_vertex_buffer(0, mesh.vertex_buf.slice(..));
+            render_pass.set_vertex_buffer(1, instance_buf.slice(..));
+            render_pass.set_index_buffer(mesh.index_buf.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.set_bind_group(0, &pixel_target.bind_group, &[]);
+            render_pass.set_bind_group(1, &self.camera_bind_group, &[0]);
+            render_pass.draw_indexed(0..mesh.index_count, 0, instance_id..instance_id + 1);
+        }
+    }
+
+    // Adds a custom post-processing pass from a wgsl file (see PostProcess::add_effect)
+    pub fn load_effect(&mut self, device: &wgpu::Device, name: &str, path: &str) -> Result<()>
+    {
+        let source = std::fs::read_to_string(path).with_context(|| format!("Failed to load effect with path: {}", path))?;
+        self.post_process.add_effect(device, name, &source).with_context(|| format!("Failed to load effect with path: {}", path))
+    }
+
+    // For example a LUT for color_grading
+    pub fn load_effect_texture(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, name: &str, path: &str)
+    {
+        let error = format!("Failed to load texture with path: {}", path);
+        let texture = TextureHandler::new_with_options(device, queue, path, TextureOptions::default().with_mipmaps(false)).expect(&error);
+        self.post_process.set_effect_texture(name, texture);
+    }
+
+    fn draw_pass(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, target: Option<usize>, clear_color: wgpu::Color)
     {
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor 
         {
             label: Some("Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment 
             {
-                view: &view,
+                view,
                 resolve_target: None,
                 ops: wgpu::Operations 
                 {
-                    load: wgpu::LoadOp::Clear(wgpu::Color 
-                    {
-                        r: 0.0,
-                        g: 0.0,
-                        b: 0.0,
-                        a: 1.0,
-                    }),
+                    load: wgpu::LoadOp::Clear(clear_color),
                     store: wgpu::StoreOp::Store,
                 },
             })],
@@ -223,7 +454,12 @@ impl Renderer
             timestamp_writes: None,
         });
 
-        render_pass.set_pipeline(&self.pipeline);
+        // Bars only on the screen, render targets and the pixel art target always use their whole size
+        if target.is_none() && self.pixel_target.is_none() && let Some((x, y, width, height)) = self.viewport().bars_rect()
+        {
+            render_pass.set_scissor_rect(x, y, width, height);
+        }
+
         // render_pass.set_vertex_buffer(0, self.vertex_buf.slice(..));
 
         // if let Some(ref instance_buf) = self.instance_buf
@@ -238,47 +474,476 @@ impl Renderer
         if let Some(ref instance_buf) = self.instance_buf
         {
             render_pass.set_vertex_buffer(1, instance_buf.slice(..));
+            let mut current_key = None;
             for (instance_id, cmd) in self.draw_commands.iter().enumerate()
             {
+                if cmd.target.map(|t| t.index) != target
+                {
+                    continue;
+                }
+
+                let key = cmd.pipeline_key();
+                if current_key != Some(key)
+                {
+                    render_pass.set_pipeline(&self.pipelines[&key]);
+                    current_key = Some(key);
+                }
+
                 let mesh = &self.meshes[cmd.mesh_id];
 
-                render_pass.set_vertex_buffer(0, mesh.vertex_buf.slice(..));
+                // The vertex buffer starts at the shape of the draw, base_vertex of draw_indexed is not there on WebGL
+                render_pass.set_vertex_buffer(0, mesh.vertex_buf.slice(cmd.base_vertex as u64 * std::mem::size_of::<Vertex>() as u64..));
                 render_pass.set_index_buffer(mesh.index_buf.slice(..), wgpu::IndexFormat::Uint16);
 
 
                 // render_pass.set_bind_group(0, &self.diffuse_bind_group, &[]);
                 match &cmd.material.kind 
                 {
-                    MaterialType::Color(_) =>
+                    MaterialType::Color(_) | MaterialType::Custom(None) =>
                     {
-                        render_pass.set_bind_group(0, self.textures[0].as_ref(), &[]);
+                        render_pass.set_bind_group(0, self.white_texture.as_ref(), &[]);
                     }
-                    MaterialType::Texture(texture) => 
+                    MaterialType::Texture(texture) | MaterialType::Custom(Some(texture)) => 
                     {
                         let test = texture.as_ref();
                         render_pass.set_bind_group(0, test, &[]);
                     }
                 }
 
+                render_pass.set_bind_group(1, &self.camera_bind_group, &[(cmd.camera as u64 * self.camera_stride) as u32]);
+
+                if let Some(resources) = &cmd.material.resources
+                {
+                    render_pass.set_bind_group(2, &resources.bind_group, &[]);
+                }
+
 
-                render_pass.draw_indexed(0..mesh.index_count, 0, instance_id as u32..instance_id as u32 + 1);
+                render_pass.draw_indexed(cmd.indices.clone(), 0, instance_id as u32..instance_id as u32 + 1);
             }
         }
     }
 
-    pub fn draw(&mut self, mesh_id: usize, transform: [[f32; 4]; 4], color: [f32; 4], z_index: u32)
+    // Part of the mesh buffers a draw of it uses right now
+    fn mesh_range(&self, mesh_id: usize) -> (std::ops::Range<u32>, u32)
     {
-        self.draw_commands.push(DrawCommand { mesh_id, transform, /*kind: DrawType::Color(color), */z_index, material: Arc::new(Material::color(color)) });
+        let mesh = &self.meshes[mesh_id];
+        (mesh.first_index..mesh.first_index + mesh.index_count, mesh.base_vertex)
     }
 
-    pub fn draw_texture(&mut self, mesh_id: usize, transform: [[f32; 4]; 4], texture_id: usize, z_index: u32)
+    pub fn draw(&mut self, mesh_id: usize, transform: impl DrawTransform, color: [f32; 4], z_index: u32)
     {
+        let (transform, camera) = transform.resolve(self);
+        let (indices, base_vertex) = self.mesh_range(mesh_id);
+        self.draw_commands.push(DrawCommand { mesh_id, transform, /*kind: DrawType::Color(color), */z_index, material: Arc::new(Material::color(color)), target: self.current_target, blend: self.current_blend, camera, uv_min: [0.0, 0.0], uv_max: [1.0, 1.0], tint: self.current_tint, indices, base_vertex });
+    }
+
+    pub fn draw_texture(&mut self, mesh_id: usize, transform: impl DrawTransform, texture_id: usize, z_index: u32)
+    {
+        self.check_target_texture(texture_id);
+        let (transform, camera) = transform.resolve(self);
         let texture = Arc::clone(&self.textures[texture_id]);
-        self.draw_commands.push(DrawCommand { mesh_id, transform, /*kind: DrawType::Texture(texture_id), */z_index, material: Arc::new(Material::texture(texture)) });
+        let (indices, base_vertex) = self.mesh_range(mesh_id);
+        self.draw_commands.push(DrawCommand { mesh_id, transform, /*kind: DrawType::Texture(texture_id), */z_index, material: Arc::new(Material::texture(texture)), target: self.current_target, blend: self.current_blend, camera, uv_min: [0.0, 0.0], uv_max: [1.0, 1.0], tint: self.current_tint, indices, base_vertex });
+    }
+
+    // Like draw_sprite, but only the source rect (x, y, width, height in pixels of the texture), for sprite sheets and atlases
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_sprite_region(&mut self, texture_id: usize, source: (f32, f32, f32, f32), pos: (f32, f32), scale: (f32, f32), rotation: f32, anchor: Anchor, z_index: u32)
+    {
+        let size = self.texture_size(texture_id);
+        let uv_min = [source.0 / size.0, source.1 / size.1];
+        let uv_max = [(source.0 + source.2) / size.0, (source.1 + source.3) / size.1];
+
+        let transform = Transform2D::new(pos, rotation, (source.2 * scale.0, source.3 * scale.1), CoordinateSpace::Virtual).with_pivot(anchor.pivot((source.2, source.3)));
+        self.draw_texture_uv(0, transform, texture_id, uv_min, uv_max, z_index);
+    }
+
+    // Like draw_texture, but only the uv_min..uv_max part of the texture, uvs outside of 0..1 repeat or clamp depending on the TextureOptions of the texture
+    pub fn draw_texture_uv(&mut self, mesh_id: usize, transform: impl DrawTransform, texture_id: usize, uv_min: [f32; 2], uv_max: [f32; 2], z_index: u32)
+    {
+        self.draw_texture(mesh_id, transform, texture_id, z_index);
+        let cmd = self.draw_commands.last_mut().unwrap();
+        (cmd.uv_min, cmd.uv_max) = (uv_min, uv_max);
+    }
+
+    // Fills rect (x, y, width, height in virtual pixels) with copies of the texture, for backgrounds and floors
+    // One tile is the texture size times uv_scale, uv_offset moves the tiles (1.0 = one whole tile), so it can be increased over time to scroll
+    // The texture has to be loaded with TextureOptions::repeating(), otherwise the edge pixels get stretched
+    pub fn draw_tiled(&mut self, texture_id: usize, rect: (f32, f32, f32, f32), uv_offset: (f32, f32), uv_scale: (f32, f32), z_index: u32)
+    {
+        let size = self.texture_size(texture_id);
+        let tiles = (rect.2 / (size.0 * uv_scale.0), rect.3 / (size.1 * uv_scale.1));
+        // Only the fraction matters, keeps the uvs small when the offset grows forever
+        let offset = (uv_offset.0.rem_euclid(1.0), uv_offset.1.rem_euclid(1.0));
+
+        let transform = Transform2D::new((rect.0, rect.1), 0.0, (rect.2, rect.3), CoordinateSpace::Virtual).with_anchor(Anchor::TopLeft);
+        self.draw_texture_uv(0, transform, texture_id, [offset.0, offset.1], [offset.0 + tiles.0, offset.1 + tiles.1], z_index);
+    }
+
+    // Draws the texture stretched over rect (x, y, width, height in virtual pixels) without stretching its borders
+    pub fn draw_nine_slice(&mut self, texture_id: usize, slice: &NineSlice, rect: (f32, f32, f32, f32), z_index: u32)
+    {
+        for (piece, uv_min, uv_max) in slice.pieces(self.texture_size(texture_id), rect)
+        {
+            let transform = Transform2D::new((piece.0, piece.1), 0.0, (piece.2, piece.3), CoordinateSpace::Virtual).with_anchor(Anchor::TopLeft);
+            self.draw_texture_uv(0, transform, texture_id, uv_min, uv_max, z_index);
+        }
+    }
+
+    // Size in pixels of a loaded texture
+    pub fn texture_size(&self, texture_id: usize) -> (f32, f32)
+    {
+        let size = self.texture_handlers[texture_id].texture.size();
+        (size.width as f32, size.height as f32)
+    }
+
+    // Draws the texture at its own size times scale in virtual pixels, placed and rotated around anchor
+    pub fn draw_sprite(&mut self, texture_id: usize, pos: (f32, f32), scale: (f32, f32), rotation: f32, anchor: Anchor, z_index: u32)
+    {
+        let size = self.texture_size(texture_id);
+        let transform = Transform2D::new(pos, rotation, (size.0 * scale.0, size.1 * scale.1), CoordinateSpace::Virtual).with_pivot(anchor.pivot(size));
+        self.draw_texture(0, transform, texture_id, z_index);
+    }
+
+    // Mesh for draw calls, positions in the same units as the quad (y up), triangles counter-clockwise or they get culled
+    pub fn create_mesh(&mut self, device: &wgpu::Device, vertices: &[Vertex], indices: &[u16]) -> usize
+    {
+        let vertex_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor
+        {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let index_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor
+        {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        self.meshes.push(Mesh { vertex_buf, index_buf, index_count: indices.len() as u32, first_index: 0, base_vertex: 0 });
+        self.meshes.len() - 1
+    }
+
+    // Replaces the vertices of a mesh from create_mesh, the count has to stay the same
+    // Gets uploaded together with the instances, so every draw of the mesh in this frame uses the last vertices set
+    pub fn set_mesh_vertices(&mut self, mesh_id: usize, vertices: &[Vertex])
+    {
+        assert_eq!(self.meshes[mesh_id].vertex_buf.size(), std::mem::size_of_val(vertices) as u64, "Mesh vertex count can not change");
+        self.pending_vertices.push((mesh_id, vertices.to_vec()));
+    }
+
+    // Mesh whose vertices and triangles change every frame (set_mesh), the counts are only the starting capacity
+    pub fn create_dynamic_mesh(&mut self, device: &wgpu::Device, vertices: usize, indices: usize) -> usize
+    {
+        let vertex_buf = dynamic_buffer(device, (vertices * std::mem::size_of::<Vertex>()) as u64, wgpu::BufferUsages::VERTEX);
+        let index_buf = dynamic_buffer(device, (indices * std::mem::size_of::<u16>()) as u64, wgpu::BufferUsages::INDEX);
+
+        self.meshes.push(Mesh { vertex_buf, index_buf, index_count: 0, first_index: 0, base_vertex: 0 });
+        self.meshes.len() - 1
+    }
+
+    // Replaces vertices and triangles of a mesh from create_dynamic_mesh, uploaded together with the instances
+    // Draws use the geometry set last before them, so the mesh can be set and drawn again in the same frame (like for onion skins)
+    // The buffers grow when they are too small
+    pub fn set_mesh(&mut self, mesh_id: usize, vertices: &[Vertex], indices: &[u16])
+    {
+        let (frame_vertices, frame_indices) = self.pending_geometry.entry(mesh_id).or_default();
+        let mesh = &mut self.meshes[mesh_id];
+        mesh.first_index = frame_indices.len() as u32;
+        mesh.base_vertex = frame_vertices.len() as u32;
+        mesh.index_count = indices.len() as u32;
+
+        frame_vertices.extend_from_slice(vertices);
+        frame_indices.extend_from_slice(indices);
     }
 
-    pub fn upload_instances(&mut self, device: &wgpu::Device)
+    // Shader for create_material, see Shader::material for what the file has to contain
+    pub fn load_shader(&mut self, device: &wgpu::Device, path: &str) -> Result<usize>
     {
+        self.shaders.push(Shader::material(device, path)?);
+        Ok(self.shaders.len() - 1)
+    }
+
+    // textures are bound in group 2 of the shader in the given order, the first one is also bound in group 0
+    // uniforms is the initial content of the uniform buffer (binding 0 in group 2), its size can not change later
+    // A shader whose bindings do not fit the textures and uniforms is an error
+    pub fn create_material(&mut self, device: &wgpu::Device, shader: usize, textures: &[usize], uniforms: &[u8]) -> Result<usize>
+    {
+        // Uniform buffers have to be at least 16 bytes and a multiple of 16
+        let mut contents = uniforms.to_vec();
+        contents.resize(contents.len().max(1).next_multiple_of(16), 0);
+
+        let uniform_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor
+        {
+            label: Some("Material Uniform Buffer"),
+            contents: &contents,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
+        });
+
+        let layout = self.material_layouts.entry(textures.len()).or_insert_with(|| pipeline::material_bind_group_layout(device, textures.len()));
+
+        let mut entries = vec![wgpu::BindGroupEntry
+        {
+            binding: 0,
+            resource: uniform_buf.as_entire_binding()
+        }];
+        for (i, texture_id) in textures.iter().enumerate()
+        {
+            let texture = &self.texture_handlers[*texture_id];
+            entries.push(wgpu::BindGroupEntry
+            {
+                binding: 1 + i as u32 * 2,
+                resource: wgpu::BindingResource::TextureView(&texture.view)
+            });
+            entries.push(wgpu::BindGroupEntry
+            {
+                binding: 2 + i as u32 * 2,
+                resource: wgpu::BindingResource::Sampler(&texture.sampler)
+            });
+        }
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor
+        {
+            label: Some("Material Bind Group"),
+            layout,
+            entries: &entries
+        });
+
+        let texture = textures.first().map(|id| Arc::clone(&self.textures[*id]));
+        let resources = MaterialResources { bind_group, uniform_buf, textures: textures.to_vec() };
+        let material = Material::custom(shader, texture, resources);
+
+        // Built right away, so a broken material shows up here and not in the middle of a frame
+        self.create_pipeline(device, material.pipeline_key())?;
+        self.materials.push(Arc::new(material));
+        Ok(self.materials.len() - 1)
+    }
+
+    // Cached pipeline for key, wgpu validation errors of the shader come back as the error
+    fn create_pipeline(&mut self, device: &wgpu::Device, key: PipelineKey) -> Result<()>
+    {
+        if self.pipelines.contains_key(&key)
+        {
+            return Ok(());
+        }
+
+        let mut layouts = vec![&self.texture_bindgroup_layout, &self.camera_bindgroup_layout];
+        if let Some(texture_count) = key.texture_count
+        {
+            layouts.push(self.material_layouts.entry(texture_count).or_insert_with(|| pipeline::material_bind_group_layout(device, texture_count)));
+        }
+        let shader = &self.shaders[key.shader];
+        let pipeline = shader::validated(device, || pipeline::create_pipeline(device, shader, &layouts, self.format, &key))
+            .map_err(|e| anyhow!("Shader {} does not fit its material: {}", shader.label, e))?;
+        self.pipelines.insert(key, pipeline);
+        Ok(())
+    }
+
+    // Only possible while the material is not used by any draw command of the current frame
+    pub fn material_mut(&mut self, material: usize) -> &mut Material
+    {
+        Arc::get_mut(&mut self.materials[material]).expect("Material is still used by draw commands")
+    }
+
+    // Gets uploaded together with the instances, so it applies to the whole frame
+    // Can be smaller than the uniform buffer, the rest keeps its content
+    pub fn set_material_uniforms(&mut self, material: usize, uniforms: &[u8])
+    {
+        let resources = self.materials[material].resources.as_ref().expect("Only materials created with create_material have uniforms");
+        let size = resources.uniform_buf.size();
+        assert!(uniforms.len() as u64 <= size, "Material {} has {} bytes of uniforms, got {}", material, size, uniforms.len());
+
+        // Buffer writes have to be a multiple of 4 bytes
+        let mut contents = uniforms.to_vec();
+        contents.resize(contents.len().next_multiple_of(4), 0);
+        self.pending_uniforms.push((material, contents));
+    }
+
+    pub fn draw_with_material(&mut self, mesh_id: usize, transform: impl DrawTransform, material: usize, z_index: u32)
+    {
+        let (transform, camera) = transform.resolve(self);
+        let material = Arc::clone(&self.materials[material]);
+        for texture_id in material.resources.iter().flat_map(|resources| &resources.textures)
+        {
+            self.check_target_texture(*texture_id);
+        }
+        let blend = material.blend;
+        let (indices, base_vertex) = self.mesh_range(mesh_id);
+        self.draw_commands.push(DrawCommand { mesh_id, transform, z_index, material, target: self.current_target, blend, camera, uv_min: [0.0, 0.0], uv_max: [1.0, 1.0], tint: self.current_tint, indices, base_vertex });
+    }
+
+    // Everything drawn inside of draw is in world space (see Camera2D), use world_matrix for the transforms
+    // The view-projection gets uploaded once per frame, every call adds one camera, so a render target can use its own
+    pub fn with_camera<T>(&mut self, camera: &Camera2D, draw: T) where T: FnOnce(&mut Renderer)
+    {
+        let prev_camera = self.current_camera;
+        self.cameras.push(camera.view_projection(&self.render_viewport(), self.pixel_art));
+        self.current_camera = self.cameras.len() - 1;
+
+        draw(self);
+
+        self.current_camera = prev_camera;
+    }
+
+    fn create_camera_buffer(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, stride: u64, count: usize) -> (wgpu::Buffer, wgpu::BindGroup)
+    {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor
+        {
+            label: Some("Camera Buffer"),
+            size: stride * count as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor
+        {
+            label: Some("Camera Bind Group"),
+            layout,
+            entries:
+            &[
+                wgpu::BindGroupEntry
+                {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding
+                    {
+                        buffer: &buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(std::mem::size_of::<[[f32; 4]; 4]>() as u64)
+                    })
+                }
+            ]
+        });
+
+        (buffer, bind_group)
+    }
+
+    // Resets everything that only lasts for one frame
+    pub(crate) fn end_frame(&mut self)
+    {
+        self.draw_commands.clear();
+        self.cameras.truncate(1);
+        self.current_camera = 0;
+    }
+
+    // draw and draw_texture inside of draw use the blend mode, materials keep their own
+    // Draws are still ordered by z_index, the pipeline only gets switched when the blend mode changes between two draws
+    pub fn with_blend_mode<T>(&mut self, blend: BlendMode, draw: T) where T: FnOnce(&mut Renderer)
+    {
+        let prev_blend = self.current_blend;
+        self.current_blend = blend;
+
+        draw(self);
+
+        self.current_blend = prev_blend;
+    }
+
+    // Everything drawn inside of draw gets multiplied with tint, for fading or coloring a whole group of draws
+    pub fn with_tint<T>(&mut self, tint: [f32; 4], draw: T) where T: FnOnce(&mut Renderer)
+    {
+        let prev_tint = self.current_tint;
+        self.current_tint = [0, 1, 2, 3].map(|i| prev_tint[i] * tint[i]);
+
+        draw(self);
+
+        self.current_tint = prev_tint;
+    }
+
+    // Saves the next presented frame to path (as png, jpg, ... depending on the extension)
+    pub fn request_screenshot(&mut self, path: &str)
+    {
+        self.screenshot_path = Some(path.to_string());
+    }
+
+    pub(crate) fn take_screenshot_request(&mut self) -> Option<String>
+    {
+        self.screenshot_path.take()
+    }
+
+    pub fn upload_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue)
+    {
+        for (mesh_id, vertices) in self.pending_vertices.drain(..)
+        {
+            let mesh = &mut self.meshes[mesh_id];
+            let size = std::mem::size_of_val(vertices.as_slice()) as u64;
+            if mesh.vertex_buf.size() < size
+            {
+                mesh.vertex_buf = dynamic_buffer(device, size.next_power_of_two(), wgpu::BufferUsages::VERTEX);
+            }
+            queue.write_buffer(&mesh.vertex_buf, 0, bytemuck::cast_slice(&vertices));
+        }
+
+        for (mesh_id, (vertices, mut indices)) in self.pending_geometry.drain()
+        {
+            // Writes have to be a multiple of 4 bytes, so odd index counts get one more
+            if indices.len() % 2 == 1
+            {
+                indices.push(0);
+            }
+
+            let mesh = &mut self.meshes[mesh_id];
+            let size = std::mem::size_of_val(vertices.as_slice()) as u64;
+            if mesh.vertex_buf.size() < size
+            {
+                mesh.vertex_buf = dynamic_buffer(device, size.next_power_of_two(), wgpu::BufferUsages::VERTEX);
+            }
+            queue.write_buffer(&mesh.vertex_buf, 0, bytemuck::cast_slice(&vertices));
+
+            let size = std::mem::size_of_val(indices.as_slice()) as u64;
+            if mesh.index_buf.size() < size
+            {
+                mesh.index_buf = dynamic_buffer(device, size.next_power_of_two(), wgpu::BufferUsages::INDEX);
+            }
+            queue.write_buffer(&mesh.index_buf, 0, bytemuck::cast_slice(&indices));
+        }
+
+        for (material, uniforms) in self.pending_uniforms.drain(..)
+        {
+            if let Some(resources) = &self.materials[material].resources
+            {
+                queue.write_buffer(&resources.uniform_buf, 0, &uniforms);
+            }
+        }
+
+        if self.camera_buf.size() < self.cameras.len() as u64 * self.camera_stride
+        {
+            (self.camera_buf, self.camera_bind_group) = Self::create_camera_buffer(device, &self.camera_bindgroup_layout, self.camera_stride, self.cameras.len());
+        }
+        let mut camera_data = vec![0u8; self.cameras.len() * self.camera_stride as usize];
+        for (i, view_proj) in self.cameras.iter().enumerate()
+        {
+            let offset = i * self.camera_stride as usize;
+            camera_data[offset..offset + std::mem::size_of::<[[f32; 4]; 4]>()].copy_from_slice(bytemuck::cast_slice(view_proj));
+        }
+        queue.write_buffer(&self.camera_buf, 0, &camera_data);
+
+        // Draws whose pipeline can not be built (a material shader that breaks with another blend mode or topology) get skipped
+        let missing: Vec<PipelineKey> = self.draw_commands.iter().map(DrawCommand::pipeline_key).filter(|key| !self.pipelines.contains_key(key)).collect();
+        for key in missing
+        {
+            if let Err(e) = self.create_pipeline(device, key)
+            {
+                log::error!("{}", e);
+                self.draw_commands.retain(|cmd| cmd.pipeline_key() != key);
+            }
+        }
+
+        if self.pixel_art
+        {
+            let size = (self.virtual_size.0.round().max(1.0) as u32, self.virtual_size.1.round().max(1.0) as u32);
+            if self.pixel_target.as_ref().is_none_or(|target| target.size != size)
+            {
+                let texture = TextureHandler::render_target(device, size.0, size.1, self.format, TextureOptions::pixel_art());
+                let bind_group = texture.bind_group(device, &self.texture_bindgroup_layout);
+                self.pixel_target = Some(PixelTarget { texture, bind_group, size });
+            }
+        }
+        else
+        {
+            self.pixel_target = None;
+        }
+
         if self.draw_commands.is_empty()
         {
             self.instance_buf = None;
@@ -293,9 +958,10 @@ impl Renderer
 
         self.draw_commands.sort_by_key(|cmd| cmd.z_index);
 
-        let instances: Vec<InstanceData> = self.draw_commands.iter().map(|cmd|
+        let mut instances: Vec<InstanceData> = self.draw_commands.iter().map(|cmd|
         {
             let material = &cmd.material;
+            let premultiply = if cmd.blend.premultiplies() { pipeline::PREMULTIPLY_MODE } else { 0 };
             // match cmd.kind
             // {
             //     DrawType::Color(color) => 
@@ -320,22 +986,46 @@ impl Renderer
                 MaterialType::Color(color) => InstanceData
                 {
                     model: cmd.transform,
-                    color: color,
-                    mode: 0,
-                    uv_min: [0.0,0.0], 
-                    uv_max: [1.0,1.0]
+                    color: [0, 1, 2, 3].map(|i| color[i] * cmd.tint[i]),
+                    mode: premultiply, // 0 = color
+                    uv_min: cmd.uv_min,
+                    uv_max: cmd.uv_max
                 },
                 MaterialType::Texture(_) => InstanceData
                 {
                     model: cmd.transform,
-                    color: [0.0, 0.0, 0.0, 1.0], // Ignored here
-                    mode: 1,
-                    uv_min: [0.0,0.0], 
-                    uv_max: [1.0,1.0]
+                    color: cmd.tint, // Multiplies the texture
+                    mode: 1 + premultiply,
+                    uv_min: cmd.uv_min,
+                    uv_max: cmd.uv_max
+                },
+                MaterialType::Custom(_) => InstanceData
+                {
+                    model: cmd.transform,
+                    color: cmd.tint,
+                    mode: 2 + premultiply,
+                    uv_min: cmd.uv_min,
+                    uv_max: cmd.uv_max
                 }
             }
         }).collect();
 
+        // Quad that covers the integer scaled area of the window, for blit_pass
+        if self.pixel_target.is_some()
+        {
+            let viewport = self.viewport();
+            let (scale, offset) = (viewport.scale(), viewport.offset());
+            let transform = Transform2D::new(offset, 0.0, (self.virtual_size.0 * scale.0, self.virtual_size.1 * scale.1), CoordinateSpace::Screen).with_anchor(Anchor::TopLeft);
+            instances.push(InstanceData
+            {
+                model: transform::to_model(transform::to_clip(&transform, &viewport, false, false)),
+                color: [1.0, 1.0, 1.0, 1.0],
+                mode: 1,
+                uv_min: [0.0, 0.0],
+                uv_max: [1.0, 1.0]
+            });
+        }
+
         self.instance_buf = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor
         {
             label: Some("Instance Buffer"),
@@ -344,41 +1034,58 @@ impl Renderer
         }));
     }
 
-    // pos in pixels, size as in 1.0 is default scale, rotation in radians (all for 2D, would work for 3D, but this is 2D)
-    pub fn to_matrix(&self, pos: (f32, f32), size: (f32, f32), rotation: f32) -> [[f32; 4]; 4]
+    // Model matrix of a transform, in clip space or in world space if it is drawn with a camera
+    pub fn transform_matrix(&self, transform: &Transform2D) -> [[f32; 4]; 4]
+    {
+        transform::to_model(transform::to_clip(transform, &self.render_viewport(), self.current_camera != 0, self.pixel_art))
+    }
+
+    // How virtual_size fits into the window right now, pixel art mode always uses integer scaling
+    pub fn viewport(&self) -> Viewport
+    {
+        let policy = if self.pixel_art { ScalingPolicy::Integer } else { self.scaling_policy };
+        Viewport::new(self.window_size, self.virtual_size, policy)
+    }
+
+    // Viewport the draws get rendered with, in pixel art mode the scene fills the low resolution target
+    // So Screen space is the same as Virtual there
+    fn render_viewport(&self) -> Viewport
     {
-        let aspect = self.window_size.0/self.window_size.1;
-        let scale = 1./aspect;
+        if self.pixel_art && self.current_target.is_none()
+        {
+            Viewport::new(self.virtual_size, self.virtual_size, ScalingPolicy::Stretch)
+        }
+        else
+        {
+            self.viewport()
+        }
+    }
 
-        let cos = rotation.cos();
-        let sin = rotation.sin();
+    pub(crate) fn current_camera(&self) -> usize
+    {
+        self.current_camera
+    }
+
+    // For draws inside of with_camera, everything in world units (pixels at zoom 1, y down)
+    pub fn world_matrix(&self, pos: (f32, f32), size: (f32, f32), rotation: f32) -> [[f32; 4]; 4]
+    {
+        let transform = Transform2D::new(pos, rotation, size, CoordinateSpace::World);
+        transform::to_model(transform::to_clip(&transform, &self.render_viewport(), true, self.pixel_art))
+    }
 
-        [
-            [scale*cos*size.0, sin*size.0, 0.0, 0.0], 
-            [scale*-sin*size.1, cos*size.1, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0], 
-            [(pos.0/self.window_size.0)*2.0-1.0, -((pos.1/self.window_size.1)*2.0-1.0), 0.0, 1.0]
-        ]
+    // pos in pixels, size as in 1.0 is default scale, rotation in radians (all for 2D, would work for 3D, but this is 2D)
+    pub fn to_matrix(&self, pos: (f32, f32), size: (f32, f32), rotation: f32) -> [[f32; 4]; 4]
+    {
+        // 1.0 is half the window height
+        let half_height = self.window_size.1 / 2.0;
+        self.transform_matrix(&Transform2D::new(pos, rotation, (size.0 * half_height, size.1 * half_height), CoordinateSpace::Screen))
     }
 
     // Size in pixels now too
     // Always stays the same size, even if screen gets resized (so always 100px big for example), so not relative says but static
     pub fn pixel_matrix(&self, pos: (f32, f32), size: (f32, f32), rotation: f32) -> [[f32; 4]; 4]
     {
-        let aspect = self.window_size.0/self.window_size.1;
-        let scale = 1./aspect;
-
-        let cos = rotation.cos();
-        let sin = rotation.sin();
-
-        let pixel_size = ((size.0/self.window_size.1)*2.0, (size.1/self.window_size.1)*2.0);
-
-        [
-            [scale*cos*pixel_size.0, sin*pixel_size.0, 0.0, 0.0], 
-            [scale*-sin*pixel_size.1, cos*pixel_size.1, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0], 
-            [(pos.0/self.window_size.0)*2.0-1.0, -((pos.1/self.window_size.1)*2.0-1.0), 0.0, 1.0]
-        ]
+        self.transform_matrix(&Transform2D::new(pos, rotation, size, CoordinateSpace::Screen))
     }
 
     // Still draws with pixels, but this time everything gets drawn like it looks with the original screen-size, so resized looks the same (in relation to each other)
@@ -386,40 +1093,147 @@ impl Renderer
     // Because everything here is in relation to the original "virtual" size, not the actual window size
     pub fn matrix(&self, pos: (f32, f32), size: (f32, f32), rotation: f32) -> [[f32; 4]; 4]
     {
-        let aspect = self.window_size.0/self.window_size.1;
-        let scale = 1./aspect;
-
-        let cos = rotation.cos();
-        let sin = rotation.sin();
-
-        let scale_x = (size.0/self.virtual_size.1)*2.0;
-        let scale_y = (size.1/self.virtual_size.1)*2.0;
+        self.transform_matrix(&Transform2D::new(pos, rotation, size, CoordinateSpace::Virtual))
+    }
 
-        [
-            [scale*cos*scale_x, sin*scale_x, 0.0, 0.0], 
-            [scale*-sin*scale_y, cos*scale_y, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0], 
-            [(pos.0/self.virtual_size.0)*2.0-1.0, -((pos.1/self.virtual_size.1)*2.0-1.0), 0.0, 1.0]
-        ]
+    // Like matrix, but pos is where the anchor of the quad ends up and rotation goes around it
+    pub fn anchored_matrix(&self, pos: (f32, f32), size: (f32, f32), rotation: f32, anchor: Anchor) -> [[f32; 4]; 4]
+    {
+        self.transform_matrix(&Transform2D::new(pos, rotation, size, CoordinateSpace::Virtual).with_anchor(anchor))
     }
 
     // Size in relative to the original, not pixels
     pub fn texture_matrix(&self, pos: (f32, f32), scale: (f32, f32), rotation: f32, texture_size: (f32, f32)) -> [[f32; 4]; 4]
     {
-        let aspect = self.window_size.0/self.window_size.1;
-        let scale_fix = 1./aspect;
+        self.matrix(pos, (texture_size.0 * scale.0, texture_size.1 * scale.1), rotation)
+    }
+}
+
+// Buffer of a dynamic mesh, the size gets rounded up to the 4 bytes writes need
+fn dynamic_buffer(device: &wgpu::Device, size: u64, usage: wgpu::BufferUsages) -> wgpu::Buffer
+{
+    device.create_buffer(&wgpu::BufferDescriptor
+    {
+        label: Some("Dynamic Mesh Buffer"),
+        size: size.max(4).next_multiple_of(4),
+        usage: usage | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false
+    })
+}
+
+// Frame that is being copied back from the gpu, started right before the frame gets presented
+pub struct FrameCapture
+{
+    buffer: wgpu::Buffer,
+    size: (u32, u32),
+    padded_bytes_per_row: u32, // Rows in the buffer are aligned to 256 bytes
+    swap_rb: bool, // Bgra surface
+    mapped: std::sync::mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>
+}
+
+impl FrameCapture
+{
+    // Starts copying texture (the presented surface texture, it needs COPY_SRC) into a staging buffer that is read back on a later frame (see poll)
+    // Has to be called after the frame got submitted and before it gets presented
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> Result<Self>
+    {
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC)
+        {
+            bail!("The surface can not be copied on this platform");
+        }
+
+        let swap_rb = match texture.format()
+        {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => bail!("Screenshots are not supported for surface format {:?}", format)
+        };
+
+        let extent = texture.size();
+        let size = (extent.width, extent.height);
+
+        // Rows in the buffer have to be aligned to 256 bytes
+        let unpadded_bytes_per_row = 4 * size.0;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let staging_buf = device.create_buffer(&wgpu::BufferDescriptor
+        {
+            label: Some("Screenshot Staging Buffer"),
+            size: (padded_bytes_per_row * size.1) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor
+        {
+            label: Some("Screenshot Encoder"),
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo
+            {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo
+            {
+                buffer: &staging_buf,
+                layout: wgpu::TexelCopyBufferLayout
+                {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.1),
+                }
+            },
+            extent
+        );
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        // Mapping finishes in the background, blocking here would stall the frame and is not possible on the web
+        let (sender, receiver) = std::sync::mpsc::channel();
+        staging_buf.slice(..).map_async(wgpu::MapMode::Read, move |result| { let _ = sender.send(result); });
+
+        Ok(FrameCapture { buffer: staging_buf, size, padded_bytes_per_row, swap_rb, mapped: receiver })
+    }
+
+    // The image once the copy is done, None while it is still running, never blocks
+    pub fn poll(&self, device: &wgpu::Device) -> Option<Result<image::RgbaImage>>
+    {
+        device.poll(wgpu::Maintain::Poll);
+        let mapped = match self.mapped.try_recv()
+        {
+            Ok(mapped) => mapped,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return None,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => return Some(Err(anyhow!("Screenshot buffer was dropped before it got mapped")))
+        };
+        if let Err(e) = mapped
+        {
+            return Some(Err(e.into()));
+        }
 
-        let cos = rotation.cos();
-        let sin = rotation.sin();
+        let unpadded_bytes_per_row = 4 * self.size.0 as usize;
+        let mut pixels = Vec::with_capacity(unpadded_bytes_per_row * self.size.1 as usize);
+        {
+            let data = self.buffer.slice(..).get_mapped_range();
+            for row in data.chunks(self.padded_bytes_per_row as usize)
+            {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row]);
+            }
+        }
+        self.buffer.unmap();
 
-        let scale_x = (texture_size.0/self.virtual_size.1)*2.0 * scale.0;
-        let scale_y = (texture_size.1/self.virtual_size.1)*2.0 * scale.1;
+        if self.swap_rb
+        {
+            for pixel in pixels.chunks_exact_mut(4)
+            {
+                pixel.swap(0, 2);
+            }
+        }
 
-        [
-            [scale_fix*cos*scale_x, sin*scale_x, 0.0, 0.0], 
-            [scale_fix*-sin*scale_y, cos*scale_y, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0], 
-            [(pos.0/self.virtual_size.0)*2.0-1.0, -((pos.1/self.virtual_size.1)*2.0-1.0), 0.0, 1.0]
-        ]
+        Some(image::RgbaImage::from_raw(self.size.0, self.size.1, pixels).ok_or_else(|| anyhow!("Screenshot buffer has the wrong size")))
     }
-}
\ No newline at end of file
+}
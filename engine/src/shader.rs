@@ -1,8 +1,11 @@
 use std::fs;
 
+use anyhow::{anyhow, Context, Result};
+
 pub struct Shader
 {
     pub module: wgpu::ShaderModule,
+    pub label: String, // Path of the file, for errors
     pub vs_entry: String,
     pub fs_entry: String
 }
@@ -11,7 +14,8 @@ impl Shader
 {
     pub fn new(device: &wgpu::Device, path: &str, vs_entry: &str, fs_entry: &str) -> Self
     {
-        let source = fs::read_to_string(path).unwrap();
+        let error = format!("Failed to load shader with path: {}", path);
+        let source = fs::read_to_string(path).expect(&error);
         let module = device.create_shader_module(wgpu::ShaderModuleDescriptor
         {
             label: Some(path),
@@ -21,24 +25,59 @@ impl Shader
         Self
         {
             module,
+            label: path.to_string(),
             vs_entry: vs_entry.to_string(),
             fs_entry: fs_entry.to_string()
         }
     }
 
+    // Shader for materials, shaders/common.wgsl (vs_main, VertexOutput, the texture in group 0 and the camera in group 1) gets put in front of the file
+    // So the file only needs an fs_main and the group 2 bindings of the material (uniform buffer at 0, then texture and sampler for every texture)
+    pub fn material(device: &wgpu::Device, path: &str) -> Result<Self>
+    {
+        let source = fs::read_to_string(path).with_context(|| format!("Failed to load shader with path: {}", path))?;
+        let module = validated(device, || device.create_shader_module(wgpu::ShaderModuleDescriptor
+        {
+            label: Some(path),
+            source: wgpu::ShaderSource::Wgsl(format!("{}\n{}", include_str!("shaders/common.wgsl"), source).into()),
+        })).map_err(|e| anyhow!("Shader {} does not compile: {}", path, e))?;
+
+        Ok(Self
+        {
+            module,
+            label: path.to_string(),
+            vs_entry: "vs_main".into(),
+            fs_entry: "fs_main".into()
+        })
+    }
+
     pub fn default(device: &wgpu::Device) -> Self 
     {
         let module = device.create_shader_module(wgpu::ShaderModuleDescriptor
         {
             label: Some("Default Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/shader.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(concat!(include_str!("shaders/common.wgsl"), "\n", include_str!("shaders/shader.wgsl")).into()),
         });
 
         Self 
         { 
             module, 
+            label: "Default Shader".into(),
             vs_entry: "vs_main".into(), 
             fs_entry: "fs_main".into()
         }
     }
+}
+
+// Runs create inside of a validation error scope, so a broken user shader becomes an error instead of ending the process
+// wgpu reports the error right away on native and WebGL, so waiting for the scope does not block
+pub(crate) fn validated<T>(device: &wgpu::Device, create: impl FnOnce() -> T) -> Result<T>
+{
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = create();
+    match pollster::block_on(device.pop_error_scope())
+    {
+        Some(error) => Err(anyhow!("{}", error)),
+        None => Ok(value)
+    }
 }
\ No newline at end of file
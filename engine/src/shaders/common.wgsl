@@ -0,0 +1,62 @@
+// Vertex stage and texture bindings shared by the default shader and every material shader
+
+struct VertexInput
+{
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+
+    //Insstance
+    @location(2) model0: vec4<f32>,
+    @location(3) model1: vec4<f32>,
+    @location(4) model2: vec4<f32>,
+    @location(5) model3: vec4<f32>,
+    @location(6) color: vec4<f32>,
+
+    @location(7) mode: u32,
+    // @location(8) texture_id: u32
+    @location(8) uv_min: vec2<f32>,
+    @location(9) uv_max: vec2<f32>,
+}
+
+struct VertexOutput 
+{
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) mode: u32,
+    // @location(3) texture_id: u32
+};
+
+struct Camera
+{
+    view_proj: mat4x4<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> camera: Camera; // Identity for screen-space draws
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput 
+{
+    var out: VertexOutput;
+
+    let model = mat4x4<f32>(
+        in.model0,
+        in.model1,
+        in.model2,
+        in.model3
+    );
+
+    out.clip_position = camera.view_proj * model * vec4<f32>(in.position, 1.0);
+    out.color = in.color;
+    out.tex_coords = mix(in.uv_min, in.uv_max, in.tex_coords);
+    out.mode = in.mode;
+    // out.texture_id = in.texture_id;
+    return out;
+}
+
+
+@group(0) @binding(0)
+var texture: texture_2d<f32>;
+@group(0) @binding(1)
+var texture_sampler: sampler;
@@ -0,0 +1,30 @@
+// params0: x = threshold, y = intensity, z = radius in pixels
+
+fn bright_part(color: vec3<f32>, threshold: f32) -> vec3<f32>
+{
+    let brightness = max(color.r, max(color.g, color.b));
+    return color * max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+}
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32>
+{
+    let color = textureSample(input_texture, input_sampler, in.uv);
+    let step = effect.params0.z / 3.0 / effect.resolution;
+
+    var glow = vec3<f32>(0.0);
+    var total = 0.0;
+    for (var x = -3; x <= 3; x++)
+    {
+        for (var y = -3; y <= 3; y++)
+        {
+            let offset = vec2<f32>(f32(x), f32(y));
+            let weight = exp(-dot(offset, offset) / 8.0);
+            let tap = textureSample(input_texture, input_sampler, in.uv + offset * step).rgb;
+            glow += bright_part(tap, effect.params0.x) * weight;
+            total += weight;
+        }
+    }
+
+    return vec4<f32>(color.rgb + glow / total * effect.params0.y, color.a);
+}
@@ -0,0 +1,22 @@
+// params0: x = radius in pixels
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32>
+{
+    let step = effect.params0.x / 3.0 / effect.resolution;
+
+    var color = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var x = -3; x <= 3; x++)
+    {
+        for (var y = -3; y <= 3; y++)
+        {
+            let offset = vec2<f32>(f32(x), f32(y));
+            let weight = exp(-dot(offset, offset) / 8.0);
+            color += textureSample(input_texture, input_sampler, in.uv + offset * step) * weight;
+            total += weight;
+        }
+    }
+
+    return color / total;
+}
@@ -0,0 +1,13 @@
+// params0: x = offset in pixels at the edges of the screen
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32>
+{
+    let offset = (in.uv - vec2<f32>(0.5)) * 2.0 * effect.params0.x / effect.resolution;
+
+    let center = textureSample(input_texture, input_sampler, in.uv);
+    let r = textureSample(input_texture, input_sampler, in.uv + offset).r;
+    let b = textureSample(input_texture, input_sampler, in.uv - offset).b;
+
+    return vec4<f32>(r, center.g, b, center.a);
+}
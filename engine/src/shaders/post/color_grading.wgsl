@@ -0,0 +1,35 @@
+// params0: x = intensity (0 = original, 1 = fully graded), y = size of the LUT (16 for a 256x16 strip)
+// The LUT is a horizontal strip of blue slices, red goes to the right and green down inside of each slice
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32>
+{
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn lookup(color: vec3<f32>, size: f32) -> vec3<f32>
+{
+    let blue = color.b * (size - 1.0);
+    let slice0 = floor(blue);
+    let slice1 = min(slice0 + 1.0, size - 1.0);
+
+    let pixel = color.rg * (size - 1.0) + 0.5;
+    let dimensions = vec2<f32>(size * size, size);
+
+    let uv0 = vec2<f32>(slice0 * size + pixel.x, pixel.y) / dimensions;
+    let uv1 = vec2<f32>(slice1 * size + pixel.x, pixel.y) / dimensions;
+
+    let graded0 = textureSampleLevel(extra_texture, extra_sampler, uv0, 0.0).rgb;
+    let graded1 = textureSampleLevel(extra_texture, extra_sampler, uv1, 0.0).rgb;
+    return mix(graded0, graded1, blue - slice0);
+}
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32>
+{
+    let color = textureSample(input_texture, input_sampler, in.uv);
+    // The LUT is sRGB, so the lookup happens with sRGB values, but the result is already linear again
+    let graded = lookup(linear_to_srgb(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0))), effect.params0.y);
+    return vec4<f32>(mix(color.rgb, graded, effect.params0.x), color.a);
+}
@@ -0,0 +1,18 @@
+// params0: x = screen curvature, y = scanline intensity, z = scanline count (0 = half the screen height)
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32>
+{
+    var uv = in.uv * 2.0 - 1.0;
+    uv = uv + uv * uv.yx * uv.yx * effect.params0.x;
+    uv = uv * 0.5 + 0.5;
+
+    let color = textureSampleLevel(input_texture, input_sampler, uv, 0.0);
+
+    let lines = select(effect.params0.z, effect.resolution.y * 0.5, effect.params0.z <= 0.0);
+    let scanline = 0.5 + 0.5 * sin(uv.y * lines * 6.28318530);
+    let rgb = color.rgb * (1.0 - effect.params0.y * (1.0 - scanline));
+
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    return select(vec4<f32>(0.0, 0.0, 0.0, 1.0), vec4<f32>(rgb, color.a), inside);
+}
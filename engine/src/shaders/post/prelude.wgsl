@@ -0,0 +1,39 @@
+// Shared by every post-processing pass, the effect source only needs to define fs_main
+
+struct EffectUniforms
+{
+    resolution: vec2<f32>,
+    time: f32,
+    _padding: f32,
+    params0: vec4<f32>,
+    params1: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> effect: EffectUniforms;
+@group(0) @binding(3)
+var extra_texture: texture_2d<f32>; // Identity LUT by default
+@group(0) @binding(4)
+var extra_sampler: sampler;
+
+struct FullscreenOutput
+{
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// One triangle that covers the whole screen
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> FullscreenOutput
+{
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var out: FullscreenOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
@@ -0,0 +1,10 @@
+// params0: x = intensity, y = radius where the darkening starts, z = softness
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32>
+{
+    let color = textureSample(input_texture, input_sampler, in.uv);
+    let dist = distance(in.uv, vec2<f32>(0.5)) * 1.41421356;
+    let vignette = smoothstep(effect.params0.y, effect.params0.y - effect.params0.z, dist);
+    return vec4<f32>(color.rgb * mix(1.0, vignette, effect.params0.x), color.a);
+}
@@ -0,0 +1,367 @@
+use std::f32::consts::PI;
+
+use cgmath::{Matrix3, SquareMatrix, Vector3};
+
+use crate::{renderer::Renderer, sprite::Frame, state::Loader, timeline::{Property, Track, TrackValue}, transform::{CoordinateSpace, Transform2D}, tween::Lerp, utility::Vertex};
+
+// Everything is in skeleton space: pixels with y down, 0,0 is the root of the skeleton, placed with the root transform when drawing
+
+pub struct Bone
+{
+    pub name: String,
+    pub parent: Option<usize>, // Always a bone added before this one
+    pub setup: Transform2D // Local transform relative to the parent in the setup (bind) pose
+}
+
+// Sprite that moves with a bone
+pub struct Attachment
+{
+    pub bone: usize,
+    pub texture: usize,
+    pub frame: Option<Frame>, // Part of the texture for atlases, None = the whole texture
+    pub offset: Transform2D, // Relative to the bone, scale multiplies the untrimmed size, pivot as for the quad (0,0 = center)
+    pub z_offset: u32 // Added to the z_index of the skeleton
+}
+
+// Bone hierarchy with its current pose, owned by the game
+// Every frame: reset_pose, apply one or more clips, update_world, then draw the attachments and skinned meshes
+pub struct Skeleton
+{
+    bones: Vec<Bone>,
+    pub pose: Vec<Transform2D>, // Local transforms of the current pose
+    pub attachments: Vec<Attachment>,
+    world: Vec<Matrix3<f32>>
+}
+
+impl Default for Skeleton
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl Skeleton
+{
+    pub fn new() -> Self
+    {
+        Self { bones: Vec::new(), pose: Vec::new(), attachments: Vec::new(), world: Vec::new() }
+    }
+
+    // rotation in radians counter-clockwise, like Transform2D
+    pub fn add_bone(&mut self, name: &str, parent: Option<usize>, translation: (f32, f32), rotation: f32, scale: (f32, f32)) -> usize
+    {
+        assert!(parent.is_none_or(|parent| parent < self.bones.len()), "The parent of bone {} has to be added first", name);
+
+        let setup = Transform2D::new(translation, rotation, scale, CoordinateSpace::Virtual);
+        self.bones.push(Bone { name: name.to_string(), parent, setup });
+        self.pose.push(setup);
+        self.world.push(Matrix3::identity());
+        self.bones.len() - 1
+    }
+
+    pub fn bone(&self, name: &str) -> Option<usize>
+    {
+        self.bones.iter().position(|bone| bone.name == name)
+    }
+
+    pub fn bones(&self) -> &[Bone]
+    {
+        &self.bones
+    }
+
+    pub fn attach(&mut self, attachment: Attachment)
+    {
+        self.attachments.push(attachment);
+    }
+
+    // Back to the setup pose
+    pub fn reset_pose(&mut self)
+    {
+        for (pose, bone) in self.pose.iter_mut().zip(&self.bones)
+        {
+            *pose = bone.setup;
+        }
+    }
+
+    // Blends the clip at time into the current pose, weight 1 replaces it, so clips applied later win
+    pub fn apply(&mut self, clip: &SkeletonClip, time: f32, weight: f32)
+    {
+        let time = if clip.looping && clip.duration > 0.0 { time.rem_euclid(clip.duration) } else { time.clamp(0.0, clip.duration) };
+
+        for bone_track in &clip.tracks
+        {
+            let Some(value) = bone_track.track.sample(time) else { continue };
+            let pose = &mut self.pose[bone_track.bone];
+
+            match (bone_track.track.property, value)
+            {
+                (Property::Position, TrackValue::Vec2(x, y)) => pose.translation = pose.translation.lerp(&(x, y), weight),
+                (Property::Rotation, TrackValue::Float(rotation)) => pose.rotation = lerp_angle(pose.rotation, rotation, weight),
+                (Property::Scale, TrackValue::Vec2(x, y)) => pose.scale = pose.scale.lerp(&(x, y), weight),
+                _ => {}
+            }
+        }
+    }
+
+    // Blends from one clip to another, t = 0 is only from, t = 1 only to
+    pub fn crossfade(&mut self, from: (&SkeletonClip, f32), to: (&SkeletonClip, f32), t: f32)
+    {
+        self.reset_pose();
+        self.apply(from.0, from.1, 1.0);
+        self.apply(to.0, to.1, t);
+    }
+
+    // Has to be called after the pose changed and before drawing
+    pub fn update_world(&mut self)
+    {
+        self.world = self.world_matrices(&self.pose);
+    }
+
+    // Bone to skeleton space in the current pose
+    pub fn world_matrix(&self, bone: usize) -> Matrix3<f32>
+    {
+        self.world[bone]
+    }
+
+    // Position of the bone in skeleton space
+    pub fn bone_position(&self, bone: usize) -> (f32, f32)
+    {
+        let world = self.world[bone];
+        (world.z.x, world.z.y)
+    }
+
+    pub(crate) fn setup_world(&self) -> Vec<Matrix3<f32>>
+    {
+        let setup: Vec<Transform2D> = self.bones.iter().map(|bone| bone.setup).collect();
+        self.world_matrices(&setup)
+    }
+
+    fn world_matrices(&self, locals: &[Transform2D]) -> Vec<Matrix3<f32>>
+    {
+        let mut world: Vec<Matrix3<f32>> = Vec::with_capacity(self.bones.len());
+        for (bone, local) in self.bones.iter().zip(locals)
+        {
+            let parent = bone.parent.map_or(Matrix3::identity(), |parent| world[parent]);
+            world.push(parent * local.matrix());
+        }
+        world
+    }
+
+    // Draws every attachment, root places the skeleton (its scale scales the whole skeleton)
+    pub fn draw(&self, renderer: &mut Renderer, root: &Transform2D, z_index: u32)
+    {
+        let root_matrix = root.matrix();
+
+        for attachment in &self.attachments
+        {
+            let frame = attachment.frame.unwrap_or_else(||
+            {
+                let (width, height) = renderer.texture_size(attachment.texture);
+                Frame::new((0.0, 0.0, width, height))
+            });
+
+            // In pixels of the untrimmed frame, like SpriteSheet::draw_frame
+            let size = frame.source_size;
+            let pivot = attachment.offset.pivot;
+            let sprite = Transform2D
+            {
+                pivot: ((pivot.0 + 0.5) * size.0, (pivot.1 + 0.5) * size.1),
+                space: root.space,
+                parent: root_matrix * self.world[attachment.bone] * attachment.offset.parent,
+                ..attachment.offset
+            };
+
+            frame.draw(renderer, attachment.texture, sprite, z_index + attachment.z_offset);
+        }
+    }
+}
+
+// Turns the short way, so blending 170 and -170 degrees passes 180 instead of 0
+fn lerp_angle(from: f32, to: f32, t: f32) -> f32
+{
+    if t >= 1.0
+    {
+        return to;
+    }
+    let delta = (to - from + PI).rem_euclid(2.0 * PI) - PI;
+    from + delta * t
+}
+
+// Keyframed bone tracks, the values are the local transforms of the bones (not offsets from the setup pose)
+// Position and Scale tracks need Vec2 values, Rotation tracks Float values in radians, other tracks are ignored
+pub struct BoneTrack
+{
+    pub bone: usize,
+    pub track: Track
+}
+
+pub struct SkeletonClip
+{
+    pub duration: f32,
+    pub looping: bool,
+    pub tracks: Vec<BoneTrack>
+}
+
+impl SkeletonClip
+{
+    pub fn new(duration: f32, looping: bool) -> Self
+    {
+        Self { duration, looping, tracks: Vec::new() }
+    }
+
+    pub fn add_track(&mut self, bone: usize, track: Track)
+    {
+        self.tracks.push(BoneTrack { bone, track });
+    }
+
+    pub fn with_track(mut self, bone: usize, track: Track) -> Self
+    {
+        self.add_track(bone, track);
+        self
+    }
+}
+
+// Vertex of a skinned mesh in skeleton space in the setup pose, weighted to up to four bones
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkinVertex
+{
+    pub position: (f32, f32),
+    pub uv: (f32, f32), // 0,0 = top left of the texture
+    pub bones: [usize; 4],
+    pub weights: [f32; 4] // Unused slots have weight 0, get normalized to add up to 1, without any weight the vertex stays at its setup position
+}
+
+// Mesh that deforms with the bones, skinned on the cpu into a dynamic mesh of the renderer
+// Every skinned mesh has its own renderer mesh, so two characters that share a skeleton layout need one each
+pub struct SkinnedMesh
+{
+    pub mesh_id: usize,
+    pub texture: usize,
+    vertices: Vec<SkinVertex>,
+    indices: Vec<u16>,
+    inverse_bind: Vec<Matrix3<f32>> // Skeleton space to bone space in the setup pose
+}
+
+impl SkinnedMesh
+{
+    // Triangles can be in any winding order
+    pub fn new(loader: &mut dyn Loader, skeleton: &Skeleton, texture: usize, vertices: Vec<SkinVertex>, mut indices: Vec<u16>) -> Self
+    {
+        let vertices: Vec<SkinVertex> = vertices.into_iter().map(|mut vertex|
+        {
+            let total: f32 = vertex.weights.iter().sum();
+            if total > 0.0
+            {
+                vertex.weights = vertex.weights.map(|weight| weight / total);
+            }
+            vertex
+        }).collect();
+
+        // Counter-clockwise with y up, otherwise the triangle gets culled
+        for triangle in indices.chunks_exact_mut(3)
+        {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| vertices[i as usize].position);
+            let area = (b.0 - a.0) * (a.1 - c.1) - (a.1 - b.1) * (c.0 - a.0);
+            if area < 0.0
+            {
+                triangle.swap(1, 2);
+            }
+        }
+
+        let inverse_bind = skeleton.setup_world().iter().map(|world| world.invert().unwrap_or(Matrix3::identity())).collect();
+        // Dynamic, so every draw in a frame keeps its own pose
+        let mesh_id = loader.create_dynamic_mesh(vertices.len(), indices.len());
+
+        Self { mesh_id, texture, vertices, indices, inverse_bind }
+    }
+
+    // Vertex positions in skeleton space for the current pose of the skeleton
+    pub fn skin(&self, skeleton: &Skeleton) -> Vec<(f32, f32)>
+    {
+        self.vertices.iter().map(|vertex|
+        {
+            let setup = Vector3::new(vertex.position.0, vertex.position.1, 1.0);
+            let mut skinned = Vector3::new(0.0, 0.0, 0.0);
+            let mut total = 0.0;
+            for (bone, weight) in vertex.bones.iter().zip(vertex.weights)
+            {
+                if weight > 0.0
+                {
+                    skinned += skeleton.world[*bone] * self.inverse_bind[*bone] * setup * weight;
+                    total += weight;
+                }
+            }
+
+            // Not bound to any bone, it would collapse to 0,0 otherwise
+            if total <= 0.0
+            {
+                return vertex.position;
+            }
+            (skinned.x, skinned.y)
+        }).collect()
+    }
+
+    // Skins the mesh with the current pose and draws it, root like in Skeleton::draw
+    pub fn draw(&self, renderer: &mut Renderer, skeleton: &Skeleton, root: &Transform2D, z_index: u32)
+    {
+        let positions = self.skin(skeleton);
+        let vertices = Self::to_vertices(&self.vertices, |i| positions[i]);
+        renderer.set_mesh(self.mesh_id, &vertices, &self.indices);
+
+        // Vertices are already in pixels, so the root keeps its own scale instead of a size
+        renderer.draw_texture(self.mesh_id, root, self.texture, z_index);
+    }
+
+    fn to_vertices<T>(vertices: &[SkinVertex], position: T) -> Vec<Vertex> where T: Fn(usize) -> (f32, f32)
+    {
+        // Meshes are y up like the quad
+        vertices.iter().enumerate().map(|(i, vertex)|
+        {
+            let (x, y) = position(i);
+            Vertex::new([x, -y, 0.0], [vertex.uv.0, vertex.uv.1])
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::timeline::Interpolation;
+
+    #[test]
+    fn rotations_blend_the_short_way()
+    {
+        let mut skeleton = Skeleton::new();
+        let bone = skeleton.add_bone("arm", None, (0.0, 0.0), 170f32.to_radians(), (1.0, 1.0));
+        let clip = SkeletonClip::new(1.0, false).with_track(bone, Track::new("arm", Property::Rotation)
+            .with_key(0.0, TrackValue::Float(-170f32.to_radians()), Interpolation::Linear));
+
+        skeleton.apply(&clip, 0.0, 0.5);
+        assert!((skeleton.pose[bone].rotation.abs() - PI).abs() < 1e-4);
+
+        skeleton.apply(&clip, 0.0, 1.0);
+        assert_eq!(skeleton.pose[bone].rotation, -170f32.to_radians());
+    }
+
+    #[test]
+    fn vertices_without_weights_stay_at_their_setup_position()
+    {
+        let mut skeleton = Skeleton::new();
+        let bone = skeleton.add_bone("root", None, (0.0, 0.0), 0.0, (1.0, 1.0));
+        let vertex = |position, weights| SkinVertex { position, uv: (0.0, 0.0), bones: [bone; 4], weights };
+        let mesh = SkinnedMesh
+        {
+            mesh_id: 0,
+            texture: 0,
+            vertices: vec![vertex((5.0, 5.0), [1.0, 0.0, 0.0, 0.0]), vertex((7.0, 3.0), [0.0; 4])],
+            indices: Vec::new(),
+            inverse_bind: skeleton.setup_world()
+        };
+
+        skeleton.pose[bone].translation = (10.0, 0.0);
+        skeleton.update_world();
+        assert_eq!(mesh.skin(&skeleton), vec![(15.0, 5.0), (7.0, 3.0)]);
+    }
+}
@@ -0,0 +1,871 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{skeleton::{Attachment, Skeleton, SkeletonClip}, sprite::SpriteSheet, timeline::{Interpolation, Property, Track, TrackValue}, transform::{CoordinateSpace, Transform2D}};
+
+// Supported: bones, slots with region attachments of the default skin, and rotate, translate and scale bone timelines
+// Everything else in the file is listed in unsupported instead of being dropped without a word
+
+// Image drawn by a slot, before it gets looked up in the atlas
+pub struct SlotRegion
+{
+    pub slot: String,
+    pub bone: usize,
+    pub image: String, // Name of the region in the atlas
+    pub offset: Transform2D, // As Attachment::offset
+    pub size: Option<(f32, f32)> // Size the image gets stretched to, None = its size in the atlas
+}
+
+// Content of a Spine or DragonBones skeleton json file, without the atlas
+pub struct SkeletonFile
+{
+    pub skeleton: Skeleton, // Only the bones
+    pub regions: Vec<SlotRegion>, // In draw order
+    pub clips: Vec<(String, SkeletonClip)>,
+    pub unsupported: Vec<String>
+}
+
+// Skeleton with its attachments and clips, ready to animate
+pub struct SkeletonRig
+{
+    pub skeleton: Skeleton,
+    pub clips: HashMap<String, SkeletonClip>,
+    pub unsupported: Vec<String> // Features of the file that got skipped, empty if it plays like in the editor
+}
+
+impl SkeletonFile
+{
+    // Attaches the regions with the frames of the atlas, names can leave out the .png of atlases that keep it
+    pub fn into_rig(self, sheet: &SpriteSheet) -> Result<SkeletonRig>
+    {
+        let mut skeleton = self.skeleton;
+        for (i, region) in self.regions.into_iter().enumerate()
+        {
+            let frame = sheet.frame_index(&region.image).or_else(|| sheet.frame_index(&format!("{}.png", region.image)))
+                .ok_or_else(|| anyhow!("Image {} of slot {} is not in the atlas", region.image, region.slot))?;
+            let frame = sheet.frames[frame];
+
+            let mut offset = region.offset;
+            if let Some((width, height)) = region.size
+            {
+                offset.scale = (offset.scale.0 * width / frame.source_size.0, offset.scale.1 * height / frame.source_size.1);
+            }
+
+            skeleton.attach(Attachment { bone: region.bone, texture: sheet.texture, frame: Some(frame), offset, z_offset: i as u32 });
+        }
+
+        Ok(SkeletonRig { skeleton, clips: self.clips.into_iter().collect(), unsupported: self.unsupported })
+    }
+}
+
+// Spine files have bones at the top, DragonBones files a list of armatures
+pub fn parse_skeleton(json: &str) -> Result<SkeletonFile>
+{
+    let value: Value = serde_json::from_str(json)?;
+    if value.get("armature").is_some()
+    {
+        parse_dragonbones(serde_json::from_value(value)?)
+    }
+    else if value.get("bones").is_some()
+    {
+        parse_spine(serde_json::from_value(value)?)
+    }
+    else
+    {
+        bail!("Neither a Spine nor a DragonBones skeleton")
+    }
+}
+
+fn one() -> f32
+{
+    1.0
+}
+
+// Lists every timeline or constraint that is not empty
+fn report_unsupported(unsupported: &mut Vec<String>, other: &Map<String, Value>, context: &str)
+{
+    for (key, value) in other
+    {
+        let empty = match value
+        {
+            Value::Array(list) => list.is_empty(),
+            Value::Object(map) => map.is_empty(),
+            _ => true // Settings, not features
+        };
+        if !empty
+        {
+            unsupported.push(format!("{} {}", context, key));
+        }
+    }
+}
+
+// Track of one bone timeline, the values are already absolute
+fn build_track(name: &str, property: Property, keys: Vec<(f32, TrackValue, Interpolation)>) -> Track
+{
+    let mut track = Track::new(name, property);
+    for (time, value, interpolation) in keys
+    {
+        track.add_key(time, value, interpolation);
+    }
+    track
+}
+
+// Spine, y up and degrees counter-clockwise, timelines are relative to the setup pose
+
+#[derive(Deserialize, Default)]
+struct SpineInfo
+{
+    spine: Option<String> // Editor version
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpineBone
+{
+    name: String,
+    parent: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "one")]
+    scale_x: f32,
+    #[serde(default = "one")]
+    scale_y: f32,
+    #[serde(default)]
+    shear_x: f32,
+    #[serde(default)]
+    shear_y: f32,
+    transform: Option<String>
+}
+
+#[derive(Deserialize)]
+struct SpineSlot
+{
+    name: String,
+    bone: String,
+    attachment: Option<String>,
+    color: Option<String>,
+    blend: Option<String>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpineAttachment
+{
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "one")]
+    scale_x: f32,
+    #[serde(default = "one")]
+    scale_y: f32,
+    width: Option<f32>,
+    height: Option<f32>
+}
+
+// Slot name -> attachment name -> attachment
+type SpineSkinAttachments = HashMap<String, HashMap<String, SpineAttachment>>;
+
+#[derive(Deserialize)]
+struct SpineSkin
+{
+    name: String,
+    #[serde(default)]
+    attachments: SpineSkinAttachments
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SpineSkins
+{
+    List(Vec<SpineSkin>), // 3.8 and newer
+    Map(HashMap<String, SpineSkinAttachments>)
+}
+
+impl Default for SpineSkins
+{
+    fn default() -> Self
+    {
+        SpineSkins::List(Vec::new())
+    }
+}
+
+#[derive(Deserialize)]
+struct SpineKey
+{
+    #[serde(default)]
+    time: f32,
+    angle: Option<f32>, // Rotate before 4.0
+    value: Option<f32>, // Rotate since 4.0
+    x: Option<f32>,
+    y: Option<f32>,
+    curve: Option<Value>,
+    c2: Option<f32>, // 3.7 stores the curve as curve, c2, c3, c4
+    c3: Option<f32>,
+    c4: Option<f32>
+}
+
+// Absolute value of a key from the setup pose of its bone
+type SpineValue = fn(&SpineKey, &Transform2D) -> TrackValue;
+
+// Raw x and y of a key as in the file, rotate has one value for both
+type SpineAxes = fn(&SpineKey) -> [f32; 2];
+
+#[derive(Deserialize)]
+struct SpineAnimation
+{
+    #[serde(default)]
+    bones: HashMap<String, HashMap<String, Vec<SpineKey>>>,
+    #[serde(flatten)]
+    other: Map<String, Value>
+}
+
+#[derive(Deserialize)]
+struct SpineFile
+{
+    #[serde(default)]
+    skeleton: SpineInfo,
+    bones: Vec<SpineBone>,
+    #[serde(default)]
+    slots: Vec<SpineSlot>,
+    #[serde(default)]
+    skins: SpineSkins,
+    #[serde(default)]
+    animations: HashMap<String, SpineAnimation>,
+    #[serde(default)]
+    ik: Vec<Value>,
+    #[serde(default)]
+    transform: Vec<Value>,
+    #[serde(default)]
+    path: Vec<Value>
+}
+
+fn parse_spine(file: SpineFile) -> Result<SkeletonFile>
+{
+    let mut unsupported = Vec::new();
+    let version_4 = file.skeleton.spine.as_deref().is_some_and(|version| version.starts_with('4'));
+
+    for (key, constraints) in [("ik", &file.ik), ("transform", &file.transform), ("path", &file.path)]
+    {
+        if !constraints.is_empty()
+        {
+            unsupported.push(format!("{} {} constraints", constraints.len(), key));
+        }
+    }
+
+    let mut skeleton = Skeleton::new();
+    for bone in &file.bones
+    {
+        let parent = match &bone.parent
+        {
+            Some(parent) => Some(skeleton.bone(parent).ok_or_else(|| anyhow!("Parent {} of bone {} has to come before it", parent, bone.name))?),
+            None => None
+        };
+
+        if bone.shear_x != 0.0 || bone.shear_y != 0.0
+        {
+            unsupported.push(format!("Shear of bone {}", bone.name));
+        }
+        if let Some(transform) = &bone.transform && transform != "normal"
+        {
+            unsupported.push(format!("Transform mode {} of bone {}", transform, bone.name));
+        }
+
+        skeleton.add_bone(&bone.name, parent, (bone.x, -bone.y), bone.rotation.to_radians(), (bone.scale_x, bone.scale_y));
+    }
+
+    let mut skins = match file.skins
+    {
+        SpineSkins::List(list) => list.into_iter().map(|skin| (skin.name, skin.attachments)).collect(),
+        SpineSkins::Map(map) => map
+    };
+    let default_skin = skins.remove("default").unwrap_or_default();
+    for name in skins.keys()
+    {
+        unsupported.push(format!("Skin {}", name));
+    }
+
+    let mut regions = Vec::new();
+    for slot in &file.slots
+    {
+        let bone = skeleton.bone(&slot.bone).ok_or_else(|| anyhow!("Slot {} uses the unknown bone {}", slot.name, slot.bone))?;
+        if slot.color.as_deref().is_some_and(|color| !color.eq_ignore_ascii_case("ffffffff"))
+        {
+            unsupported.push(format!("Color of slot {}", slot.name));
+        }
+        if let Some(blend) = &slot.blend && blend != "normal"
+        {
+            unsupported.push(format!("Blend mode {} of slot {}", blend, slot.name));
+        }
+
+        let Some(name) = &slot.attachment else { continue };
+        let attachment = default_skin.get(&slot.name).and_then(|attachments| attachments.get(name))
+            .ok_or_else(|| anyhow!("Attachment {} of slot {} is not in the default skin", name, slot.name))?;
+
+        let kind = attachment.kind.as_deref().unwrap_or("region");
+        if kind != "region"
+        {
+            unsupported.push(format!("{} attachment {} of slot {}", kind, name, slot.name));
+            continue;
+        }
+
+        regions.push(SlotRegion
+        {
+            slot: slot.name.clone(),
+            bone,
+            image: attachment.path.clone().unwrap_or_else(|| name.clone()),
+            offset: Transform2D::new((attachment.x, -attachment.y), attachment.rotation.to_radians(), (attachment.scale_x, attachment.scale_y), CoordinateSpace::Virtual),
+            size: attachment.width.zip(attachment.height)
+        });
+    }
+
+    let mut clips = Vec::new();
+    for (name, animation) in file.animations
+    {
+        report_unsupported(&mut unsupported, &animation.other, &format!("Animation {}:", name));
+
+        let mut tracks = Vec::new();
+        let mut duration: f32 = 0.0;
+        for (bone_name, timelines) in &animation.bones
+        {
+            let bone = skeleton.bone(bone_name).ok_or_else(|| anyhow!("Animation {} uses the unknown bone {}", name, bone_name))?;
+            let setup = skeleton.bones()[bone].setup;
+
+            for (timeline, keys) in timelines
+            {
+                duration = keys.iter().map(|key| key.time).fold(duration, f32::max);
+
+                // The curves of a key are read from the raw values, one curve per axis in Spine 4
+                let (property, axes, value): (Property, SpineAxes, SpineValue) = match timeline.as_str()
+                {
+                    "rotate" =>
+                    (
+                        Property::Rotation,
+                        |key| [key.value.or(key.angle).unwrap_or(0.0); 2],
+                        |key, setup| TrackValue::Float(setup.rotation + key.value.or(key.angle).unwrap_or(0.0).to_radians())
+                    ),
+                    "translate" =>
+                    (
+                        Property::Position,
+                        |key| [key.x.unwrap_or(0.0), key.y.unwrap_or(0.0)],
+                        |key, setup| TrackValue::Vec2(setup.translation.0 + key.x.unwrap_or(0.0), setup.translation.1 - key.y.unwrap_or(0.0))
+                    ),
+                    "scale" =>
+                    (
+                        Property::Scale,
+                        |key| [key.x.unwrap_or(1.0), key.y.unwrap_or(1.0)],
+                        |key, setup| TrackValue::Vec2(setup.scale.0 * key.x.unwrap_or(1.0), setup.scale.1 * key.y.unwrap_or(1.0))
+                    ),
+                    _ =>
+                    {
+                        unsupported.push(format!("Animation {}: {} timeline of bone {}", name, timeline, bone_name));
+                        continue;
+                    }
+                };
+
+                let keys = keys.iter().enumerate().map(|(k, key)|
+                {
+                    let interpolation = spine_curve(key, keys.get(k + 1), version_4, axes).unwrap_or_else(||
+                    {
+                        unsupported.push(format!("Animation {}: different x and y curves at {} in the {} timeline of bone {}", name, key.time, timeline, bone_name));
+                        Interpolation::Linear
+                    });
+                    (key.time, value(key, &setup), interpolation)
+                }).collect();
+                tracks.push((bone, build_track(&format!("{}.{}", bone_name, timeline), property, keys)));
+            }
+        }
+
+        // Spine has no loop setting, the game picks it when playing
+        let mut clip = SkeletonClip::new(duration, true);
+        for (bone, track) in tracks
+        {
+            clip.add_track(bone, track);
+        }
+        clips.push((name, clip));
+    }
+
+    Ok(SkeletonFile { skeleton, regions, clips, unsupported })
+}
+
+// Spine 4 stores the bezier handles in time and value of the timeline, older versions relative to the segment like Interpolation
+// Spine 4 has a curve for every axis, None if the axes that change have different curves, a track has one curve for both
+fn spine_curve(key: &SpineKey, next: Option<&SpineKey>, version_4: bool, axes: SpineAxes) -> Option<Interpolation>
+{
+    match &key.curve
+    {
+        Some(Value::String(curve)) if curve == "stepped" => Some(Interpolation::Step),
+        Some(Value::Number(c1)) => Some(Interpolation::Bezier((c1.as_f64().unwrap_or(0.0) as f32, key.c2.unwrap_or(0.0)), (key.c3.unwrap_or(1.0), key.c4.unwrap_or(1.0)))),
+        Some(Value::Array(curve)) if curve.len() >= 4 =>
+        {
+            let curve: Vec<f32> = curve.iter().map(|value| value.as_f64().unwrap_or(0.0) as f32).collect();
+            if !version_4
+            {
+                return Some(Interpolation::Bezier((curve[0], curve[1]), (curve[2], curve[3])));
+            }
+
+            let Some(next) = next else { return Some(Interpolation::Linear) };
+            let time = next.time - key.time;
+            if time <= 0.0
+            {
+                return Some(Interpolation::Linear);
+            }
+
+            // Axes that do not change have no say in the curve
+            let (from, to) = (axes(key), axes(next));
+            let mut curves = curve.chunks_exact(4).zip(from.iter().zip(&to)).filter(|(_, (from, to))| (*to - *from).abs() >= 1e-6).map(|(handles, (from, to))|
+            {
+                let value = to - from;
+                ((handles[0] - key.time) / time, (handles[1] - from) / value, (handles[2] - key.time) / time, (handles[3] - from) / value)
+            });
+
+            let Some(first) = curves.next() else { return Some(Interpolation::Linear) };
+            let same = |other: (f32, f32, f32, f32)| [first.0 - other.0, first.1 - other.1, first.2 - other.2, first.3 - other.3].iter().all(|d| d.abs() < 1e-3);
+            if !curves.all(same)
+            {
+                return None;
+            }
+            Some(Interpolation::Bezier((first.0, first.1), (first.2, first.3)))
+        }
+        _ => Some(Interpolation::Linear)
+    }
+}
+
+// DragonBones, y down and degrees clockwise, frame durations in frames, timelines are relative to the setup pose
+
+fn default_frame_rate() -> f32
+{
+    24.0
+}
+
+fn default_duration() -> f32
+{
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesTransform
+{
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    sk_x: f32,
+    #[serde(default)]
+    sk_y: f32, // Rotation
+    #[serde(default = "one")]
+    sc_x: f32,
+    #[serde(default = "one")]
+    sc_y: f32
+}
+
+impl Default for DragonBonesTransform
+{
+    fn default() -> Self
+    {
+        Self { x: 0.0, y: 0.0, sk_x: 0.0, sk_y: 0.0, sc_x: 1.0, sc_y: 1.0 }
+    }
+}
+
+impl DragonBonesTransform
+{
+    fn to_transform(&self) -> Transform2D
+    {
+        Transform2D::new((self.x, self.y), -self.sk_y.to_radians(), (self.sc_x, self.sc_y), CoordinateSpace::Virtual)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesBone
+{
+    name: String,
+    parent: Option<String>,
+    #[serde(default)]
+    transform: DragonBonesTransform,
+    inherit_rotation: Option<bool>,
+    inherit_scale: Option<bool>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesSlot
+{
+    name: String,
+    parent: String, // Bone
+    #[serde(default)]
+    display_index: i32, // -1 = hidden
+    color: Option<Map<String, Value>>,
+    blend_mode: Option<String>
+}
+
+#[derive(Deserialize)]
+struct DragonBonesDisplay
+{
+    name: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    transform: DragonBonesTransform
+}
+
+#[derive(Deserialize)]
+struct DragonBonesSkinSlot
+{
+    name: String,
+    #[serde(default)]
+    display: Vec<DragonBonesDisplay>
+}
+
+#[derive(Deserialize)]
+struct DragonBonesSkin
+{
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    slot: Vec<DragonBonesSkinSlot>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesFrame
+{
+    #[serde(default = "default_duration")]
+    duration: f32,
+    tween_easing: Option<f32>, // None = holds the value
+    curve: Option<Vec<f32>>,
+    x: Option<f32>,
+    y: Option<f32>,
+    #[serde(default)]
+    rotate: f32
+}
+
+type DragonBonesValue = fn(&DragonBonesFrame, &Transform2D) -> TrackValue;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesBoneTimeline
+{
+    name: String,
+    #[serde(default)]
+    translate_frame: Vec<DragonBonesFrame>,
+    #[serde(default)]
+    rotate_frame: Vec<DragonBonesFrame>,
+    #[serde(default)]
+    scale_frame: Vec<DragonBonesFrame>,
+    #[serde(flatten)]
+    other: Map<String, Value>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesAnimation
+{
+    name: String,
+    #[serde(default)]
+    duration: f32,
+    #[serde(default = "default_play_times")]
+    play_times: u32, // 0 = forever
+    #[serde(default)]
+    bone: Vec<DragonBonesBoneTimeline>,
+    #[serde(flatten)]
+    other: Map<String, Value>
+}
+
+fn default_play_times() -> u32
+{
+    1
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesArmature
+{
+    name: String,
+    frame_rate: Option<f32>,
+    #[serde(default)]
+    bone: Vec<DragonBonesBone>,
+    #[serde(default)]
+    slot: Vec<DragonBonesSlot>,
+    #[serde(default)]
+    skin: Vec<DragonBonesSkin>,
+    #[serde(default)]
+    animation: Vec<DragonBonesAnimation>,
+    #[serde(default)]
+    ik: Vec<Value>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesFile
+{
+    #[serde(default = "default_frame_rate")]
+    frame_rate: f32,
+    armature: Vec<DragonBonesArmature>
+}
+
+// Only the first armature gets imported
+fn parse_dragonbones(file: DragonBonesFile) -> Result<SkeletonFile>
+{
+    let mut unsupported = Vec::new();
+    let mut armatures = file.armature.into_iter();
+    let armature = armatures.next().ok_or_else(|| anyhow!("File has no armature"))?;
+    for other in armatures
+    {
+        unsupported.push(format!("Armature {}", other.name));
+    }
+    if !armature.ik.is_empty()
+    {
+        unsupported.push(format!("{} ik constraints", armature.ik.len()));
+    }
+
+    let frame_rate = armature.frame_rate.unwrap_or(file.frame_rate);
+
+    let mut skeleton = Skeleton::new();
+    for bone in &armature.bone
+    {
+        let parent = match &bone.parent
+        {
+            Some(parent) => Some(skeleton.bone(parent).ok_or_else(|| anyhow!("Parent {} of bone {} has to come before it", parent, bone.name))?),
+            None => None
+        };
+
+        if bone.transform.sk_x != bone.transform.sk_y
+        {
+            unsupported.push(format!("Skew of bone {}", bone.name));
+        }
+        if bone.inherit_rotation == Some(false) || bone.inherit_scale == Some(false)
+        {
+            unsupported.push(format!("Not inheriting rotation or scale in bone {}", bone.name));
+        }
+
+        let setup = bone.transform.to_transform();
+        skeleton.add_bone(&bone.name, parent, setup.translation, setup.rotation, setup.scale);
+    }
+
+    let mut skins = armature.skin.into_iter();
+    let skin = skins.next();
+    for other in skins
+    {
+        unsupported.push(format!("Skin {}", other.name));
+    }
+
+    let mut regions = Vec::new();
+    for slot in &armature.slot
+    {
+        let bone = skeleton.bone(&slot.parent).ok_or_else(|| anyhow!("Slot {} uses the unknown bone {}", slot.name, slot.parent))?;
+        if slot.color.as_ref().is_some_and(|color| !color.is_empty())
+        {
+            unsupported.push(format!("Color of slot {}", slot.name));
+        }
+        if let Some(blend) = &slot.blend_mode && blend != "normal"
+        {
+            unsupported.push(format!("Blend mode {} of slot {}", blend, slot.name));
+        }
+
+        let Ok(index) = usize::try_from(slot.display_index) else { continue };
+        let Some(display) = skin.as_ref().and_then(|skin| skin.slot.iter().find(|skin_slot| skin_slot.name == slot.name)).and_then(|skin_slot| skin_slot.display.get(index)) else { continue };
+
+        let kind = display.kind.as_deref().unwrap_or("image");
+        if kind != "image"
+        {
+            unsupported.push(format!("{} display {} of slot {}", kind, display.name, slot.name));
+            continue;
+        }
+        if display.transform.sk_x != display.transform.sk_y
+        {
+            unsupported.push(format!("Skew of display {}", display.name));
+        }
+
+        regions.push(SlotRegion
+        {
+            slot: slot.name.clone(),
+            bone,
+            image: display.path.clone().unwrap_or_else(|| display.name.clone()),
+            offset: display.transform.to_transform(),
+            size: None
+        });
+    }
+
+    let mut clips = Vec::new();
+    for animation in armature.animation
+    {
+        report_unsupported(&mut unsupported, &animation.other, &format!("Animation {}:", animation.name));
+
+        let mut clip = SkeletonClip::new(animation.duration / frame_rate, animation.play_times == 0);
+        for timeline in &animation.bone
+        {
+            report_unsupported(&mut unsupported, &timeline.other, &format!("Animation {}: bone {}", animation.name, timeline.name));
+
+            let bone = skeleton.bone(&timeline.name).ok_or_else(|| anyhow!("Animation {} uses the unknown bone {}", animation.name, timeline.name))?;
+            let setup = skeleton.bones()[bone].setup;
+
+            let frames: [(&str, Property, &[DragonBonesFrame], DragonBonesValue); 3] =
+            [
+                ("translate", Property::Position, &timeline.translate_frame, |frame, setup| TrackValue::Vec2(setup.translation.0 + frame.x.unwrap_or(0.0), setup.translation.1 + frame.y.unwrap_or(0.0))),
+                ("rotate", Property::Rotation, &timeline.rotate_frame, |frame, setup| TrackValue::Float(setup.rotation - frame.rotate.to_radians())),
+                ("scale", Property::Scale, &timeline.scale_frame, |frame, setup| TrackValue::Vec2(setup.scale.0 * frame.x.unwrap_or(1.0), setup.scale.1 * frame.y.unwrap_or(1.0)))
+            ];
+
+            for (kind, property, frames, value) in frames
+            {
+                if frames.is_empty()
+                {
+                    continue;
+                }
+
+                let mut time = 0.0;
+                let mut keys = Vec::with_capacity(frames.len());
+                for frame in frames
+                {
+                    let interpolation = dragonbones_curve(frame).unwrap_or_else(||
+                    {
+                        unsupported.push(format!("Animation {}: curve with more than one segment in the {} timeline of bone {}", animation.name, kind, timeline.name));
+                        Interpolation::Linear
+                    });
+                    keys.push((time / frame_rate, value(frame, &setup), interpolation));
+                    time += frame.duration;
+                }
+                clip.add_track(bone, build_track(&format!("{}.{}", timeline.name, kind), property, keys));
+            }
+        }
+        clips.push((animation.name, clip));
+    }
+
+    Ok(SkeletonFile { skeleton, regions, clips, unsupported })
+}
+
+// None for curves that do not fit into a single bezier
+// tweenEasing only keeps its direction: -1..0 eases in, 0..1 out and 1..2 both, with the css curves
+fn dragonbones_curve(frame: &DragonBonesFrame) -> Option<Interpolation>
+{
+    if let Some(curve) = &frame.curve
+    {
+        return match curve.as_slice()
+        {
+            [x1, y1, x2, y2] => Some(Interpolation::Bezier((*x1, *y1), (*x2, *y2))),
+            _ => None
+        };
+    }
+
+    Some(match frame.tween_easing
+    {
+        None => Interpolation::Step,
+        Some(0.0) => Interpolation::Linear,
+        Some(easing) if easing < 0.0 => Interpolation::Bezier((0.42, 0.0), (1.0, 1.0)),
+        Some(easing) if easing <= 1.0 => Interpolation::Bezier((0.0, 0.0), (0.58, 1.0)),
+        Some(_) => Interpolation::Bezier((0.42, 0.0), (0.58, 1.0))
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const SPINE: &str = r#"{
+        "skeleton": { "spine": "4.1.20" },
+        "bones": [{ "name": "root" }, { "name": "arm", "parent": "root", "x": 10, "y": 20, "rotation": 90 }],
+        "slots": [{ "name": "arm", "bone": "arm", "attachment": "arm" }],
+        "skins": [{ "name": "default", "attachments": { "arm": { "arm": { "x": 1, "y": 2, "width": 4, "height": 8 } } } }],
+        "ik": [{ "name": "reach" }],
+        "animations": { "jump": { "bones": { "arm": {
+            "translate": [{ "x": 0, "y": 0, "curve": [0.25, 0, 0.75, 0, 0.25, 0, 0.75, 10] }, { "time": 1, "x": 0, "y": 10 }],
+            "scale": [{ "x": 1, "y": 1, "curve": [0.25, 1.5, 0.75, 2, 0.25, 1, 0.75, 2] }, { "time": 1, "x": 2, "y": 2 }]
+        } } } }
+    }"#;
+
+    const DRAGONBONES: &str = r#"{
+        "frameRate": 10,
+        "armature": [{
+            "name": "hero",
+            "bone": [{ "name": "root" }, { "name": "arm", "parent": "root", "transform": { "x": 5, "skX": 30, "skY": 30 } }],
+            "slot": [{ "name": "arm", "parent": "arm" }],
+            "skin": [{ "slot": [{ "name": "arm", "display": [{ "name": "arm_image" }] }] }],
+            "animation": [{ "name": "wave", "duration": 10, "playTimes": 0, "bone": [{ "name": "arm", "rotateFrame": [
+                { "duration": 5, "tweenEasing": 0, "rotate": 45 },
+                { "duration": 5, "tweenEasing": 0, "curve": [0, 0, 0.5, 1, 1, 1] },
+                { "duration": 0 }
+            ] }] }]
+        }]
+    }"#;
+
+    fn keys<'a>(clip: &'a SkeletonClip, track: &str) -> &'a [crate::timeline::Keyframe]
+    {
+        clip.tracks.iter().find(|bone_track| bone_track.track.name == track).unwrap().track.keys()
+    }
+
+    #[test]
+    fn spine_bones_are_flipped_to_y_down()
+    {
+        let file = parse_skeleton(SPINE).unwrap();
+        let arm = file.skeleton.bone("arm").unwrap();
+        let setup = file.skeleton.bones()[arm].setup;
+        assert_eq!(setup.translation, (10.0, -20.0));
+        assert_eq!(setup.rotation, 90f32.to_radians());
+
+        assert_eq!(file.regions[0].image, "arm");
+        assert_eq!(file.regions[0].offset.translation, (1.0, -2.0));
+        assert_eq!(file.regions[0].size, Some((4.0, 8.0)));
+        assert!(file.unsupported.contains(&"1 ik constraints".to_string()));
+    }
+
+    #[test]
+    fn spine_4_curves_use_the_axis_that_changes()
+    {
+        let file = parse_skeleton(SPINE).unwrap();
+        let translate = keys(&file.clips[0].1, "arm.translate");
+        assert_eq!(translate[0].interpolation, Interpolation::Bezier((0.25, 0.0), (0.75, 1.0)));
+        assert_eq!(translate[1].value, TrackValue::Vec2(10.0, -30.0));
+    }
+
+    #[test]
+    fn spine_4_curves_that_differ_per_axis_are_reported()
+    {
+        let file = parse_skeleton(SPINE).unwrap();
+        assert_eq!(keys(&file.clips[0].1, "arm.scale")[0].interpolation, Interpolation::Linear);
+        assert!(file.unsupported.iter().any(|feature| feature.contains("different x and y curves") && feature.contains("scale")));
+    }
+
+    #[test]
+    fn dragonbones_frames_become_keys()
+    {
+        let file = parse_skeleton(DRAGONBONES).unwrap();
+        let arm = file.skeleton.bone("arm").unwrap();
+        assert_eq!(file.skeleton.bones()[arm].setup.rotation, -30f32.to_radians());
+        assert_eq!(file.regions[0].image, "arm_image");
+
+        let (name, clip) = &file.clips[0];
+        assert_eq!(name, "wave");
+        assert_eq!(clip.duration, 1.0);
+        assert!(clip.looping);
+
+        let rotate = keys(clip, "arm.rotate");
+        assert_eq!(rotate.iter().map(|key| key.time).collect::<Vec<_>>(), vec![0.0, 0.5, 1.0]);
+        assert_eq!(rotate[0].value, TrackValue::Float(-75f32.to_radians()));
+        assert_eq!(rotate[2].interpolation, Interpolation::Step);
+        assert!(file.unsupported.iter().any(|feature| feature.contains("more than one segment")));
+    }
+
+    #[test]
+    fn other_json_is_rejected()
+    {
+        assert!(parse_skeleton("{ \"frames\": [] }").is_err());
+        assert!(parse_skeleton(&SPINE.replace("\"parent\": \"root\"", "\"parent\": \"hand\"")).is_err());
+    }
+}
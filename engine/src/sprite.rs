@@ -0,0 +1,389 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{renderer::Renderer, transform::{Anchor, CoordinateSpace, Transform2D}};
+
+// One frame of a sprite sheet, atlases can cut off the transparent border (trim) and rotate frames to pack them tighter
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frame
+{
+    pub rect: (f32, f32, f32, f32), // x, y, width, height in pixels of the texture, width and height before the rotation
+    pub rotated: bool, // Stored turned 90 degrees clockwise in the texture
+    pub offset: (f32, f32), // Position of rect inside of the untrimmed sprite
+    pub source_size: (f32, f32), // Untrimmed size, the size the frame gets drawn with
+    pub pivot: Option<(f32, f32)> // From the atlas file, 0,0 = top left, 1,1 = bottom right
+}
+
+impl Frame
+{
+    pub fn new(rect: (f32, f32, f32, f32)) -> Self
+    {
+        Self { rect, rotated: false, offset: (0.0, 0.0), source_size: (rect.2, rect.3), pivot: None }
+    }
+
+    // Pivot of the atlas file, Center if there is none
+    pub fn anchor(&self) -> Anchor
+    {
+        self.pivot.map_or(Anchor::Center, |(x, y)| Anchor::Normalized(x, y))
+    }
+
+    // sprite maps pixels of the untrimmed frame (0,0 = top left) to its space
+    pub(crate) fn draw(&self, renderer: &mut Renderer, texture: usize, sprite: Transform2D, z_index: u32)
+    {
+        let (x, y, width, height) = self.rect;
+        let center = (self.offset.0 + width / 2.0, self.offset.1 + height / 2.0);
+        // Rotated frames are drawn as they are stored and then turned back counter-clockwise
+        let (quad, region) = if self.rotated
+        {
+            (Transform2D::new(center, std::f32::consts::FRAC_PI_2, (height, width), CoordinateSpace::Virtual), (height, width))
+        }
+        else
+        {
+            (Transform2D::new(center, 0.0, (width, height), CoordinateSpace::Virtual), (width, height))
+        };
+
+        let texture_size = renderer.texture_size(texture);
+        let uv_min = [x / texture_size.0, y / texture_size.1];
+        let uv_max = [(x + region.0) / texture_size.0, (y + region.1) / texture_size.1];
+        renderer.draw_texture_uv(0, sprite * quad, texture, uv_min, uv_max, z_index);
+    }
+}
+
+// Frames of a texture with named animation clips, loaded from an atlas file (see Renderer::load_atlas) or built by hand
+pub struct SpriteSheet
+{
+    pub texture: usize,
+    pub frames: Vec<Frame>,
+    names: HashMap<String, usize>,
+    clips: HashMap<String, AnimationClip>
+}
+
+impl SpriteSheet
+{
+    pub fn from_frames(texture: usize, frames: Vec<Frame>) -> Self
+    {
+        Self { texture, frames, names: HashMap::new(), clips: HashMap::new() }
+    }
+
+    // Every rect is x, y, width, height in pixels of the texture
+    pub fn from_rects(texture: usize, rects: Vec<(f32, f32, f32, f32)>) -> Self
+    {
+        Self::from_frames(texture, rects.into_iter().map(Frame::new).collect())
+    }
+
+    // Frames of the same size, numbered row by row starting at the top left
+    pub fn grid(texture: usize, frame_size: (f32, f32), columns: usize, rows: usize) -> Self
+    {
+        let frames = (0..rows).flat_map(|row| (0..columns).map(move |column| (column as f32 * frame_size.0, row as f32 * frame_size.1, frame_size.0, frame_size.1))).collect();
+        Self::from_rects(texture, frames)
+    }
+
+    // Names a frame, atlas files name every frame after the image it came from
+    pub fn set_name(&mut self, name: &str, frame: usize)
+    {
+        self.names.insert(name.to_string(), frame);
+    }
+
+    pub fn frame_index(&self, name: &str) -> Option<usize>
+    {
+        self.names.get(name).copied()
+    }
+
+    pub fn frame_names(&self) -> impl Iterator<Item = &String>
+    {
+        self.names.keys()
+    }
+
+    // Clips need at least one frame, AnimatedSprite always shows one
+    pub fn add_clip(&mut self, name: &str, clip: AnimationClip)
+    {
+        assert!(!clip.frames.is_empty(), "Animation clip {} has no frames", name);
+        self.clips.insert(name.to_string(), clip);
+    }
+
+    pub fn with_clip(mut self, name: &str, clip: AnimationClip) -> Self
+    {
+        self.add_clip(name, clip);
+        self
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip>
+    {
+        self.clips.get(name)
+    }
+
+    pub fn clip_names(&self) -> impl Iterator<Item = &String>
+    {
+        self.clips.keys()
+    }
+
+    // Draws a single frame like Renderer::draw_sprite, at its untrimmed size, anchor is relative to the untrimmed size as well
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_frame(&self, renderer: &mut Renderer, frame: usize, pos: (f32, f32), scale: (f32, f32), rotation: f32, anchor: Anchor, z_index: u32)
+    {
+        let frame = &self.frames[frame];
+        let size = frame.source_size;
+        let pivot = anchor.pivot(size);
+
+        // In pixels of the untrimmed frame, 0,0 = top left
+        let sprite = Transform2D::new(pos, rotation, scale, CoordinateSpace::Virtual).with_pivot(((pivot.0 + 0.5) * size.0, (pivot.1 + 0.5) * size.1));
+        frame.draw(renderer, self.texture, sprite, z_index);
+    }
+
+    // Draws the frame with that name around the pivot of the atlas file
+    pub fn draw_named(&self, renderer: &mut Renderer, name: &str, pos: (f32, f32), scale: (f32, f32), rotation: f32, z_index: u32)
+    {
+        let frame = self.frame_index(name).unwrap_or_else(|| panic!("Sprite sheet has no frame named {}", name));
+        self.draw_frame(renderer, frame, pos, scale, rotation, self.frames[frame].anchor(), z_index);
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PlayMode
+{
+    Once, // Stops on the last frame
+    #[default]
+    Loop,
+    PingPong // Forwards, then backwards, then forwards again
+}
+
+// Sequence of frames of a sprite sheet, durations in seconds
+#[derive(Clone, Debug)]
+pub struct AnimationClip
+{
+    pub frames: Vec<usize>,
+    pub durations: Vec<f32>, // One per frame
+    pub mode: PlayMode,
+    pub events: Vec<(usize, String)> // Index into frames and the name reported when that frame gets shown
+}
+
+impl AnimationClip
+{
+    // Every frame shown for frame_duration
+    pub fn new(frames: Vec<usize>, frame_duration: f32, mode: PlayMode) -> Self
+    {
+        let durations = vec![frame_duration; frames.len()];
+        Self { frames, durations, mode, events: Vec::new() }
+    }
+
+    // Frames first..=last of the sheet
+    pub fn range(first: usize, last: usize, frame_duration: f32, mode: PlayMode) -> Self
+    {
+        Self::new((first..=last).collect(), frame_duration, mode)
+    }
+
+    pub fn with_durations(mut self, durations: Vec<f32>) -> Self
+    {
+        assert_eq!(durations.len(), self.frames.len(), "Every frame needs a duration");
+        self.durations = durations;
+        self
+    }
+
+    // For example a footstep on the frame where the foot touches the ground
+    pub fn with_event(mut self, frame: usize, name: &str) -> Self
+    {
+        self.events.push((frame, name.to_string()));
+        self
+    }
+
+    // Length of one pass through the frames
+    pub fn duration(&self) -> f32
+    {
+        self.durations.iter().sum()
+    }
+}
+
+// Reported by AnimatedSprite::update when a frame with an event gets shown
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameEvent
+{
+    pub clip: String,
+    pub name: String,
+    pub frame: usize // Index into the frames of the clip
+}
+
+// Plays the clips of a sprite sheet, owned by the game, updated in update and drawn in render
+pub struct AnimatedSprite
+{
+    pub sheet: Arc<SpriteSheet>,
+    pub speed: f32, // 1.0 = normal speed
+    clip: String,
+    index: usize, // Index into the frames of the clip
+    time: f32, // Time on the current frame
+    reverse: bool, // Going backwards in PingPong
+    finished: bool,
+    entered: bool // The current frame was just entered and its events are not reported yet
+}
+
+impl AnimatedSprite
+{
+    pub fn new(sheet: Arc<SpriteSheet>, clip: &str) -> Self
+    {
+        let mut sprite = Self
+        {
+            sheet,
+            speed: 1.0,
+            clip: String::new(),
+            index: 0,
+            time: 0.0,
+            reverse: false,
+            finished: false,
+            entered: false
+        };
+        sprite.restart(clip);
+        sprite
+    }
+
+    // Switches to clip, keeps playing if it already is the current one
+    pub fn play(&mut self, clip: &str)
+    {
+        if self.clip != clip
+        {
+            self.restart(clip);
+        }
+    }
+
+    // Starts clip from its first frame
+    pub fn restart(&mut self, clip: &str)
+    {
+        assert!(self.sheet.clip(clip).is_some(), "Sprite sheet has no clip named {}", clip);
+        self.clip = clip.to_string();
+        self.index = 0;
+        self.time = 0.0;
+        self.reverse = false;
+        self.finished = false;
+        self.entered = true;
+    }
+
+    pub fn clip(&self) -> &str
+    {
+        &self.clip
+    }
+
+    // Only for Once clips that reached their last frame
+    pub fn is_finished(&self) -> bool
+    {
+        self.finished
+    }
+
+    // Frame of the sprite sheet that is shown right now
+    pub fn current_frame(&self) -> usize
+    {
+        self.sheet.clip(&self.clip).unwrap().frames[self.index]
+    }
+
+    // Advances by dt (the one passed to EngineEvent::update), returns the events of every frame that got shown
+    pub fn update(&mut self, dt: f64) -> Vec<FrameEvent>
+    {
+        let sheet = Arc::clone(&self.sheet);
+        let clip = sheet.clip(&self.clip).unwrap();
+        let mut events = Vec::new();
+
+        if self.entered
+        {
+            self.entered = false;
+            self.collect_events(clip, &mut events);
+        }
+
+        if self.finished || clip.duration() <= 0.0
+        {
+            return events;
+        }
+
+        self.time += dt as f32 * self.speed;
+        // Several frames can pass in one update when dt is big
+        while self.time >= clip.durations[self.index]
+        {
+            self.time -= clip.durations[self.index].max(f32::EPSILON);
+
+            match self.next_index(clip)
+            {
+                Some(index) =>
+                {
+                    self.index = index;
+                    self.collect_events(clip, &mut events);
+                }
+                None =>
+                {
+                    self.finished = true;
+                    self.time = 0.0;
+                    break;
+                }
+            }
+        }
+
+        events
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, pos: (f32, f32), scale: (f32, f32), rotation: f32, anchor: Anchor, z_index: u32)
+    {
+        self.sheet.draw_frame(renderer, self.current_frame(), pos, scale, rotation, anchor, z_index);
+    }
+
+    fn next_index(&mut self, clip: &AnimationClip) -> Option<usize>
+    {
+        let last = clip.frames.len() - 1;
+
+        match clip.mode
+        {
+            PlayMode::Once => (self.index < last).then_some(self.index + 1),
+            PlayMode::Loop => Some(if self.index < last { self.index + 1 } else { 0 }),
+            PlayMode::PingPong =>
+            {
+                if last == 0
+                {
+                    return Some(0);
+                }
+
+                if (self.reverse && self.index == 0) || (!self.reverse && self.index == last)
+                {
+                    self.reverse = !self.reverse;
+                }
+                Some(if self.reverse { self.index - 1 } else { self.index + 1 })
+            }
+        }
+    }
+
+    fn collect_events(&self, clip: &AnimationClip, events: &mut Vec<FrameEvent>)
+    {
+        for (frame, name) in &clip.events
+        {
+            if *frame == self.index
+            {
+                events.push(FrameEvent { clip: self.clip.clone(), name: name.clone(), frame: *frame });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn sheet() -> Arc<SpriteSheet>
+    {
+        let clip = AnimationClip::range(0, 2, 0.1, PlayMode::Loop).with_event(2, "step");
+        Arc::new(SpriteSheet::from_rects(0, vec![(0.0, 0.0, 16.0, 16.0); 3]).with_clip("walk", clip))
+    }
+
+    #[test]
+    #[should_panic(expected = "has no frames")]
+    fn empty_clips_are_rejected()
+    {
+        let _ = SpriteSheet::from_rects(0, vec![(0.0, 0.0, 16.0, 16.0)]).with_clip("empty", AnimationClip::new(Vec::new(), 0.1, PlayMode::Loop));
+    }
+
+    #[test]
+    fn frames_advance_and_report_events()
+    {
+        let mut sprite = AnimatedSprite::new(sheet(), "walk");
+        assert_eq!(sprite.current_frame(), 0);
+
+        let events = sprite.update(0.25);
+        assert_eq!(sprite.current_frame(), 2);
+        assert_eq!(events, vec![FrameEvent { clip: "walk".to_string(), name: "step".to_string(), frame: 2 }]);
+
+        sprite.update(0.1);
+        assert_eq!(sprite.current_frame(), 0);
+    }
+}
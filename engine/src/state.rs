@@ -1,7 +1,7 @@
 use std::iter;
 use winit::{event::*,window::Window};
 
-use crate::renderer::Renderer;
+use crate::{animation_file::{Animation, AnimationFileError}, lottie::{Lottie, LottiePlayer}, renderer::{FrameCapture, Renderer}, skeleton_import::SkeletonRig, sprite::SpriteSheet, texture::TextureOptions, utility::{RenderTargetId, Vertex}, viewport::ScalingPolicy};
 
 pub struct State<'a> 
 {
@@ -11,7 +11,8 @@ pub struct State<'a>
     config: wgpu::SurfaceConfiguration,
     pub size: winit::dpi::PhysicalSize<u32>,
     window: &'a Window,
-    pub renderer: Renderer
+    pub renderer: Renderer,
+    screenshots: Vec<(String, FrameCapture)> // Requested screenshots that are still being copied back
 }
 
 impl<'a> State<'a> 
@@ -55,9 +56,11 @@ impl<'a> State<'a>
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps.formats.iter().copied().find(|f| f.is_srgb()).unwrap_or(surface_caps.formats[0]);
+        // Screenshots copy the presented frame, so the surface has to be a copy source where that is possible
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC);
         let config = wgpu::SurfaceConfiguration 
         {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: size.width,
             height: size.height,
@@ -70,7 +73,7 @@ impl<'a> State<'a>
         surface.configure(&device, &config);
 
         let size = window.inner_size();
-        let renderer = Renderer::new(&device, &config, (size.width as f32, size.height as f32));
+        let renderer = Renderer::new(&device, &queue, &config, (size.width as f32, size.height as f32));
 
         Self 
         {
@@ -80,13 +83,14 @@ impl<'a> State<'a>
             config,
             size,
             window,
-            renderer
+            renderer,
+            screenshots: Vec::new()
         }
     }
 
     pub fn window(&self) -> &Window 
     {
-        &self.window
+        self.window
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) 
@@ -122,19 +126,52 @@ impl<'a> State<'a>
         // self.renderer.draw(0, [[1.0, 0.0, 0.0, 0.0], [0.0, 1.75, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]], [0.0, 0.0, 1.0, 1.0]);
         // self.renderer.draw(0, [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]], [1.0, 0.0, 0.0, 1.0]);
         draw(&mut self.renderer);
-        self.renderer.upload_instances(&self.device);
+        self.renderer.upload_instances(&self.device, &self.queue);
+        self.renderer.post_process.prepare(&self.device, &self.queue, (self.config.width, self.config.height));
         {
             self.renderer.begin_pass(&mut encoder, &view);
         }
 
         self.queue.submit(iter::once(encoder.finish()));
+
+        if let Some(path) = self.renderer.take_screenshot_request()
+        {
+            match FrameCapture::new(&self.device, &self.queue, &output.texture)
+            {
+                Ok(capture) => self.screenshots.push((path, capture)),
+                Err(e) => log::error!("Screenshot failed: {:?}", e)
+            }
+        }
+
+        // Saved a few frames later, once the copy is done
+        let device = &self.device;
+        self.screenshots.retain(|(path, capture)|
+        {
+            match capture.poll(device)
+            {
+                None => return true,
+                Some(Ok(image)) => match image.save(path)
+                {
+                    Ok(_) => log::info!("Saved screenshot to {}", path),
+                    Err(e) => log::error!("Failed to save screenshot to {}: {:?}", path, e)
+                },
+                Some(Err(e)) => log::error!("Screenshot failed: {:?}", e)
+            }
+            false
+        });
+
         output.present();
 
-        self.renderer.draw_commands.clear();
+        self.renderer.end_frame();
 
         Ok(())
     }
 
+    pub fn request_screenshot(&mut self, path: &str)
+    {
+        self.renderer.request_screenshot(path);
+    }
+
     // pub fn load_texture(&mut self, path: &str) -> usize // Returns ID
     // {
     //     self.renderer.load_texture(&self.device, &self.queue, path)
@@ -150,8 +187,23 @@ impl<'a> State<'a>
 pub trait Loader
 {
     fn load_texture(&mut self, path: &str) -> usize;
+    fn load_texture_with_options(&mut self, path: &str, options: TextureOptions) -> usize;
+    fn load_atlas(&mut self, path: &str) -> SpriteSheet;
+    fn load_atlas_with_options(&mut self, path: &str, options: TextureOptions) -> SpriteSheet;
+    fn load_animation(&mut self, path: &str) -> Result<Animation, AnimationFileError>;
+    fn create_mesh(&mut self, vertices: &[Vertex], indices: &[u16]) -> usize;
+    fn create_dynamic_mesh(&mut self, vertices: usize, indices: usize) -> usize;
+    fn load_skeleton(&mut self, path: &str, atlas_path: &str) -> SkeletonRig;
+    fn load_lottie(&mut self, path: &str) -> LottiePlayer;
     fn load_char(&mut self, char: char) -> Option<usize>;
     fn load_text(&mut self, text: &str, size: f32) -> Option<usize>;
+    fn create_render_target(&mut self, width: u32, height: u32) -> RenderTargetId;
+    fn load_effect(&mut self, name: &str, path: &str) -> anyhow::Result<()>;
+    fn load_effect_texture(&mut self, name: &str, path: &str);
+    fn load_shader(&mut self, path: &str) -> anyhow::Result<usize>;
+    fn set_scaling(&mut self, virtual_size: (f32, f32), policy: ScalingPolicy);
+    fn set_pixel_art(&mut self, enabled: bool);
+    fn create_material(&mut self, shader: usize, textures: &[usize], uniforms: &[u8]) -> anyhow::Result<usize>;
 }
 
 pub struct LoadingContext<'a> 
@@ -176,6 +228,51 @@ impl<'a> Loader for LoadingContext<'a>
         self.renderer.load_texture(self.device, self.queue, path)
     }
 
+    fn load_texture_with_options(&mut self, path: &str, options: TextureOptions) -> usize
+    {
+        self.renderer.load_texture_with_options(self.device, self.queue, path, options)
+    }
+
+    fn load_atlas(&mut self, path: &str) -> SpriteSheet
+    {
+        self.renderer.load_atlas(self.device, self.queue, path)
+    }
+
+    fn load_atlas_with_options(&mut self, path: &str, options: TextureOptions) -> SpriteSheet
+    {
+        self.renderer.load_atlas_with_options(self.device, self.queue, path, options)
+    }
+
+    fn load_animation(&mut self, path: &str) -> Result<Animation, AnimationFileError>
+    {
+        self.renderer.load_animation(self.device, self.queue, path)
+    }
+
+    fn create_mesh(&mut self, vertices: &[Vertex], indices: &[u16]) -> usize
+    {
+        self.renderer.create_mesh(self.device, vertices, indices)
+    }
+
+    fn create_dynamic_mesh(&mut self, vertices: usize, indices: usize) -> usize
+    {
+        self.renderer.create_dynamic_mesh(self.device, vertices, indices)
+    }
+
+    fn load_skeleton(&mut self, path: &str, atlas_path: &str) -> SkeletonRig
+    {
+        self.renderer.load_skeleton(self.device, self.queue, path, atlas_path)
+    }
+
+    fn load_lottie(&mut self, path: &str) -> LottiePlayer
+    {
+        let animation = Lottie::load(path).unwrap_or_else(|e| panic!("Failed to load lottie with path: {}: {}", path, e));
+        for feature in &animation.unsupported
+        {
+            log::warn!("{}: {} is not supported", path, feature);
+        }
+        LottiePlayer::new(self, animation)
+    }
+
     fn load_char(&mut self, char: char) -> Option<usize>
     {
         self.renderer.load_char(self.device, self.queue, char)
@@ -185,4 +282,40 @@ impl<'a> Loader for LoadingContext<'a>
     {
         self.renderer.load_text(self.device, self.queue, text, size)
     }
+
+    fn create_render_target(&mut self, width: u32, height: u32) -> RenderTargetId
+    {
+        self.renderer.create_render_target(self.device, width, height)
+    }
+
+    fn load_effect(&mut self, name: &str, path: &str) -> anyhow::Result<()>
+    {
+        self.renderer.load_effect(self.device, name, path)
+    }
+
+    fn load_effect_texture(&mut self, name: &str, path: &str)
+    {
+        self.renderer.load_effect_texture(self.device, self.queue, name, path)
+    }
+
+    fn set_scaling(&mut self, virtual_size: (f32, f32), policy: ScalingPolicy)
+    {
+        self.renderer.virtual_size = virtual_size;
+        self.renderer.scaling_policy = policy;
+    }
+
+    fn set_pixel_art(&mut self, enabled: bool)
+    {
+        self.renderer.pixel_art = enabled;
+    }
+
+    fn load_shader(&mut self, path: &str) -> anyhow::Result<usize>
+    {
+        self.renderer.load_shader(self.device, path)
+    }
+
+    fn create_material(&mut self, shader: usize, textures: &[usize], uniforms: &[u8]) -> anyhow::Result<usize>
+    {
+        self.renderer.create_material(self.device, shader, textures, uniforms)
+    }
 }
\ No newline at end of file
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use ab_glyph::{point, Font, FontArc, PxScale};
 use anyhow::{Ok, anyhow};
 
@@ -140,8 +138,6 @@ pub fn rasterize_static_text(font_path: &str, text: &str, text_scale: f32) -> st
     let mut bitmaps = Vec::new();
 
     let mut total_width = 0;
-    let mut total_height = 0;
-
     let mut min_y = f32::MAX;
     let mut max_y = f32::MIN;
 
@@ -181,13 +177,13 @@ pub fn rasterize_static_text(font_path: &str, text: &str, text_scale: f32) -> st
         println!("Char '{}', height: {}", char, height);
     }
     // let y_offset = -min_y.ceil() as usize;
-    total_height = (max_y - min_y).ceil() as usize;
+    let total_height = (max_y - min_y).ceil() as usize;
 
     let mut atlas = vec![0u8; total_width * total_height];
 
     let mut x_cursor = 0;
 
-    for ((bitmap, width, height), y_offset) in bitmaps.into_iter().zip(glyph_offsets.into_iter())
+    for ((bitmap, width, height), y_offset) in bitmaps.into_iter().zip(glyph_offsets)
     {
         for y in 0..height
         {
@@ -2,6 +2,90 @@ use image::GenericImageView;
 use anyhow::*;
 
 
+// How a texture gets sampled
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextureOptions
+{
+    pub mag_filter: wgpu::FilterMode, // When drawn bigger than the texture
+    pub min_filter: wgpu::FilterMode, // When drawn smaller than the texture
+    pub address_mode: wgpu::AddressMode, // ClampToEdge, Repeat or MirrorRepeat for uvs outside of 0..1
+    pub mipmaps: bool // Generated on load, so big textures do not shimmer when drawn smaller, turn off for UI, pixel art and atlases
+}
+
+// Trilinear with mipmaps, the textures before mipmaps used Linear for mag_filter only (see TextureOptions::sprite_sheet)
+impl Default for TextureOptions
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            address_mode: wgpu::AddressMode::ClampToEdge,
+            mipmaps: true
+        }
+    }
+}
+
+impl TextureOptions
+{
+    // Crisp pixels when scaled up
+    pub fn pixel_art() -> Self
+    {
+        Self
+        {
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            address_mode: wgpu::AddressMode::ClampToEdge,
+            mipmaps: false
+        }
+    }
+
+    // No mipmaps and the sampling of textures from before mipmaps, lower levels would mix neighbouring frames of an atlas
+    pub fn sprite_sheet() -> Self
+    {
+        Self
+        {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            address_mode: wgpu::AddressMode::ClampToEdge,
+            mipmaps: false
+        }
+    }
+
+    // Default, but uvs outside of 0..1 wrap around, for Renderer::draw_tiled
+    pub fn repeating() -> Self
+    {
+        Self
+        {
+            address_mode: wgpu::AddressMode::Repeat,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_mipmaps(mut self, mipmaps: bool) -> Self
+    {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    pub fn sampler(&self, device: &wgpu::Device) -> wgpu::Sampler
+    {
+        device.create_sampler(&wgpu::SamplerDescriptor
+        {
+            address_mode_u: self.address_mode,
+            address_mode_v: self.address_mode,
+            address_mode_w: self.address_mode,
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            // Linear between mip levels too (trilinear), only matters for textures that have mipmaps
+            mipmap_filter: if self.mipmaps { wgpu::FilterMode::Linear } else { wgpu::FilterMode::Nearest },
+            ..Default::default()
+        })
+    }
+}
+
+
 // Right now, it is just used as a hlper, that returns things, but I need to change it, so that it does everything texture related
 pub struct TextureHandler
 {
@@ -26,6 +110,12 @@ impl TextureHandler
         Self::from_image(device, queue, &img, None)
     }
     
+    pub fn new_with_options(device: &wgpu::Device, queue: &wgpu::Queue, path: &str, options: TextureOptions) -> Result<Self>
+    {
+        let img = image::open(path)?;
+        Self::from_image_with_options(device, queue, &img, None, options)
+    }
+
     pub fn from_bytes(device: &wgpu::Device, queue: &wgpu::Queue, bytes: &[u8], label: &str) -> Result<Self>
     {
         let img = image::load_from_memory(bytes)?;
@@ -33,6 +123,11 @@ impl TextureHandler
     }
 
     pub fn from_image(device: &wgpu::Device, queue: &wgpu::Queue, img: &image::DynamicImage, label: Option<&str>) -> Result<Self>
+    {
+        Self::from_image_with_options(device, queue, img, label, TextureOptions::default())
+    }
+
+    pub fn from_image_with_options(device: &wgpu::Device, queue: &wgpu::Queue, img: &image::DynamicImage, label: Option<&str>, options: TextureOptions) -> Result<Self>
     {
         let rgba = img.to_rgba8();
         let dimensions = img.dimensions();
@@ -44,11 +139,14 @@ impl TextureHandler
             depth_or_array_layers: 1,
         };
 
+        // Halving until 1x1
+        let mip_level_count = if options.mipmaps { 32 - dimensions.0.max(dimensions.1).leading_zeros() } else { 1 };
+
         let texture = device.create_texture(&wgpu::TextureDescriptor
         {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
@@ -74,18 +172,37 @@ impl TextureHandler
             size
         );
 
+        // Every level gets downsampled on the cpu from the one before, in linear space so edges do not get darker
+        let mut level = if mip_level_count > 1 { to_linear(&rgba) } else { image::Rgba32FImage::default() };
+        for mip_level in 1..mip_level_count
+        {
+            let width = (dimensions.0 >> mip_level).max(1);
+            let height = (dimensions.1 >> mip_level).max(1);
+            level = image::imageops::resize(&level, width, height, image::imageops::FilterType::Triangle);
+            let srgb = to_srgb(&level);
+
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo
+                {
+                    texture: &texture,
+                    mip_level,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &srgb,
+                wgpu::TexelCopyBufferLayout
+                {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d { width, height, depth_or_array_layers: 1 }
+            );
+        }
+
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor
-        {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        let sampler = options.sampler(device);
 
         Ok(Self { texture, view, sampler, bind_group: None })
     }
@@ -138,26 +255,44 @@ impl TextureHandler
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor
-        {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        let sampler = TextureOptions::default().sampler(device);
 
         Ok(Self { texture, view, sampler, bind_group: None })
     }
 
 
 
+    // Empty texture that can be rendered into and then sampled like any other texture
+    pub fn render_target(device: &wgpu::Device, width: u32, height: u32, format: wgpu::TextureFormat, options: TextureOptions) -> Self
+    {
+        let texture = device.create_texture(&wgpu::TextureDescriptor
+        {
+            label: Some("Render Target"),
+            size: wgpu::Extent3d
+            {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[]
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = options.sampler(device);
+
+        Self { texture, view, sampler, bind_group: None }
+    }
+
     // bindgroup_layout
     pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout
     {
-        let texture_bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor
         {
             label: Some("Texture Bind Group Layout"),
             entries: 
@@ -182,8 +317,7 @@ impl TextureHandler
                     count: None
                 }
             ]
-        });
-        texture_bindgroup_layout
+        })
     }
 
     // bind_group
@@ -210,4 +344,67 @@ impl TextureHandler
         // self.bind_group = Some(bind_group);
         bind_group
     }
-}
\ No newline at end of file
+}
+// The texture is Rgba8UnormSrgb, so the bytes are sRGB and averaging them directly would be too dark, alpha is already linear
+fn to_linear(rgba: &image::RgbaImage) -> image::Rgba32FImage
+{
+    let mut linear = image::Rgba32FImage::new(rgba.width(), rgba.height());
+    for (from, to) in rgba.pixels().zip(linear.pixels_mut())
+    {
+        let [r, g, b, a] = from.0;
+        to.0 = [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a as f32 / 255.0];
+    }
+    linear
+}
+
+fn to_srgb(linear: &image::Rgba32FImage) -> image::RgbaImage
+{
+    let mut rgba = image::RgbaImage::new(linear.width(), linear.height());
+    for (from, to) in linear.pixels().zip(rgba.pixels_mut())
+    {
+        let [r, g, b, a] = from.0;
+        to.0 = [linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), (a.clamp(0.0, 1.0) * 255.0).round() as u8];
+    }
+    rgba
+}
+
+fn srgb_to_linear(value: u8) -> f32
+{
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(value: f32) -> u8
+{
+    let value = value.clamp(0.0, 1.0);
+    let value = if value <= 0.0031308 { value * 12.92 } else { 1.055 * value.powf(1.0 / 2.4) - 0.055 };
+    (value * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn srgb_round_trips()
+    {
+        for value in 0..=255
+        {
+            assert_eq!(linear_to_srgb(srgb_to_linear(value)), value);
+        }
+    }
+
+    #[test]
+    fn downsampling_averages_light_not_bytes()
+    {
+        // Black and white stripes, half the light is sRGB 188 and not 128
+        let stripes = image::RgbaImage::from_fn(2, 2, |x, _| if x % 2 == 0 { image::Rgba([0, 0, 0, 255]) } else { image::Rgba([255, 255, 255, 255]) });
+        let half = to_srgb(&image::imageops::resize(&to_linear(&stripes), 1, 1, image::imageops::FilterType::Triangle));
+
+        for pixel in half.pixels()
+        {
+            assert_eq!(pixel.0, [188, 188, 188, 255]);
+        }
+    }
+}
@@ -0,0 +1,446 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tween::{self, Lerp};
+
+// What a track animates, only a hint for tools and for applying the values, the value type is what matters for sampling
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Property
+{
+    Position,
+    Rotation,
+    Scale,
+    Color,
+    Opacity,
+    Frame // Sprite sheet frame, always stepped
+}
+
+impl Property
+{
+    // Same as in animation files
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Property::Position => "position",
+            Property::Rotation => "rotation",
+            Property::Scale => "scale",
+            Property::Color => "color",
+            Property::Opacity => "opacity",
+            Property::Frame => "frame"
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TrackValue
+{
+    Float(f32), // Rotation, opacity
+    Vec2(f32, f32), // Position, scale
+    Color([f32; 4]),
+    Frame(usize)
+}
+
+impl TrackValue
+{
+    pub fn as_f32(&self) -> Option<f32>
+    {
+        if let TrackValue::Float(value) = *self { Some(value) } else { None }
+    }
+
+    pub fn as_vec2(&self) -> Option<(f32, f32)>
+    {
+        if let TrackValue::Vec2(x, y) = *self { Some((x, y)) } else { None }
+    }
+
+    pub fn as_color(&self) -> Option<[f32; 4]>
+    {
+        if let TrackValue::Color(color) = *self { Some(color) } else { None }
+    }
+
+    pub fn as_frame(&self) -> Option<usize>
+    {
+        if let TrackValue::Frame(frame) = *self { Some(frame) } else { None }
+    }
+}
+
+// Values of different types do not blend, the first one is kept until the next key, frames as well
+impl Lerp for TrackValue
+{
+    fn lerp(&self, to: &Self, t: f32) -> Self
+    {
+        match (*self, *to)
+        {
+            (TrackValue::Float(a), TrackValue::Float(b)) => TrackValue::Float(a.lerp(&b, t)),
+            (TrackValue::Vec2(ax, ay), TrackValue::Vec2(bx, by)) => TrackValue::Vec2(ax.lerp(&bx, t), ay.lerp(&by, t)),
+            (TrackValue::Color(a), TrackValue::Color(b)) => TrackValue::Color(a.lerp(&b, t)),
+            _ => *self
+        }
+    }
+}
+
+// How the value gets from a key to the next one
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation
+{
+    Step, // Holds the value until the next key
+    #[default]
+    Linear,
+    // Out handle of this key and in handle of the next key, both as (time, value) relative to the segment, like css cubic-bezier
+    Bezier((f32, f32), (f32, f32))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Keyframe
+{
+    pub time: f32, // Seconds
+    pub value: TrackValue,
+    pub interpolation: Interpolation // To the next key
+}
+
+pub struct Track
+{
+    pub name: String,
+    pub property: Property,
+    keys: Vec<Keyframe> // Sorted by time
+}
+
+impl Track
+{
+    pub fn new(name: &str, property: Property) -> Self
+    {
+        Self { name: name.to_string(), property, keys: Vec::new() }
+    }
+
+    // Replaces the key at the same time
+    pub fn add_key(&mut self, time: f32, value: TrackValue, interpolation: Interpolation)
+    {
+        let key = Keyframe { time, value, interpolation };
+        match self.keys.binary_search_by(|k| k.time.total_cmp(&time))
+        {
+            Ok(index) => self.keys[index] = key,
+            Err(index) => self.keys.insert(index, key)
+        }
+    }
+
+    pub fn with_key(mut self, time: f32, value: TrackValue, interpolation: Interpolation) -> Self
+    {
+        self.add_key(time, value, interpolation);
+        self
+    }
+
+    pub fn remove_key(&mut self, index: usize) -> Keyframe
+    {
+        self.keys.remove(index)
+    }
+
+    pub fn keys(&self) -> &[Keyframe]
+    {
+        &self.keys
+    }
+
+    // Time of the last key
+    pub fn duration(&self) -> f32
+    {
+        self.keys.last().map_or(0.0, |key| key.time)
+    }
+
+    // Before the first key it has the value of the first key, after the last key the value of the last one
+    pub fn sample(&self, time: f32) -> Option<TrackValue>
+    {
+        let next = self.keys.partition_point(|key| key.time <= time);
+        if next == 0
+        {
+            return self.keys.first().map(|key| key.value);
+        }
+
+        let key = &self.keys[next - 1];
+        let Some(next) = self.keys.get(next) else { return Some(key.value) };
+
+        let t = (time - key.time) / (next.time - key.time);
+        let t = match key.interpolation
+        {
+            _ if self.property == Property::Frame => 0.0,
+            Interpolation::Step => 0.0,
+            Interpolation::Linear => t,
+            Interpolation::Bezier(out, into) => tween::cubic_bezier(out.0, out.1, into.0, into.1, t)
+        };
+        Some(key.value.lerp(&next.value, t))
+    }
+}
+
+// Tracks that play together, owned by the game and updated with dt in update, or stepped with seek_frame for offline export
+pub struct Timeline
+{
+    pub tracks: Vec<Track>,
+    pub speed: f32, // Negative plays backwards
+    pub loop_region: Option<(f32, f32)>, // Start and end in seconds, playback wraps inside of it once it got there
+    time: f32,
+    playing: bool,
+    duration: Option<f32> // None = until the last key of all tracks
+}
+
+impl Default for Timeline
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl Timeline
+{
+    pub fn new() -> Self
+    {
+        Self
+        {
+            tracks: Vec::new(),
+            speed: 1.0,
+            loop_region: None,
+            time: 0.0,
+            playing: false,
+            duration: None
+        }
+    }
+
+    pub fn add_track(&mut self, track: Track) -> usize
+    {
+        self.tracks.push(track);
+        self.tracks.len() - 1
+    }
+
+    pub fn with_track(mut self, track: Track) -> Self
+    {
+        self.add_track(track);
+        self
+    }
+
+    pub fn track(&self, name: &str) -> Option<&Track>
+    {
+        self.tracks.iter().find(|track| track.name == name)
+    }
+
+    pub fn track_mut(&mut self, name: &str) -> Option<&mut Track>
+    {
+        self.tracks.iter_mut().find(|track| track.name == name)
+    }
+
+    // Loops the whole timeline
+    pub fn with_loop(mut self) -> Self
+    {
+        self.loop_region = Some((0.0, self.duration()));
+        self
+    }
+
+    pub fn set_duration(&mut self, duration: Option<f32>)
+    {
+        self.duration = duration;
+    }
+
+    // Set with set_duration, None when the timeline ends at its last key
+    pub fn explicit_duration(&self) -> Option<f32>
+    {
+        self.duration
+    }
+
+    pub fn duration(&self) -> f32
+    {
+        self.duration.unwrap_or_else(|| self.tracks.iter().map(Track::duration).fold(0.0, f32::max))
+    }
+
+    pub fn play(&mut self)
+    {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self)
+    {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool
+    {
+        self.playing
+    }
+
+    pub fn time(&self) -> f32
+    {
+        self.time
+    }
+
+    pub fn seek(&mut self, time: f32)
+    {
+        self.time = time.clamp(0.0, self.duration());
+    }
+
+    // Exact time of frame at fps, so exporting does not collect rounding errors like adding up dt would
+    pub fn seek_frame(&mut self, frame: u32, fps: f32)
+    {
+        self.seek(frame as f32 / fps);
+    }
+
+    // Frames needed to export the whole timeline at fps, including the last key
+    pub fn frame_count(&self, fps: f32) -> u32
+    {
+        (self.duration() * fps).floor() as u32 + 1
+    }
+
+    // Advances by dt when playing, stops at the ends unless it is inside of the loop region
+    pub fn update(&mut self, dt: f64)
+    {
+        if !self.playing
+        {
+            return;
+        }
+
+        let previous = self.time;
+        self.time += dt as f32 * self.speed;
+
+        if let Some((start, end)) = self.loop_region && end > start
+        {
+            let length = end - start;
+            // Only wraps once the playhead is in the region, so an intro before it plays once
+            let inside = previous >= start && previous <= end;
+            if self.speed > 0.0 && inside && self.time >= end
+            {
+                self.time = start + (self.time - end).rem_euclid(length);
+                return;
+            }
+            if self.speed < 0.0 && inside && self.time <= start
+            {
+                self.time = end - (start - self.time).rem_euclid(length);
+                return;
+            }
+        }
+
+        // Only the end it is heading to stops it, so playing from the other end works
+        let duration = self.duration();
+        if (self.speed > 0.0 && self.time >= duration) || (self.speed < 0.0 && self.time <= 0.0)
+        {
+            self.time = self.time.clamp(0.0, duration);
+            self.playing = false;
+        }
+    }
+
+    // Value of the track with that name at the current time
+    pub fn value(&self, track: &str) -> Option<TrackValue>
+    {
+        self.track(track)?.sample(self.time)
+    }
+
+    // Every track at the current time
+    pub fn sample(&self) -> Vec<(&Track, TrackValue)>
+    {
+        self.tracks.iter().filter_map(|track| track.sample(self.time).map(|value| (track, value))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn timeline() -> Timeline
+    {
+        Timeline::new()
+            .with_track(Track::new("x", Property::Opacity)
+                .with_key(0.0, TrackValue::Float(0.0), Interpolation::Linear)
+                .with_key(1.0, TrackValue::Float(10.0), Interpolation::Step)
+                .with_key(2.0, TrackValue::Float(20.0), Interpolation::Linear))
+            .with_track(Track::new("frame", Property::Frame)
+                .with_key(0.0, TrackValue::Frame(0), Interpolation::Linear)
+                .with_key(2.0, TrackValue::Frame(4), Interpolation::Linear))
+    }
+
+    #[test]
+    fn tracks_interpolate_between_keys()
+    {
+        let mut timeline = timeline();
+        timeline.seek(0.5);
+        assert_eq!(timeline.value("x"), Some(TrackValue::Float(5.0)));
+        timeline.seek(1.5);
+        assert_eq!(timeline.value("x"), Some(TrackValue::Float(10.0)));
+        // Frames are stepped even with linear keys
+        assert_eq!(timeline.value("frame"), Some(TrackValue::Frame(0)));
+        timeline.seek(5.0);
+        assert_eq!(timeline.time(), 2.0);
+        assert_eq!(timeline.sample().len(), 2);
+        assert_eq!(timeline.value("missing"), None);
+    }
+
+    #[test]
+    fn keys_at_the_same_time_are_replaced()
+    {
+        let track = Track::new("x", Property::Opacity)
+            .with_key(1.0, TrackValue::Float(1.0), Interpolation::Linear)
+            .with_key(0.0, TrackValue::Float(0.0), Interpolation::Linear)
+            .with_key(1.0, TrackValue::Float(2.0), Interpolation::Linear);
+        assert_eq!(track.keys().len(), 2);
+        assert_eq!(track.sample(-1.0), Some(TrackValue::Float(0.0)));
+        assert_eq!(track.sample(3.0), Some(TrackValue::Float(2.0)));
+    }
+
+    #[test]
+    fn playback_stops_at_the_end_and_wraps_in_the_loop_region()
+    {
+        let mut timeline = timeline();
+        timeline.play();
+        timeline.update(3.0);
+        assert_eq!(timeline.time(), 2.0);
+        assert!(!timeline.is_playing());
+
+        let mut timeline = timeline.with_loop();
+        timeline.seek(0.0);
+        timeline.play();
+        timeline.update(2.5);
+        assert!((timeline.time() - 0.5).abs() < 1e-5);
+        assert!(timeline.is_playing());
+    }
+
+    #[test]
+    fn playback_starts_from_either_end()
+    {
+        // A first frame without time does not stop it at the start
+        let mut timeline = timeline();
+        timeline.play();
+        timeline.update(0.0);
+        assert!(timeline.is_playing());
+
+        let mut timeline = timeline.with_loop();
+        timeline.seek(2.0);
+        timeline.play();
+        timeline.update(0.5);
+        assert!((timeline.time() - 0.5).abs() < 1e-5);
+        assert!(timeline.is_playing());
+
+        timeline.speed = -1.0;
+        timeline.seek(0.0);
+        timeline.update(0.5);
+        assert!((timeline.time() - 1.5).abs() < 1e-5);
+        assert!(timeline.is_playing());
+    }
+
+    #[test]
+    fn backwards_playback_stops_at_the_start()
+    {
+        let mut timeline = timeline();
+        timeline.speed = -1.0;
+        timeline.seek(2.0);
+        timeline.play();
+        timeline.update(0.0);
+        assert!(timeline.is_playing());
+        timeline.update(3.0);
+        assert_eq!(timeline.time(), 0.0);
+        assert!(!timeline.is_playing());
+    }
+
+    #[test]
+    fn frames_cover_the_last_key()
+    {
+        let mut timeline = timeline();
+        assert_eq!(timeline.frame_count(10.0), 21);
+        timeline.seek_frame(15, 10.0);
+        assert_eq!(timeline.time(), 1.5);
+    }
+}
@@ -0,0 +1,320 @@
+use std::ops::Mul;
+
+use cgmath::{Matrix3, Rad, SquareMatrix, Vector2, Vector3};
+
+use crate::{renderer::Renderer, viewport::{ScalingPolicy, Viewport}};
+
+// Every space is in pixels with y pointing down
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CoordinateSpace
+{
+    Screen, // Actual window pixels
+    #[default]
+    Virtual, // Pixels of virtual_size, fitted into the window by the scaling policy of the renderer
+    World // World units of the camera passed to Renderer::with_camera, the same as Virtual without a camera
+}
+
+// Point of a sprite that gets placed at the position and that it rotates around
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Anchor
+{
+    TopLeft,
+    TopCenter,
+    TopRight,
+    CenterLeft,
+    #[default]
+    Center,
+    CenterRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+    Normalized(f32, f32), // 0,0 = top left, 1,1 = bottom right
+    Pixels(f32, f32) // Offset from the top left in pixels of the sprite
+}
+
+impl Anchor
+{
+    // As Transform2D::pivot (0,0 = center), size is the unscaled size of the sprite, only needed for Pixels
+    pub fn pivot(&self, size: (f32, f32)) -> (f32, f32)
+    {
+        let normalized = match *self
+        {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::TopCenter => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::CenterLeft => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::CenterRight => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::BottomCenter => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+            Anchor::Normalized(x, y) => (x, y),
+            Anchor::Pixels(x, y) => (x / size.0, y / size.1)
+        };
+        (normalized.0 - 0.5, normalized.1 - 0.5)
+    }
+}
+
+// Local transform of a quad (or any mesh in -0.5..0.5), the order is scale, skew, rotation, translation
+// scale is the size in pixels for the quad, pivot the point of the mesh that ends up at translation (0,0 = center, -0.5,-0.5 = top left)
+// Multiplying parent * child puts the child into the parent, the result has the space of the parent
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform2D
+{
+    pub translation: (f32, f32),
+    pub rotation: f32, // Radians, counter-clockwise
+    pub scale: (f32, f32),
+    pub pivot: (f32, f32),
+    pub skew: (f32, f32), // Radians, x shears along x with increasing y, y along y with increasing x
+    pub space: CoordinateSpace,
+    pub parent: Matrix3<f32>
+}
+
+impl Transform2D
+{
+    pub fn new(translation: (f32, f32), rotation: f32, scale: (f32, f32), space: CoordinateSpace) -> Self
+    {
+        Self
+        {
+            translation,
+            rotation,
+            scale,
+            pivot: (0.0, 0.0),
+            skew: (0.0, 0.0),
+            space,
+            parent: Matrix3::identity()
+        }
+    }
+
+    pub fn identity(space: CoordinateSpace) -> Self
+    {
+        Self::new((0.0, 0.0), 0.0, (1.0, 1.0), space)
+    }
+
+    pub fn with_pivot(mut self, pivot: (f32, f32)) -> Self
+    {
+        self.pivot = pivot;
+        self
+    }
+
+    // Pixels anchors are in drawn pixels (the size is scale), so this has to be called after scale is set
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self
+    {
+        self.pivot = anchor.pivot(self.scale);
+        self
+    }
+
+    pub fn with_skew(mut self, skew: (f32, f32)) -> Self
+    {
+        self.skew = skew;
+        self
+    }
+
+    // Local matrix combined with the parents, in pixels of the space, y down
+    pub fn matrix(&self) -> Matrix3<f32>
+    {
+        let translation = Matrix3::from_translation(Vector2::new(self.translation.0, self.translation.1));
+        // Negative, because y is down, so this is counter-clockwise on screen
+        let rotation = Matrix3::from_angle_z(Rad(-self.rotation));
+        let skew = Matrix3::new(1.0, self.skew.1.tan(), 0.0, self.skew.0.tan(), 1.0, 0.0, 0.0, 0.0, 1.0);
+        let scale = Matrix3::from_nonuniform_scale(self.scale.0, self.scale.1);
+        let pivot = Matrix3::from_translation(Vector2::new(-self.pivot.0, -self.pivot.1));
+
+        self.parent * translation * rotation * skew * scale * pivot
+    }
+
+    // Maps a point of the mesh (y down) into the space
+    pub fn transform_point(&self, point: (f32, f32)) -> (f32, f32)
+    {
+        let p = self.matrix() * cgmath::Vector3::new(point.0, point.1, 1.0);
+        (p.x, p.y)
+    }
+}
+
+impl Mul for Transform2D
+{
+    type Output = Transform2D;
+
+    fn mul(self, child: Transform2D) -> Transform2D
+    {
+        Transform2D
+        {
+            space: self.space,
+            parent: self.matrix() * child.parent,
+            ..child
+        }
+    }
+}
+
+// Clip space matrix of the transform, or world space if it gets drawn with a camera
+// snap moves the top left corner of the mesh onto a whole pixel of the space (pixel art mode)
+pub fn to_clip(transform: &Transform2D, viewport: &Viewport, camera: bool, snap: bool) -> Matrix3<f32>
+{
+    // The quad is y up, every space is y down
+    let mut m = transform.matrix() * Matrix3::from_nonuniform_scale(1.0, -1.0);
+
+    if snap
+    {
+        let corner = m * cgmath::Vector3::new(-0.5, 0.5, 1.0);
+        m = Matrix3::from_translation(Vector2::new(corner.x.round() - corner.x, corner.y.round() - corner.y)) * m;
+    }
+
+    match transform.space
+    {
+        CoordinateSpace::Screen => pixel_projection(viewport.window_size) * m,
+        CoordinateSpace::World if camera => m,
+        CoordinateSpace::Virtual | CoordinateSpace::World if viewport.policy == ScalingPolicy::Legacy =>
+        {
+            // Only the position gets stretched, the rest scales with the height
+            let scale = viewport.scale();
+            let m = Matrix3::from_cols(m.x * scale.1, m.y * scale.1, Vector3::new(m.z.x * scale.0, m.z.y * scale.1, 1.0));
+            pixel_projection(viewport.window_size) * m
+        }
+        CoordinateSpace::Virtual | CoordinateSpace::World => virtual_projection(viewport) * m
+    }
+}
+
+// Virtual pixels to clip space
+pub fn virtual_projection(viewport: &Viewport) -> Matrix3<f32>
+{
+    let (scale, offset) = (viewport.scale(), viewport.offset());
+    pixel_projection(viewport.window_size) * Matrix3::from_translation(Vector2::new(offset.0, offset.1)) * Matrix3::from_nonuniform_scale(scale.0, scale.1)
+}
+
+// Pixels (y down) to clip space
+pub fn pixel_projection(size: (f32, f32)) -> Matrix3<f32>
+{
+    Matrix3::from_translation(Vector2::new(-1.0, 1.0)) * Matrix3::from_nonuniform_scale(2.0 / size.0, -2.0 / size.1)
+}
+
+// 2D affine matrix to the 4x4 column-major model matrix the shader uses
+pub fn to_model(m: Matrix3<f32>) -> [[f32; 4]; 4]
+{
+    [
+        [m.x.x, m.x.y, 0.0, 0.0],
+        [m.y.x, m.y.y, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [m.z.x, m.z.y, 0.0, 1.0]
+    ]
+}
+
+// Everything the draw calls accept as transform
+pub trait DrawTransform
+{
+    // Model matrix and the camera of the frame it is drawn with (0 = none)
+    fn resolve(&self, renderer: &Renderer) -> ([[f32; 4]; 4], usize);
+}
+
+// Raw model matrices are used as they are, with the current camera
+impl DrawTransform for [[f32; 4]; 4]
+{
+    fn resolve(&self, renderer: &Renderer) -> ([[f32; 4]; 4], usize)
+    {
+        (*self, renderer.current_camera())
+    }
+}
+
+// Only World uses the camera, Screen and Virtual ignore it
+impl DrawTransform for Transform2D
+{
+    fn resolve(&self, renderer: &Renderer) -> ([[f32; 4]; 4], usize)
+    {
+        let camera = if self.space == CoordinateSpace::World { renderer.current_camera() } else { 0 };
+        (renderer.transform_matrix(self), camera)
+    }
+}
+
+impl DrawTransform for &Transform2D
+{
+    fn resolve(&self, renderer: &Renderer) -> ([[f32; 4]; 4], usize)
+    {
+        (*self).resolve(renderer)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn assert_near(actual: (f32, f32), expected: (f32, f32), case: &str)
+    {
+        assert!((actual.0 - expected.0).abs() < 1e-5 && (actual.1 - expected.1).abs() < 1e-5, "{case}: {actual:?} != {expected:?}");
+    }
+
+    // Clip position of a point of the y up quad
+    fn clip(m: Matrix3<f32>, point: (f32, f32)) -> (f32, f32)
+    {
+        let clip = m * Vector3::new(point.0, point.1, 1.0);
+        (clip.x, clip.y)
+    }
+
+    #[test]
+    fn anchors_are_pivots_around_the_center()
+    {
+        let cases =
+        [
+            (Anchor::TopLeft, (-0.5, -0.5)),
+            (Anchor::TopCenter, (0.0, -0.5)),
+            (Anchor::TopRight, (0.5, -0.5)),
+            (Anchor::CenterLeft, (-0.5, 0.0)),
+            (Anchor::Center, (0.0, 0.0)),
+            (Anchor::CenterRight, (0.5, 0.0)),
+            (Anchor::BottomLeft, (-0.5, 0.5)),
+            (Anchor::BottomCenter, (0.0, 0.5)),
+            (Anchor::BottomRight, (0.5, 0.5)),
+            (Anchor::Normalized(0.25, 1.0), (-0.25, 0.5)),
+            (Anchor::Pixels(8.0, 32.0), (-0.25, 0.0))
+        ];
+
+        for (anchor, pivot) in cases
+        {
+            assert_eq!(anchor.pivot((32.0, 64.0)), pivot, "{anchor:?}");
+        }
+    }
+
+    #[test]
+    fn every_space_maps_to_clip_space()
+    {
+        // Centered at 50,50 with a size of 10, top right corner of the quad at 55,45 (y down)
+        let at = |space| Transform2D::new((50.0, 50.0), 0.0, (10.0, 10.0), space);
+        let letterbox = Viewport::new((400.0, 200.0), (100.0, 100.0), ScalingPolicy::Letterbox);
+        let stretch = Viewport::new((400.0, 200.0), (100.0, 200.0), ScalingPolicy::Stretch);
+        let legacy = Viewport::new((400.0, 200.0), (100.0, 200.0), ScalingPolicy::Legacy);
+
+        // Space, viewport, drawn with a camera, clip position of the center and of the top right corner
+        let cases =
+        [
+            (CoordinateSpace::Screen, letterbox, false, (-0.75, 0.5), (-0.725, 0.55)),
+            (CoordinateSpace::Screen, letterbox, true, (-0.75, 0.5), (-0.725, 0.55)),
+            (CoordinateSpace::Virtual, letterbox, false, (0.0, 0.0), (0.05, 0.1)),
+            (CoordinateSpace::Virtual, letterbox, true, (0.0, 0.0), (0.05, 0.1)),
+            (CoordinateSpace::World, letterbox, false, (0.0, 0.0), (0.05, 0.1)),
+            // The camera projects, the transform stays in world units
+            (CoordinateSpace::World, letterbox, true, (50.0, 50.0), (55.0, 45.0)),
+            (CoordinateSpace::Virtual, stretch, false, (0.0, 0.5), (0.1, 0.55)),
+            // Legacy stretches the position but scales the size with the height only
+            (CoordinateSpace::Virtual, legacy, false, (0.0, 0.5), (0.025, 0.55)),
+            (CoordinateSpace::World, legacy, false, (0.0, 0.5), (0.025, 0.55))
+        ];
+
+        for (space, viewport, camera, center, corner) in cases
+        {
+            let m = to_clip(&at(space), &viewport, camera, false);
+            let case = format!("{space:?} {:?} camera {camera}", viewport.policy);
+            assert_near(clip(m, (0.0, 0.0)), center, &case);
+            assert_near(clip(m, (0.5, 0.5)), corner, &case);
+        }
+    }
+
+    #[test]
+    fn snapping_moves_the_top_left_corner_onto_a_pixel()
+    {
+        let viewport = Viewport::new((400.0, 200.0), (400.0, 200.0), ScalingPolicy::Stretch);
+        let transform = Transform2D::new((50.3, 49.6), 0.0, (10.0, 10.0), CoordinateSpace::Screen);
+
+        // Top left corner at 45,45 instead of 45.3,44.6
+        assert_near(clip(to_clip(&transform, &viewport, false, true), (-0.5, 0.5)), (-0.775, 0.55), "snapped");
+        assert_near(clip(to_clip(&transform, &viewport, false, false), (-0.5, 0.5)), (-0.7735, 0.554), "unsnapped");
+    }
+}
@@ -0,0 +1,459 @@
+use std::{any::Any, collections::HashMap, f32::consts::PI};
+
+use crate::transform::Transform2D;
+
+// Maps the progress of a tween (0..1) to the progress of the value, most curves stay in 0..1, elastic and back overshoot
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Ease
+{
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    CubicBezier(f32, f32, f32, f32) // Control points x1, y1, x2, y2 like in css, x has to be in 0..1
+}
+
+impl Ease
+{
+    pub fn apply(&self, t: f32) -> f32
+    {
+        let t = t.clamp(0.0, 1.0);
+        // Overshoot of back
+        let c1 = 1.70158;
+        let c2 = c1 * 1.525;
+
+        match *self
+        {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::QuadInOut => if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 },
+            Ease::CubicIn => t * t * t,
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::CubicInOut => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 },
+            Ease::ElasticIn => if t == 0.0 || t == 1.0 { t } else { -(2f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin() },
+            Ease::ElasticOut => if t == 0.0 || t == 1.0 { t } else { 2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0 },
+            Ease::ElasticInOut =>
+            {
+                let c5 = 2.0 * PI / 4.5;
+                if t == 0.0 || t == 1.0 { t }
+                else if t < 0.5 { -(2f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * c5).sin()) / 2.0 }
+                else { 2f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * c5).sin() / 2.0 + 1.0 }
+            }
+            Ease::BackIn => (c1 + 1.0) * t * t * t - c1 * t * t,
+            Ease::BackOut => 1.0 + (c1 + 1.0) * (t - 1.0).powi(3) + c1 * (t - 1.0).powi(2),
+            Ease::BackInOut =>
+            {
+                if t < 0.5 { (2.0 * t).powi(2) * ((c2 + 1.0) * 2.0 * t - c2) / 2.0 }
+                else { ((2.0 * t - 2.0).powi(2) * ((c2 + 1.0) * (t * 2.0 - 2.0) + c2) + 2.0) / 2.0 }
+            }
+            Ease::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Ease::BounceOut => bounce_out(t),
+            Ease::BounceInOut => if t < 0.5 { (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0 } else { (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0 },
+            Ease::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t)
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32
+{
+    let n1 = 7.5625;
+    let d1 = 2.75;
+
+    if t < 1.0 / d1
+    {
+        n1 * t * t
+    }
+    else if t < 2.0 / d1
+    {
+        let t = t - 1.5 / d1;
+        n1 * t * t + 0.75
+    }
+    else if t < 2.5 / d1
+    {
+        let t = t - 2.25 / d1;
+        n1 * t * t + 0.9375
+    }
+    else
+    {
+        let t = t - 2.625 / d1;
+        n1 * t * t + 0.984375
+    }
+}
+
+// Curve from 0,0 to 1,1, finds the bezier parameter where x = t and returns y there
+pub(crate) fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, t: f32) -> f32
+{
+    let bezier = |a: f32, b: f32, s: f32| 3.0 * a * s * (1.0 - s) * (1.0 - s) + 3.0 * b * s * s * (1.0 - s) + s * s * s;
+    let derivative = |a: f32, b: f32, s: f32| 3.0 * a * (1.0 - s) * (1.0 - s) + 6.0 * (b - a) * s * (1.0 - s) + 3.0 * (1.0 - b) * s * s;
+
+    // Newton first, it is fast, but can fail on flat parts of the curve, then bisection
+    let mut s = t;
+    for _ in 0..8
+    {
+        let error = bezier(x1, x2, s) - t;
+        if error.abs() < 1e-6
+        {
+            return bezier(y1, y2, s);
+        }
+        let slope = derivative(x1, x2, s);
+        if slope.abs() < 1e-6
+        {
+            break;
+        }
+        s -= error / slope;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = t;
+    for _ in 0..32
+    {
+        if bezier(x1, x2, s) < t { low = s } else { high = s }
+        s = (low + high) / 2.0;
+    }
+    bezier(y1, y2, s)
+}
+
+// Everything that can be tweened, t is the eased progress and can be outside of 0..1
+pub trait Lerp: Clone
+{
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32
+{
+    fn lerp(&self, to: &Self, t: f32) -> Self
+    {
+        self + (to - self) * t
+    }
+}
+
+// Positions and sizes
+impl Lerp for (f32, f32)
+{
+    fn lerp(&self, to: &Self, t: f32) -> Self
+    {
+        (self.0.lerp(&to.0, t), self.1.lerp(&to.1, t))
+    }
+}
+
+// Point lists of the same length (shape morphs), point by point
+impl Lerp for Vec<(f32, f32)>
+{
+    fn lerp(&self, to: &Self, t: f32) -> Self
+    {
+        self.iter().zip(to).map(|(a, b)| a.lerp(b, t)).collect()
+    }
+}
+
+impl<const N: usize> Lerp for [f32; N]
+{
+    fn lerp(&self, to: &Self, t: f32) -> Self
+    {
+        std::array::from_fn(|i| self[i].lerp(&to[i], t))
+    }
+}
+
+// Space and parent are taken from the start
+impl Lerp for Transform2D
+{
+    fn lerp(&self, to: &Self, t: f32) -> Self
+    {
+        Transform2D
+        {
+            translation: self.translation.lerp(&to.translation, t),
+            rotation: self.rotation.lerp(&to.rotation, t),
+            scale: self.scale.lerp(&to.scale, t),
+            pivot: self.pivot.lerp(&to.pivot, t),
+            skew: self.skew.lerp(&to.skew, t),
+            ..*self
+        }
+    }
+}
+
+// Used with with_repeat to play a tween until it gets removed
+pub const REPEAT_FOREVER: u32 = u32::MAX;
+
+// Goes from one value to another over duration seconds, can be used on its own or through a TweenManager
+pub struct Tween<T: Lerp>
+{
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub ease: Ease,
+    pub delay: f32, // Only before the first play
+    pub repeat: u32, // Plays after the first one
+    pub yoyo: bool, // Every second play goes backwards
+    elapsed: f32,
+    finished: bool,
+    on_complete: Option<Box<dyn FnMut()>>
+}
+
+impl<T: Lerp> Tween<T>
+{
+    pub fn new(from: T, to: T, duration: f32) -> Self
+    {
+        Self
+        {
+            from,
+            to,
+            duration,
+            ease: Ease::Linear,
+            delay: 0.0,
+            repeat: 0,
+            yoyo: false,
+            elapsed: 0.0,
+            finished: false,
+            on_complete: None
+        }
+    }
+
+    pub fn with_ease(mut self, ease: Ease) -> Self
+    {
+        self.ease = ease;
+        self
+    }
+
+    pub fn with_delay(mut self, delay: f32) -> Self
+    {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_repeat(mut self, repeat: u32) -> Self
+    {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn with_yoyo(mut self, yoyo: bool) -> Self
+    {
+        self.yoyo = yoyo;
+        self
+    }
+
+    // Called once when the last play ends, never for REPEAT_FOREVER
+    pub fn on_complete(mut self, callback: impl FnMut() + 'static) -> Self
+    {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    pub fn update(&mut self, dt: f64)
+    {
+        if self.finished
+        {
+            return;
+        }
+
+        self.elapsed += dt as f32;
+
+        let active = self.elapsed - self.delay;
+        let plays = if self.duration > 0.0 { active / self.duration } else if active >= 0.0 { f32::INFINITY } else { 0.0 };
+        if self.repeat != REPEAT_FOREVER && plays >= self.repeat as f32 + 1.0
+        {
+            self.finished = true;
+            if let Some(callback) = &mut self.on_complete
+            {
+                callback();
+            }
+        }
+    }
+
+    pub fn is_finished(&self) -> bool
+    {
+        self.finished
+    }
+
+    // Back to the start, including the delay
+    pub fn restart(&mut self)
+    {
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+
+    pub fn value(&self) -> T
+    {
+        let (_, t) = self.play();
+        let t = if self.is_reversed() { 1.0 - t } else { t };
+        self.from.lerp(&self.to, self.ease.apply(t))
+    }
+
+    // Going back from to to from, every second play with yoyo
+    pub fn is_reversed(&self) -> bool
+    {
+        self.yoyo && self.play().0 % 2 == 1
+    }
+
+    // Index of the current play and how far into it
+    fn play(&self) -> (u32, f32)
+    {
+        if self.finished
+        {
+            (self.repeat, 1.0)
+        }
+        else if self.duration > 0.0
+        {
+            let plays = (self.elapsed - self.delay).max(0.0) / self.duration;
+            (plays as u32, plays.fract())
+        }
+        else
+        {
+            (0, 0.0)
+        }
+    }
+}
+
+// Type erased tween for the manager
+trait AnyTween
+{
+    fn update(&mut self, dt: f64);
+    fn is_finished(&self) -> bool;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Lerp + 'static> AnyTween for Tween<T>
+{
+    fn update(&mut self, dt: f64)
+    {
+        Tween::update(self, dt);
+    }
+
+    fn is_finished(&self) -> bool
+    {
+        Tween::is_finished(self)
+    }
+
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+}
+
+// Tweens of any value type, owned by the game and returned from EngineEvent::tweens, so the game loop updates it before every update
+// Finished tweens keep their last value until they get removed
+#[derive(Default)]
+pub struct TweenManager
+{
+    tweens: HashMap<usize, Box<dyn AnyTween>>,
+    next_id: usize
+}
+
+impl TweenManager
+{
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    // Returns the id to read the value with
+    pub fn add<T: Lerp + 'static>(&mut self, tween: Tween<T>) -> usize
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.tweens.insert(id, Box::new(tween));
+        id
+    }
+
+    // None if there is no tween with that id or it tweens another type
+    pub fn value<T: Lerp + 'static>(&self, id: usize) -> Option<T>
+    {
+        self.tweens.get(&id)?.as_any().downcast_ref::<Tween<T>>().map(|tween| tween.value())
+    }
+
+    pub fn get_mut<T: Lerp + 'static>(&mut self, id: usize) -> Option<&mut Tween<T>>
+    {
+        self.tweens.get_mut(&id)?.as_any_mut().downcast_mut::<Tween<T>>()
+    }
+
+    pub fn is_finished(&self, id: usize) -> bool
+    {
+        self.tweens.get(&id).is_none_or(|tween| tween.is_finished())
+    }
+
+    pub fn remove(&mut self, id: usize)
+    {
+        self.tweens.remove(&id);
+    }
+
+    pub fn clear_finished(&mut self)
+    {
+        self.tweens.retain(|_, tween| !tween.is_finished());
+    }
+
+    pub fn update(&mut self, dt: f64)
+    {
+        for tween in self.tweens.values_mut()
+        {
+            tween.update(dt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn cubic_bezier_keeps_the_endpoints()
+    {
+        for (x1, y1, x2, y2) in [(0.25, 0.1, 0.25, 1.0), (0.0, 0.0, 1.0, 1.0), (0.7, -0.5, 0.3, 1.5)]
+        {
+            assert!(cubic_bezier(x1, y1, x2, y2, 0.0).abs() < 1e-4);
+            assert!((cubic_bezier(x1, y1, x2, y2, 1.0) - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn cubic_bezier_with_linear_handles_is_linear()
+    {
+        for i in 0..=10
+        {
+            let t = i as f32 / 10.0;
+            assert!((cubic_bezier(1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, t) - t).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn cubic_bezier_handles_flat_curves()
+    {
+        // The x derivative is 0 at both ends, Newton gives up there and bisection has to find it
+        let ease_in_out = |t| cubic_bezier(1.0, 0.0, 0.0, 1.0, t);
+        assert!((ease_in_out(0.5) - 0.5).abs() < 1e-4);
+        assert!(ease_in_out(0.1) < 0.1);
+        assert!(ease_in_out(0.9) > 0.9);
+    }
+
+    #[test]
+    fn yoyo_plays_back_and_finishes_at_the_end()
+    {
+        let mut tween = Tween::new(0.0, 10.0, 1.0).with_repeat(1).with_yoyo(true);
+        tween.update(0.25);
+        assert_eq!(tween.value(), 2.5);
+        tween.update(1.0);
+        assert_eq!(tween.value(), 7.5);
+        tween.update(1.0);
+        assert!(tween.is_finished());
+        assert_eq!(tween.value(), 0.0);
+    }
+}
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use crate::pipeline::{BlendMode, PipelineKey};
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex
@@ -55,7 +57,8 @@ pub enum DrawType
 pub enum MaterialType
 {
     Texture(Arc<wgpu::BindGroup>),
-    Color([f32; 4])
+    Color([f32; 4]),
+    Custom(Option<Arc<wgpu::BindGroup>>) // First texture of the material, bound to group 0 as well
 }
 
 // #[derive(Copy, Clone)]
@@ -65,7 +68,30 @@ pub struct DrawCommand
     pub transform: [[f32; 4]; 4], // 4x4 model matrix
     // pub kind: DrawType,
     pub z_index: u32,
-    pub material: Arc<Material>
+    pub material: Arc<Material>,
+    pub target: Option<RenderTargetId>, // None = screen
+    pub blend: BlendMode,
+    pub camera: usize, // Camera of the frame, 0 = no camera, transform is already in clip space
+    pub uv_min: [f32; 2], // Part of the texture on the mesh, the tex_coords of the mesh go from uv_min to uv_max
+    pub uv_max: [f32; 2],
+    pub tint: [f32; 4], // Multiplies the color or texture, white = unchanged
+    pub indices: std::ops::Range<u32>, // Part of the mesh this draw uses, a dynamic mesh can hold several shapes in one frame
+    pub base_vertex: u32
+}
+
+impl DrawCommand
+{
+    pub fn pipeline_key(&self) -> PipelineKey
+    {
+        PipelineKey { blend: self.blend, ..self.material.pipeline_key() }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RenderTargetId
+{
+    pub index: usize,
+    pub texture_id: usize // Use this with draw_texture to draw the result
 }
 
 #[repr(C)]
@@ -74,7 +100,7 @@ pub struct InstanceData
 {
     pub model: [[f32; 4]; 4],
     pub color: [f32; 4],
-    pub mode: u32, //0 = color, 1 = texture
+    pub mode: u32, //0 = color, 1 = texture, 2 = custom material, plus PREMULTIPLY_MODE for multiply and screen blending
     pub uv_min: [f32; 2],
     pub uv_max: [f32; 2]
 }
@@ -148,7 +174,10 @@ pub struct Mesh
 {
     pub vertex_buf: wgpu::Buffer,
     pub index_buf: wgpu::Buffer,
-    pub index_count: u32
+    pub index_count: u32,
+    // Where the geometry set last with set_mesh starts in the buffers, 0 for meshes that are not dynamic
+    pub first_index: u32,
+    pub base_vertex: u32
 }
 
 pub enum MeshID
@@ -160,9 +189,20 @@ pub enum MeshID
 
 pub struct Material
 {
-    // pub shader: Arc<Shader>, // Will do it later
+    pub shader: usize, // Index of the shader in the renderer, 0 is the default shader
     // pub texture: Option<Arc<Texture>>
-    pub kind: MaterialType
+    pub kind: MaterialType,
+    pub blend: BlendMode, // Default for draws with this material
+    pub topology: wgpu::PrimitiveTopology,
+    pub resources: Option<MaterialResources>
+}
+
+// Group 2 of custom materials
+pub struct MaterialResources
+{
+    pub bind_group: wgpu::BindGroup,
+    pub uniform_buf: wgpu::Buffer,
+    pub textures: Vec<usize> // Texture ids in binding order
 }
 
 impl Material
@@ -180,7 +220,11 @@ impl Material
     {
         Material 
         {
-            kind: MaterialType::Color(color)
+            shader: 0,
+            kind: MaterialType::Color(color),
+            blend: BlendMode::Alpha,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            resources: None
         }
     }
 
@@ -188,7 +232,34 @@ impl Material
     {
         Material 
         { 
-            kind: MaterialType::Texture(texture)
+            shader: 0,
+            kind: MaterialType::Texture(texture),
+            blend: BlendMode::Alpha,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            resources: None
+        }
+    }
+
+    pub fn custom(shader: usize, texture: Option<Arc<wgpu::BindGroup>>, resources: MaterialResources) -> Self
+    {
+        Material
+        {
+            shader,
+            kind: MaterialType::Custom(texture),
+            blend: BlendMode::Alpha,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            resources: Some(resources)
+        }
+    }
+
+    pub fn pipeline_key(&self) -> PipelineKey
+    {
+        PipelineKey
+        {
+            shader: self.shader,
+            blend: self.blend,
+            topology: self.topology,
+            texture_count: self.resources.as_ref().map(|resources| resources.textures.len())
         }
     }
 }
\ No newline at end of file
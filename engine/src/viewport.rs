@@ -0,0 +1,161 @@
+// How virtual_size (the design resolution) gets fitted into the window
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ScalingPolicy
+{
+    // How matrix and texture_matrix worked before the other policies: positions stretch with the window, sizes scale with its height
+    // Draws with a camera get stretched like Stretch
+    #[default]
+    Legacy,
+    Stretch, // Fills the window, distorts if the aspect ratio differs
+    Letterbox, // Keeps the aspect ratio, black bars on the sides that do not fit
+    Crop, // Keeps the aspect ratio and fills the window, cuts off what does not fit
+    Expand, // Like Letterbox, but shows more of the world instead of bars
+    Integer // Like Letterbox, but only whole number scales, for pixel art
+}
+
+// Mapping between virtual and window pixels, used for rendering and Input::mouse_position
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Viewport
+{
+    pub window_size: (f32, f32),
+    pub virtual_size: (f32, f32),
+    pub policy: ScalingPolicy
+}
+
+impl Viewport
+{
+    pub fn new(window_size: (f32, f32), virtual_size: (f32, f32), policy: ScalingPolicy) -> Self
+    {
+        Self { window_size, virtual_size, policy }
+    }
+
+    // Window pixels per virtual pixel, for Legacy only of positions (sizes use the y scale)
+    pub fn scale(&self) -> (f32, f32)
+    {
+        let fit = (self.window_size.0 / self.virtual_size.0, self.window_size.1 / self.virtual_size.1);
+
+        match self.policy
+        {
+            ScalingPolicy::Legacy | ScalingPolicy::Stretch => fit,
+            ScalingPolicy::Letterbox | ScalingPolicy::Expand =>
+            {
+                let scale = fit.0.min(fit.1);
+                (scale, scale)
+            }
+            ScalingPolicy::Crop =>
+            {
+                let scale = fit.0.max(fit.1);
+                (scale, scale)
+            }
+            ScalingPolicy::Integer =>
+            {
+                let scale = fit.0.min(fit.1).floor().max(1.0);
+                (scale, scale)
+            }
+        }
+    }
+
+    // Window position of virtual 0,0, the virtual area is always centered
+    pub fn offset(&self) -> (f32, f32)
+    {
+        let scale = self.scale();
+        let offset = ((self.window_size.0 - self.virtual_size.0 * scale.0) / 2.0, (self.window_size.1 - self.virtual_size.1 * scale.1) / 2.0);
+
+        // Whole pixels, so integer scaling stays crisp
+        match self.policy
+        {
+            ScalingPolicy::Integer => (offset.0.floor(), offset.1.floor()),
+            _ => offset
+        }
+    }
+
+    pub fn virtual_to_window(&self, pos: (f32, f32)) -> (f32, f32)
+    {
+        let (scale, offset) = (self.scale(), self.offset());
+        (pos.0 * scale.0 + offset.0, pos.1 * scale.1 + offset.1)
+    }
+
+    pub fn window_to_virtual(&self, pos: (f32, f32)) -> (f32, f32)
+    {
+        let (scale, offset) = (self.scale(), self.offset());
+        ((pos.0 - offset.0) / scale.0, (pos.1 - offset.1) / scale.1)
+    }
+
+    // Part of the window (x, y, width, height) that shows the virtual area, everything else are bars
+    // None if there are no bars
+    pub fn bars_rect(&self) -> Option<(u32, u32, u32, u32)>
+    {
+        match self.policy
+        {
+            ScalingPolicy::Letterbox | ScalingPolicy::Integer =>
+            {
+                let (scale, offset) = (self.scale(), self.offset());
+                let x = offset.0.max(0.0).round() as u32;
+                let y = offset.1.max(0.0).round() as u32;
+                let width = ((self.virtual_size.0 * scale.0).round() as u32).min(self.window_size.0 as u32 - x.min(self.window_size.0 as u32));
+                let height = ((self.virtual_size.1 * scale.1).round() as u32).min(self.window_size.1 as u32 - y.min(self.window_size.1 as u32));
+                Some((x, y, width, height))
+            }
+            _ => None
+        }
+    }
+
+    // Virtual area (x, y, width, height) that is visible in the window, bigger than virtual_size for Expand, smaller for Crop
+    // For Letterbox and Integer everything outside of virtual_size is covered by the bars
+    pub fn visible_rect(&self) -> (f32, f32, f32, f32)
+    {
+        let top_left = self.window_to_virtual((0.0, 0.0));
+        let bottom_right = self.window_to_virtual(self.window_size);
+        (top_left.0, top_left.1, bottom_right.0 - top_left.0, bottom_right.1 - top_left.1)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn every_policy_scales_and_centers_the_virtual_area()
+    {
+        // Policy, window size, scale, offset, virtual position of the top left window corner
+        let cases =
+        [
+            (ScalingPolicy::Legacy, (400.0, 200.0), (4.0, 2.0), (0.0, 0.0), (0.0, 0.0)),
+            (ScalingPolicy::Stretch, (400.0, 200.0), (4.0, 2.0), (0.0, 0.0), (0.0, 0.0)),
+            (ScalingPolicy::Letterbox, (400.0, 200.0), (2.0, 2.0), (100.0, 0.0), (-50.0, 0.0)),
+            (ScalingPolicy::Expand, (400.0, 200.0), (2.0, 2.0), (100.0, 0.0), (-50.0, 0.0)),
+            (ScalingPolicy::Crop, (400.0, 200.0), (4.0, 4.0), (0.0, -100.0), (0.0, 25.0)),
+            (ScalingPolicy::Integer, (351.0, 250.0), (2.0, 2.0), (75.0, 25.0), (-37.5, -12.5)),
+            (ScalingPolicy::Integer, (50.0, 50.0), (1.0, 1.0), (-25.0, -25.0), (25.0, 25.0))
+        ];
+
+        for (policy, window, scale, offset, top_left) in cases
+        {
+            let viewport = Viewport::new(window, (100.0, 100.0), policy);
+            assert_eq!(viewport.scale(), scale, "{policy:?} {window:?}");
+            assert_eq!(viewport.offset(), offset, "{policy:?} {window:?}");
+            assert_eq!(viewport.window_to_virtual((0.0, 0.0)), top_left, "{policy:?} {window:?}");
+            assert_eq!(viewport.virtual_to_window(viewport.window_to_virtual((120.0, 40.0))), (120.0, 40.0), "{policy:?} {window:?}");
+        }
+    }
+
+    #[test]
+    fn only_letterbox_and_integer_have_bars()
+    {
+        let bars = |policy| Viewport::new((400.0, 200.0), (100.0, 100.0), policy).bars_rect();
+        assert_eq!(bars(ScalingPolicy::Letterbox), Some((100, 0, 200, 200)));
+        assert_eq!(bars(ScalingPolicy::Integer), Some((100, 0, 200, 200)));
+        assert_eq!(bars(ScalingPolicy::Expand), None);
+        assert_eq!(bars(ScalingPolicy::Crop), None);
+    }
+
+    #[test]
+    fn expand_shows_more_and_crop_less_of_the_virtual_area()
+    {
+        let visible = |policy| Viewport::new((400.0, 200.0), (100.0, 100.0), policy).visible_rect();
+        assert_eq!(visible(ScalingPolicy::Expand), (-50.0, 0.0, 200.0, 100.0));
+        assert_eq!(visible(ScalingPolicy::Crop), (0.0, 25.0, 100.0, 50.0));
+        assert_eq!(visible(ScalingPolicy::Stretch), (0.0, 0.0, 100.0, 100.0));
+    }
+}